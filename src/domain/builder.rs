@@ -0,0 +1,347 @@
+//! Fluent construction of `Flight` and `Gate` with defaults for everything optional.
+
+use chrono::{DateTime, NaiveTime, Utc};
+
+use super::*;
+
+/// Builds a `Flight`; start from `Flight::builder()`.  Flight id, airline, origin, destination,
+/// aircraft type and both scheduled times are required.  Everything else defaults to a
+/// scheduled passenger flight with no codeshares, zone, registration, load or wingspan.
+#[derive(Debug, Clone, Default)]
+pub struct FlightBuilder {
+    flight_id: String,
+    airline: String,
+    codeshare_airlines: Vec<String>,
+    origin: String,
+    destination: String,
+    aircraft_type: String,
+    scheduled_arrival: Option<DateTime<Utc>>,
+    scheduled_departure: Option<DateTime<Utc>>,
+    status: Option<FlightStatus>,
+    requires_double_stand: bool,
+    baggage_zone: Option<String>,
+    registration: Option<String>,
+    passenger_count: Option<u32>,
+    is_ferry: bool,
+    wingspan_m: Option<f64>,
+}
+
+impl Flight {
+    pub fn builder() -> FlightBuilder {
+        FlightBuilder::default()
+    }
+}
+
+impl FlightBuilder {
+    pub fn flight_id(mut self, flight_id: impl Into<String>) -> Self {
+        self.flight_id = flight_id.into();
+        self
+    }
+
+    pub fn airline(mut self, airline: impl Into<String>) -> Self {
+        self.airline = airline.into();
+        self
+    }
+
+    /// Add a marketing partner.
+    pub fn codeshare(mut self, airline: impl Into<String>) -> Self {
+        self.codeshare_airlines.push(airline.into());
+        self
+    }
+
+    pub fn origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = origin.into();
+        self
+    }
+
+    pub fn destination(mut self, destination: impl Into<String>) -> Self {
+        self.destination = destination.into();
+        self
+    }
+
+    pub fn aircraft_type(mut self, aircraft_type: impl Into<String>) -> Self {
+        self.aircraft_type = aircraft_type.into();
+        self
+    }
+
+    pub fn scheduled_arrival(mut self, at: DateTime<Utc>) -> Self {
+        self.scheduled_arrival = Some(at);
+        self
+    }
+
+    pub fn scheduled_departure(mut self, at: DateTime<Utc>) -> Self {
+        self.scheduled_departure = Some(at);
+        self
+    }
+
+    pub fn status(mut self, status: FlightStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn requires_double_stand(mut self, required: bool) -> Self {
+        self.requires_double_stand = required;
+        self
+    }
+
+    pub fn baggage_zone(mut self, zone: impl Into<String>) -> Self {
+        self.baggage_zone = Some(zone.into());
+        self
+    }
+
+    pub fn registration(mut self, registration: impl Into<String>) -> Self {
+        self.registration = Some(registration.into());
+        self
+    }
+
+    pub fn passenger_count(mut self, passengers: u32) -> Self {
+        self.passenger_count = Some(passengers);
+        self
+    }
+
+    pub fn ferry(mut self, is_ferry: bool) -> Self {
+        self.is_ferry = is_ferry;
+        self
+    }
+
+    pub fn wingspan_m(mut self, wingspan_m: f64) -> Self {
+        self.wingspan_m = Some(wingspan_m);
+        self
+    }
+
+    /// The flight, or an error naming every missing field, or a departure not after arrival.
+    pub fn build(self) -> Result<Flight, String> {
+        let missing: Vec<&str> = [
+            ("flight_id", self.flight_id.is_empty()),
+            ("airline", self.airline.is_empty()),
+            ("origin", self.origin.is_empty()),
+            ("destination", self.destination.is_empty()),
+            ("aircraft_type", self.aircraft_type.is_empty()),
+            ("scheduled_arrival", self.scheduled_arrival.is_none()),
+            ("scheduled_departure", self.scheduled_departure.is_none()),
+        ]
+        .into_iter()
+        .filter(|&(_, absent)| absent)
+        .map(|(field, _)| field)
+        .collect();
+        let (Some(scheduled_arrival), Some(scheduled_departure), true) = (
+            self.scheduled_arrival,
+            self.scheduled_departure,
+            missing.is_empty(),
+        ) else {
+            return Err(format!("Flight missing {}", missing.join(", ")));
+        };
+        if scheduled_departure <= scheduled_arrival {
+            return Err(format!(
+                "Flight {} departs before it arrives",
+                self.flight_id
+            ));
+        }
+        Ok(Flight {
+            flight_id: self.flight_id,
+            airline: self.airline,
+            codeshare_airlines: self.codeshare_airlines,
+            origin: self.origin,
+            destination: self.destination,
+            aircraft_type: self.aircraft_type,
+            scheduled_arrival,
+            scheduled_departure,
+            status: self.status.unwrap_or(FlightStatus::Scheduled),
+            requires_double_stand: self.requires_double_stand,
+            baggage_zone: self.baggage_zone,
+            registration: self.registration,
+            passenger_count: self.passenger_count,
+            is_ferry: self.is_ferry,
+            wingspan_m: self.wingspan_m,
+        })
+    }
+}
+
+/// Builds a `Gate`; start from `Gate::builder()`.  Gate id, terminal and size are required.
+/// Everything else defaults to an in-service swing contact gate with jet bridges on both sides,
+/// no closures, type lists, occupancy limits or staffing shifts.
+#[derive(Debug, Clone)]
+pub struct GateBuilder {
+    gate: Gate,
+    size: Option<AircraftSize>,
+}
+
+impl Gate {
+    pub fn builder() -> GateBuilder {
+        GateBuilder {
+            gate: Gate {
+                gate_id: String::new(),
+                terminal: String::new(),
+                size: AircraftSize::Small,
+                is_available: true,
+                gate_class: GateClass::Swing,
+                unavailable_windows: Vec::new(),
+                permitted_types: Vec::new(),
+                excluded_types: Vec::new(),
+                mars: false,
+                favours: None,
+                baggage_zone: None,
+                min_occupancy_minutes: None,
+                max_occupancy_minutes: None,
+                remote: false,
+                jetbridge_config: JetbridgeConfig::Both,
+                staffed_shifts: Vec::new(),
+            },
+            size: None,
+        }
+    }
+}
+
+impl GateBuilder {
+    pub fn gate_id(mut self, gate_id: impl Into<String>) -> Self {
+        self.gate.gate_id = gate_id.into();
+        self
+    }
+
+    pub fn terminal(mut self, terminal: impl Into<String>) -> Self {
+        self.gate.terminal = terminal.into();
+        self
+    }
+
+    pub fn size(mut self, size: AircraftSize) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn available(mut self, is_available: bool) -> Self {
+        self.gate.is_available = is_available;
+        self
+    }
+
+    pub fn gate_class(mut self, class: GateClass) -> Self {
+        self.gate.gate_class = class;
+        self
+    }
+
+    /// Add a planned closure over `[from, until)`.
+    pub fn closure(mut self, from: DateTime<Utc>, until: DateTime<Utc>) -> Self {
+        self.gate.unavailable_windows.push((from, until));
+        self
+    }
+
+    /// Designate `aircraft_type` to this gate.
+    pub fn permit_type(mut self, aircraft_type: impl Into<String>) -> Self {
+        self.gate.permitted_types.push(aircraft_type.into());
+        self
+    }
+
+    pub fn exclude_type(mut self, aircraft_type: impl Into<String>) -> Self {
+        self.gate.excluded_types.push(aircraft_type.into());
+        self
+    }
+
+    pub fn mars(mut self, mars: bool) -> Self {
+        self.gate.mars = mars;
+        self
+    }
+
+    pub fn favours(mut self, role: FlightRole) -> Self {
+        self.gate.favours = Some(role);
+        self
+    }
+
+    pub fn baggage_zone(mut self, zone: impl Into<String>) -> Self {
+        self.gate.baggage_zone = Some(zone.into());
+        self
+    }
+
+    pub fn occupancy_minutes(mut self, min: Option<i64>, max: Option<i64>) -> Self {
+        self.gate.min_occupancy_minutes = min;
+        self.gate.max_occupancy_minutes = max;
+        self
+    }
+
+    pub fn remote(mut self, remote: bool) -> Self {
+        self.gate.remote = remote;
+        self
+    }
+
+    pub fn jetbridge_config(mut self, config: JetbridgeConfig) -> Self {
+        self.gate.jetbridge_config = config;
+        self
+    }
+
+    /// Add a daily crew shift.
+    pub fn staffed_shift(mut self, start: NaiveTime, end: NaiveTime) -> Self {
+        self.gate.staffed_shifts.push(StaffingShift { start, end });
+        self
+    }
+
+    /// The gate, or an error naming every missing field, or a closure not ending after it starts.
+    pub fn build(self) -> Result<Gate, String> {
+        let missing: Vec<&str> = [
+            ("gate_id", self.gate.gate_id.is_empty()),
+            ("terminal", self.gate.terminal.is_empty()),
+            ("size", self.size.is_none()),
+        ]
+        .into_iter()
+        .filter(|&(_, absent)| absent)
+        .map(|(field, _)| field)
+        .collect();
+        let (Some(size), true) = (self.size, missing.is_empty()) else {
+            return Err(format!("Gate missing {}", missing.join(", ")));
+        };
+        if self
+            .gate
+            .unavailable_windows
+            .iter()
+            .any(|(from, until)| until <= from)
+        {
+            return Err(format!(
+                "Gate {} closure must end after it starts",
+                self.gate.gate_id
+            ));
+        }
+        Ok(Gate { size, ..self.gate })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn builders_fill_defaults_and_check_required_fields() {
+        let at = |h| Utc.with_ymd_and_hms(2026, 3, 1, h, 0, 0).unwrap();
+        let flight = Flight::builder()
+            .flight_id("BA-117")
+            .airline("British Airways")
+            .origin("JFK")
+            .destination("LHR")
+            .aircraft_type("B777")
+            .scheduled_arrival(at(6))
+            .scheduled_departure(at(9))
+            .build()
+            .unwrap();
+        assert_eq!(flight.status, FlightStatus::Scheduled);
+        assert!(flight.codeshare_airlines.is_empty());
+        assert_eq!((flight.registration, flight.passenger_count), (None, None));
+        assert!(!flight.is_ferry && !flight.requires_double_stand);
+
+        let gate = Gate::builder()
+            .gate_id("T5-A1")
+            .terminal("T5")
+            .size(AircraftSize::Large)
+            .build()
+            .unwrap();
+        assert!(gate.is_available && !gate.remote && !gate.mars);
+        assert_eq!(gate.gate_class, GateClass::Swing);
+        assert_eq!(gate.jetbridge_config, JetbridgeConfig::Both);
+
+        assert_eq!(
+            Flight::builder().flight_id("BA-1").airline("BA").build(),
+            Err("Flight missing origin, destination, aircraft_type, scheduled_arrival, scheduled_departure".to_string())
+        );
+        assert_eq!(
+            Gate::builder().terminal("T5").build(),
+            Err("Gate missing gate_id, size".to_string())
+        );
+    }
+}
@@ -1,15 +1,45 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub use builder::{FlightBuilder, GateBuilder};
+
+mod builder;
+
+/// Gate and aircraft size class.  Sizes are ordered by `rank`, not by declaration order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum AircraftSize {
     Small,
     Medium,
     Large,
 }
 
+impl AircraftSize {
+    /// Position in the size hierarchy: a gate takes any aircraft of equal or lower rank, and
+    /// each step between ranks is one size of over- or under-provision.
+    pub fn rank(&self) -> u8 {
+        match self {
+            Self::Small => 1,
+            Self::Medium => 2,
+            Self::Large => 3,
+        }
+    }
+}
+
+impl PartialOrd for AircraftSize {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AircraftSize {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
 impl fmt::Display for AircraftSize {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -24,6 +54,42 @@ impl fmt::Display for AircraftSize {
 const LARGE_AIRCRAFT_TYPES: &[&str] = &["A350", "A380", "B777", "B787", "B747", "A330", "A340"];
 /// Regional jets and turboprops that fit a Small gate.
 const SMALL_AIRCRAFT_TYPES: &[&str] = &["E190", "E195", "ATR72", "ATR42", "CRJ900", "CRJ700"];
+/// Narrow-bodies known to need a Medium gate.  Unlisted types also default to Medium, unless
+/// their wingspan says otherwise (see `Flight::aircraft_size`).
+const MEDIUM_AIRCRAFT_TYPES: &[&str] = &[
+    "A318", "A319", "A320", "A321", "A20N", "A21N", "B737", "B738", "B739", "B38M", "B39M", "B757",
+];
+
+/// Wingspans (metres) from which ICAO aerodrome reference code letters C (24 m) and D (36 m)
+/// begin.  Codes A-B fit a Small gate, C a Medium one, and D-F need a Large one.
+const ICAO_CODE_C_MIN_WINGSPAN_M: f64 = 24.0;
+const ICAO_CODE_D_MIN_WINGSPAN_M: f64 = 36.0;
+
+impl AircraftSize {
+    /// Size bucket for an aircraft with the given wingspan in metres, by ICAO aerodrome
+    /// reference code letter.
+    pub fn from_wingspan(wingspan_m: f64) -> Self {
+        if wingspan_m >= ICAO_CODE_D_MIN_WINGSPAN_M {
+            Self::Large
+        } else if wingspan_m >= ICAO_CODE_C_MIN_WINGSPAN_M {
+            Self::Medium
+        } else {
+            Self::Small
+        }
+    }
+}
+
+/// Whether `aircraft_type` is one of the designators `classify_aircraft` knows.
+pub fn is_known_aircraft(aircraft_type: &str) -> bool {
+    let t = aircraft_type.to_uppercase();
+    [
+        LARGE_AIRCRAFT_TYPES,
+        SMALL_AIRCRAFT_TYPES,
+        MEDIUM_AIRCRAFT_TYPES,
+    ]
+    .iter()
+    .any(|list| list.contains(&t.as_str()))
+}
 
 /// Map an ICAO/common aircraft designator to a gate-size category.
 /// The default returns `Medium`, which covers the most common unrecognised
@@ -41,7 +107,55 @@ pub fn classify_aircraft(aircraft_type: &str) -> AircraftSize {
     }
 }
 
+/// Types whose door layout needs the bridge on the right-hand side; all others board from the
+/// left.
+const RIGHT_DOOR_AIRCRAFT_TYPES: &[&str] = &["ATR72", "ATR42"];
+
+/// Side of the fuselage an aircraft is boarded from at a contact gate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum DoorSide {
+    Left,
+    Right,
+}
+
+/// Boarding door side for an aircraft designator.  To add a right-side type, append it to
+/// `RIGHT_DOOR_AIRCRAFT_TYPES`.
+pub fn door_config(aircraft_type: &str) -> DoorSide {
+    let t = aircraft_type.to_uppercase();
+    if RIGHT_DOOR_AIRCRAFT_TYPES.contains(&t.as_str()) {
+        DoorSide::Right
+    } else {
+        DoorSide::Left
+    }
+}
+
+/// IATA airport code to ISO 3166 country code for the airports the engine knows out of the box.
+/// Further airports can be registered at runtime via `AllocationEngine::register_airport`.
+pub const AIRPORT_COUNTRIES: &[(&str, &str)] = &[
+    ("LHR", "GB"),
+    ("LGW", "GB"),
+    ("STN", "GB"),
+    ("MAN", "GB"),
+    ("EDI", "GB"),
+    ("GLA", "GB"),
+    ("JFK", "US"),
+    ("EWR", "US"),
+    ("LAX", "US"),
+    ("ORD", "US"),
+    ("CDG", "FR"),
+    ("FRA", "DE"),
+    ("MUC", "DE"),
+    ("AMS", "NL"),
+    ("MAD", "ES"),
+    ("DUB", "IE"),
+    ("DXB", "AE"),
+    ("SIN", "SG"),
+    ("HKG", "HK"),
+];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum FlightStatus {
     Scheduled,
     Boarding,
@@ -53,27 +167,93 @@ pub enum FlightStatus {
     Diverted,
 }
 
+impl FlightStatus {
+    /// Statuses after which the flight will not use a gate here again.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Cancelled | Self::Diverted | Self::Departed)
+    }
+}
+
 impl fmt::Display for FlightStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self)
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Flight {
     pub flight_id: String,
+    /// Operating carrier; gate leases are looked up against this airline.
     pub airline: String,
+    /// Marketing partners selling seats on this flight under their own codes.
+    #[serde(default)]
+    pub codeshare_airlines: Vec<String>,
     pub origin: String,
     pub destination: String,
     pub aircraft_type: String,
     pub scheduled_arrival: DateTime<Utc>,
     pub scheduled_departure: DateTime<Utc>,
     pub status: FlightStatus,
+    /// The operation needs two adjacent gates held together (e.g. an oversized aircraft).
+    #[serde(default)]
+    pub requires_double_stand: bool,
+    /// Baggage hall / sort destination the flight's bags go through.
+    #[serde(default)]
+    pub baggage_zone: Option<String>,
+    /// Aircraft registration (tail number), when known; see `engine::validate_registrations`.
+    #[serde(default)]
+    pub registration: Option<String>,
+    /// Expected passengers; busier flights are steered to contact gates, lighter ones to remote
+    /// stands.
+    #[serde(default)]
+    pub passenger_count: Option<u32>,
+    /// Positioning flight without passengers: any stand will do, preferably a remote one, and
+    /// the turnaround buffer is shorter.
+    #[serde(default)]
+    pub is_ferry: bool,
+    /// Wingspan in metres, used to size the gate when the type designator is unknown.
+    #[serde(default)]
+    pub wingspan_m: Option<f64>,
+}
+
+/// Ground time up to which a visit counts as a quick turn dominated by boarding.
+pub const QUICK_TURN_MAX_MINUTES: i64 = 60;
+
+/// Which passenger flow dominates a flight's time at the gate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum FlightRole {
+    Arrival,
+    Departure,
 }
 
 impl Flight {
+    /// Half-open `[from, until)` window the flight holds a gate: arrival to departure plus
+    /// `buffer` for turnaround.
+    pub fn occupancy_window(&self, buffer: Duration) -> (DateTime<Utc>, DateTime<Utc>) {
+        (self.scheduled_arrival, self.scheduled_departure + buffer)
+    }
+
+    /// Gate size the aircraft needs: by type designator, or for an unknown type by wingspan
+    /// when given.
     pub fn aircraft_size(&self) -> AircraftSize {
-        classify_aircraft(&self.aircraft_type)
+        match self.wingspan_m {
+            Some(span) if !is_known_aircraft(&self.aircraft_type) => {
+                AircraftSize::from_wingspan(span)
+            }
+            _ => classify_aircraft(&self.aircraft_type),
+        }
+    }
+
+    /// Quick turns are dominated by the departing load; longer visits by the arriving one.
+    pub fn dominant_role(&self) -> FlightRole {
+        let ground = self.scheduled_departure - self.scheduled_arrival;
+        if ground.num_minutes() <= QUICK_TURN_MAX_MINUTES {
+            FlightRole::Departure
+        } else {
+            FlightRole::Arrival
+        }
     }
 }
 
@@ -93,17 +273,280 @@ impl fmt::Display for Flight {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Passenger-processing class of a gate.  `Swing` gates can be switched between domestic and
+/// international operation and therefore accept either.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum GateClass {
+    Domestic,
+    International,
+    #[default]
+    Swing,
+}
+
+impl GateClass {
+    pub fn accepts(&self, international: bool) -> bool {
+        match self {
+            Self::Domestic => !international,
+            Self::International => international,
+            Self::Swing => true,
+        }
+    }
+}
+
+/// Which side(s) of an aircraft a gate's jet bridges can reach.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum JetbridgeConfig {
+    LeftOnly,
+    RightOnly,
+    #[default]
+    Both,
+}
+
+impl JetbridgeConfig {
+    pub fn serves(&self, door: DoorSide) -> bool {
+        match self {
+            Self::LeftOnly => door == DoorSide::Left,
+            Self::RightOnly => door == DoorSide::Right,
+            Self::Both => true,
+        }
+    }
+}
+
+impl fmt::Display for GateClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Why `Gate::accepts` turned a flight away, checked in this order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionReason {
+    TooSmall,
+    OutOfService,
+    /// The window is shorter or longer than the gate may be booked for.
+    OccupancyLimit,
+    /// A planned closure overlaps the window.
+    Closed,
+    /// Part of the window falls outside the gate's staffed shifts.
+    Unstaffed,
+    /// The gate's own type lists exclude the aircraft type.
+    TypeExcluded,
+    /// The jet bridges do not reach the aircraft's boarding door.
+    DoorMismatch,
+    /// The aircraft type is designated to other gates in the pool.
+    TypeDesignatedElsewhere,
+    /// A domestic gate for an international flight, or the reverse.
+    ClassMismatch,
+    /// The airline's lease confines it to other terminals.
+    NotLeased,
+}
+
+impl fmt::Display for RejectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Self::TooSmall => "gate too small",
+            Self::OutOfService => "gate out of service",
+            Self::OccupancyLimit => "window outside the gate's occupancy limits",
+            Self::Closed => "gate closed in window",
+            Self::Unstaffed => "gate not staffed for the whole window",
+            Self::TypeExcluded => "aircraft type excluded",
+            Self::DoorMismatch => "jet bridge cannot reach the door",
+            Self::TypeDesignatedElsewhere => "aircraft type designated to other gates",
+            Self::ClassMismatch => "wrong domestic/international class",
+            Self::NotLeased => "terminal not leased to the airline",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// What `Gate::accepts` needs to know beyond the gate and flight, usually built by the engine.
+#[derive(Debug, Clone, Default)]
+pub struct GateContext {
+    /// The flight touches a foreign airport.
+    pub international: bool,
+    /// Window to check occupancy limits, planned closures and staffing against; `None` skips them.
+    pub window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    /// Some gate in the pool designates the flight's aircraft type.
+    pub type_designated_in_pool: bool,
+    /// Terminals the airline's lease confines it to; empty when unrestricted.
+    pub leased_terminals: Vec<String>,
+}
+
+/// A static capability a planner can ask for when looking up gates, see
+/// `AllocationEngine::compatible_gates`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum GateFeature {
+    /// Contact gate boarding over a jet bridge.
+    JetBridge,
+    /// Remote stand boarding by bus and stairs.
+    RemoteStand,
+    /// MARS stand that can take two narrow-bodies at once.
+    Mars,
+    /// Can process international flights (international or swing class).
+    International,
+    /// Can process domestic flights (domestic or swing class).
+    Domestic,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Gate {
     pub gate_id: String,
     pub terminal: String,
     pub size: AircraftSize,
     pub is_available: bool,
+    #[serde(default)]
+    pub gate_class: GateClass,
+    /// Planned closures (e.g. maintenance) as half-open `[from, until)` windows.
+    #[serde(default)]
+    pub unavailable_windows: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+    /// Aircraft types designated to this gate.  When non-empty only these types may use it, and
+    /// each listed type is confined to the gates that list it.
+    #[serde(default)]
+    pub permitted_types: Vec<String>,
+    /// Aircraft types that may never use this gate, whatever its size.
+    #[serde(default)]
+    pub excluded_types: Vec<String>,
+    /// Multiple Aircraft Ramp System: the stand takes one wide-body, or two narrow-bodies side by
+    /// side on its sub-stands.
+    #[serde(default)]
+    pub mars: bool,
+    /// Passenger flow this gate should be kept for, e.g. `Arrival` for gates next to immigration.
+    #[serde(default)]
+    pub favours: Option<FlightRole>,
+    /// Baggage hall / sort destination nearest the gate.
+    #[serde(default)]
+    pub baggage_zone: Option<String>,
+    /// Shortest window the gate may be booked for (e.g. a minimum billing block), in minutes.
+    #[serde(default)]
+    pub min_occupancy_minutes: Option<i64>,
+    /// Longest window the gate may be booked for (e.g. a remote stand's limit), in minutes.
+    #[serde(default)]
+    pub max_occupancy_minutes: Option<i64>,
+    /// Remote stand (bussed boarding, no jet bridge) rather than a contact gate.
+    #[serde(default)]
+    pub remote: bool,
+    /// Jet bridge side(s); ignored on remote stands, which board by stairs.
+    #[serde(default)]
+    pub jetbridge_config: JetbridgeConfig,
+    /// Daily shifts the gate is crewed; a booking must fall wholly inside staffed hours.  Empty
+    /// means always staffed.
+    #[serde(default)]
+    pub staffed_shifts: Vec<StaffingShift>,
 }
 
 impl Gate {
     pub fn can_accommodate(&self, aircraft_size: AircraftSize) -> bool {
-        self.size >= aircraft_size
+        self.size.rank() >= aircraft_size.rank()
+    }
+
+    /// Whether this gate explicitly names `aircraft_type` as permitted (case-insensitive).
+    pub fn designates_type(&self, aircraft_type: &str) -> bool {
+        self.permitted_types
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(aircraft_type))
+    }
+
+    /// Whether the gate's own type lists let `aircraft_type` use it.
+    pub fn admits_type(&self, aircraft_type: &str) -> bool {
+        (self.permitted_types.is_empty() || self.designates_type(aircraft_type))
+            && !self
+                .excluded_types
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(aircraft_type))
+    }
+
+    /// Every hard constraint of the gate itself on `flight`, with the first one that fails.
+    /// Whether the gate is free in the window is not checked; that needs the schedule.
+    pub fn accepts(&self, flight: &Flight, ctx: &GateContext) -> Result<(), RejectionReason> {
+        let ac = &flight.aircraft_type;
+        let reason = if !self.can_accommodate(flight.aircraft_size()) {
+            RejectionReason::TooSmall
+        } else if !self.is_available {
+            RejectionReason::OutOfService
+        } else if ctx
+            .window
+            .is_some_and(|(from, until)| !self.allows_occupancy(from, until))
+        {
+            RejectionReason::OccupancyLimit
+        } else if ctx
+            .window
+            .is_some_and(|(from, until)| self.is_closed_during(from, until))
+        {
+            RejectionReason::Closed
+        } else if ctx
+            .window
+            .is_some_and(|(from, until)| !self.is_staffed_during(from, until))
+        {
+            RejectionReason::Unstaffed
+        } else if !self.admits_type(ac) {
+            RejectionReason::TypeExcluded
+        } else if !self.bridges_doors_of(ac) {
+            RejectionReason::DoorMismatch
+        } else if ctx.type_designated_in_pool && !self.designates_type(ac) {
+            RejectionReason::TypeDesignatedElsewhere
+        } else if !self.gate_class.accepts(ctx.international) {
+            RejectionReason::ClassMismatch
+        } else if !ctx.leased_terminals.is_empty() && !ctx.leased_terminals.contains(&self.terminal)
+        {
+            RejectionReason::NotLeased
+        } else {
+            return Ok(());
+        };
+        Err(reason)
+    }
+
+    pub fn has_feature(&self, feature: GateFeature) -> bool {
+        match feature {
+            GateFeature::JetBridge => !self.remote,
+            GateFeature::RemoteStand => self.remote,
+            GateFeature::Mars => self.mars,
+            GateFeature::International => self.gate_class.accepts(true),
+            GateFeature::Domestic => self.gate_class.accepts(false),
+        }
+    }
+
+    /// Whether the gate's jet bridges reach `aircraft_type`'s boarding door.
+    pub fn bridges_doors_of(&self, aircraft_type: &str) -> bool {
+        self.remote || self.jetbridge_config.serves(door_config(aircraft_type))
+    }
+
+    /// Whether a booking over `[from, until)` respects the gate's occupancy limits.
+    pub fn allows_occupancy(&self, from: DateTime<Utc>, until: DateTime<Utc>) -> bool {
+        let minutes = (until - from).num_minutes();
+        self.min_occupancy_minutes.is_none_or(|min| minutes >= min)
+            && self.max_occupancy_minutes.is_none_or(|max| minutes <= max)
+    }
+
+    /// Whether crew is on the gate for all of `[from, until)`, possibly across consecutive shifts.
+    pub fn is_staffed_during(&self, from: DateTime<Utc>, until: DateTime<Utc>) -> bool {
+        if self.staffed_shifts.is_empty() {
+            return true;
+        }
+        // Walk forward through whichever shift covers the current instant.
+        let mut covered = from;
+        while covered < until {
+            match self
+                .staffed_shifts
+                .iter()
+                .filter_map(|s| s.end_of_shift_covering(covered))
+                .max()
+            {
+                Some(end) => covered = end,
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// Returns true if any planned closure overlaps the half-open interval `[from, until)`.
+    pub fn is_closed_during(&self, from: DateTime<Utc>, until: DateTime<Utc>) -> bool {
+        self.unavailable_windows
+            .iter()
+            .any(|&(start, end)| start < until && end > from)
     }
 }
 
@@ -118,13 +561,65 @@ impl fmt::Display for Gate {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Dispatcher workflow state of an assignment.  `Locked` assignments are never moved by
+/// automatic re-allocation or optimisation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum AssignmentStatus {
+    #[default]
+    Provisional,
+    Confirmed,
+    Locked,
+}
+
+impl fmt::Display for AssignmentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct GateAssignment {
     pub assignment_id: Uuid,
     pub flight: Flight,
     pub gate: Gate,
     pub assigned_from: DateTime<Utc>,
     pub assigned_until: DateTime<Utc>,
+    #[serde(default)]
+    pub status: AssignmentStatus,
+    /// IATA code of the airport the gate was allocated at.
+    #[serde(default)]
+    pub airport: String,
+    /// Window as first planned, before disruptions moved it; `assigned_from`/`assigned_until`
+    /// hold the current estimate.  `None` for records saved before this was tracked.
+    #[serde(default)]
+    pub scheduled: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    /// Sub-stand (0 or 1) of a MARS gate shared with another narrow-body; `None` when the flight
+    /// has the whole stand.
+    #[serde(default)]
+    pub sub_stand: Option<u8>,
+    /// Free-text dispatcher notes, oldest first (e.g. "towing at 10:30").
+    #[serde(default)]
+    pub notes: Vec<String>,
+}
+
+impl GateAssignment {
+    pub fn is_locked(&self) -> bool {
+        self.status == AssignmentStatus::Locked
+    }
+
+    /// Whether the assigned window shares any instant with half-open `[from, until)`.  Windows
+    /// that merely touch do not overlap.
+    pub fn overlaps(&self, from: DateTime<Utc>, until: DateTime<Utc>) -> bool {
+        self.assigned_from < until && self.assigned_until > from
+    }
+
+    /// How far the current window starts after the scheduled one (negative when brought forward).
+    pub fn delay(&self) -> Duration {
+        self.scheduled
+            .map_or(Duration::zero(), |(from, _)| self.assigned_from - from)
+    }
 }
 
 impl fmt::Display for GateAssignment {
@@ -140,7 +635,156 @@ impl fmt::Display for GateAssignment {
     }
 }
 
+/// A daily no-movement period expressed in UTC time of day.  When `end <= start` the curfew
+/// wraps past midnight (e.g. 23:00–06:00).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Curfew {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl Curfew {
+    /// Returns true if any part of the half-open interval `[from, until)` falls inside the curfew.
+    pub fn overlaps(&self, from: DateTime<Utc>, until: DateTime<Utc>) -> bool {
+        // Start a day early so a curfew that began the previous evening is also considered.
+        let mut day = from.date_naive() - Duration::days(1);
+        while day <= until.date_naive() {
+            let start = day.and_time(self.start).and_utc();
+            let end = if self.end <= self.start {
+                (day + Duration::days(1)).and_time(self.end).and_utc()
+            } else {
+                day.and_time(self.end).and_utc()
+            };
+            if start < until && end > from {
+                return true;
+            }
+            day += Duration::days(1);
+        }
+        false
+    }
+}
+
+impl fmt::Display for Curfew {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+/// A daily crew shift on a gate in UTC time of day.  When `end <= start` the shift wraps past
+/// midnight (e.g. 22:00–06:00); equal times mean round the clock.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StaffingShift {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl StaffingShift {
+    /// End of the occurrence of this shift under way at `at`, if any.
+    pub fn end_of_shift_covering(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        // The occurrence may have begun the previous day.
+        [at.date_naive() - Duration::days(1), at.date_naive()]
+            .into_iter()
+            .map(|day| {
+                let start = day.and_time(self.start).and_utc();
+                let end_day = if self.end <= self.start {
+                    day + Duration::days(1)
+                } else {
+                    day
+                };
+                (start, end_day.and_time(self.end).and_utc())
+            })
+            .find(|&(start, end)| start <= at && at < end)
+            .map(|(_, end)| end)
+    }
+}
+
+impl fmt::Display for StaffingShift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+/// Grants `airline` use of the gates in `terminal`.  An airline holding any lease in force when a
+/// flight arrives is restricted to those leased terminals; otherwise it may use any gate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GateLease {
+    pub airline: String,
+    pub terminal: String,
+    /// Daily UTC `[start, end)` window the lease is in force, wrapping past midnight when
+    /// `end <= start`.  `None` means all day.
+    #[serde(default)]
+    pub hours: Option<(NaiveTime, NaiveTime)>,
+}
+
+impl GateLease {
+    /// Whether the lease is in force at `at`.
+    pub fn applies_at(&self, at: DateTime<Utc>) -> bool {
+        let Some((start, end)) = self.hours else {
+            return true;
+        };
+        let t = at.time();
+        if start < end {
+            start <= t && t < end
+        } else {
+            t >= start || t < end
+        }
+    }
+}
+
+/// Two gates sharing a taxiway lane: their aircraft may stand side by side, but may not push
+/// back within `window_minutes` of each other.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PushbackConflict {
+    pub gate_a: String,
+    pub gate_b: String,
+    pub window_minutes: i64,
+}
+
+impl PushbackConflict {
+    /// The pushback window if this relationship links `gate` and `other`.
+    pub fn window_between(&self, gate: &str, other: &str) -> Option<Duration> {
+        let linked = (self.gate_a == gate && self.gate_b == other)
+            || (self.gate_a == other && self.gate_b == gate);
+        linked.then(|| Duration::minutes(self.window_minutes))
+    }
+}
+
+/// A flight-less hold on a gate, e.g. kept free for a possible diversion or medical arrival.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GateReservation {
+    pub reservation_id: Uuid,
+    pub gate_id: String,
+    pub from: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub reason: String,
+}
+
+impl fmt::Display for GateReservation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Gate {} held ({} - {}): {}",
+            self.gate_id,
+            self.from.format("%H:%M"),
+            self.until.format("%H:%M"),
+            self.reason
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum DisruptionType {
     Delay,
     Cancellation,
@@ -156,7 +800,8 @@ impl fmt::Display for DisruptionType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct DisruptionEvent {
     pub event_id: Uuid,
     pub disruption_type: DisruptionType,
@@ -164,6 +809,18 @@ pub struct DisruptionEvent {
     pub description: String,
     pub reported_at: DateTime<Utc>,
     pub delay_minutes: i32,
+    /// Restricts the event to assignments at this airport; `None` applies it at every airport.
+    #[serde(default)]
+    pub airport: Option<String>,
+}
+
+impl DisruptionEvent {
+    /// True when the event applies to assignments made at `airport`.
+    pub fn covers_airport(&self, airport: &str) -> bool {
+        self.airport
+            .as_deref()
+            .is_none_or(|a| a.eq_ignore_ascii_case(airport))
+    }
 }
 
 impl fmt::Display for DisruptionEvent {
@@ -178,3 +835,45 @@ impl fmt::Display for DisruptionEvent {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_ranks_decide_what_a_gate_accommodates() {
+        use AircraftSize::*;
+        assert_eq!([Small.rank(), Medium.rank(), Large.rank()], [1, 2, 3]);
+
+        let gate = |size| Gate {
+            gate_id: "G1".into(),
+            terminal: "T1".into(),
+            size,
+            is_available: true,
+            gate_class: GateClass::Swing,
+            unavailable_windows: Vec::new(),
+            permitted_types: Vec::new(),
+            excluded_types: Vec::new(),
+            mars: false,
+            favours: None,
+            baggage_zone: None,
+            min_occupancy_minutes: None,
+            max_occupancy_minutes: None,
+            remote: false,
+            jetbridge_config: JetbridgeConfig::Both,
+            staffed_shifts: Vec::new(),
+        };
+        for g in [Small, Medium, Large] {
+            for a in [Small, Medium, Large] {
+                assert_eq!(
+                    gate(g).can_accommodate(a),
+                    g.rank() >= a.rank(),
+                    "{g} gate, {a} aircraft"
+                );
+            }
+        }
+        let mut sizes = vec![Large, Small, Medium];
+        sizes.sort();
+        assert_eq!(sizes, [Small, Medium, Large]);
+    }
+}
@@ -0,0 +1,325 @@
+//! Allocating many flights in one run and summarising the ones left without a gate.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use super::{AllocationEngine, AllocationError, PlanBias};
+use crate::domain::*;
+
+/// Knobs for `allocate_batch_with`.
+#[derive(Debug, Clone, Default)]
+pub struct BatchOptions {
+    /// Gates every flight in the batch would like, e.g. the pier nearest the lounges.
+    pub preferred: Vec<String>,
+    /// Share preferred-gate grants between airlines in proportion to their number of flights
+    /// in the batch, instead of first come, first served.
+    pub fairness: bool,
+    /// Airline -> priority within an arrival bank; higher goes first among flights arriving
+    /// at the same time.  Airlines not listed have priority 0.
+    pub priorities: HashMap<String, i32>,
+}
+
+/// Outcome of `allocate_batch`.
+#[derive(Debug, Default)]
+pub struct BatchResult {
+    pub assignments: Vec<GateAssignment>,
+    /// Flights that could not be placed, in allocation order, with the reason.
+    pub unplaced: Vec<(Flight, AllocationError)>,
+}
+
+/// Coarse reason a flight was left unplaced, as reported to planners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UnplacedCause {
+    NoGatesRegistered,
+    InactiveFlight,
+    WindowInPast,
+    /// No gate of (at least) this size was free in the flight's window.
+    NoGate(AircraftSize),
+    ScoreTooHigh,
+    TerminalCurfew,
+    NightRestricted,
+    AirlineCapReached,
+    UnknownAircraftType,
+}
+
+impl UnplacedCause {
+    fn of(flight: &Flight, error: &AllocationError) -> Self {
+        match error {
+            AllocationError::NoGatesRegistered { .. } => Self::NoGatesRegistered,
+            AllocationError::InactiveFlight { .. } => Self::InactiveFlight,
+            AllocationError::WindowInPast { .. } => Self::WindowInPast,
+            AllocationError::NoCompatibleGate | AllocationError::GateRefused { .. } => {
+                Self::NoGate(flight.aircraft_size())
+            }
+            AllocationError::ScoreTooHigh { .. } => Self::ScoreTooHigh,
+            AllocationError::TerminalCurfew { .. } => Self::TerminalCurfew,
+            AllocationError::NightRestricted { .. } => Self::NightRestricted,
+            AllocationError::AirlineCapReached { .. } => Self::AirlineCapReached,
+            AllocationError::UnknownAircraftType { .. } => Self::UnknownAircraftType,
+        }
+    }
+}
+
+impl fmt::Display for UnplacedCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoGatesRegistered => write!(f, "no gates registered"),
+            Self::InactiveFlight => write!(f, "flight cancelled, diverted or departed"),
+            Self::WindowInPast => write!(f, "window already over"),
+            Self::NoGate(size) => write!(f, "no {} gate in window", size),
+            Self::ScoreTooHigh => write!(f, "best gate above score threshold"),
+            Self::TerminalCurfew => write!(f, "terminal curfew"),
+            Self::NightRestricted => write!(f, "aircraft type restricted at night"),
+            Self::AirlineCapReached => write!(f, "airline at its gate cap"),
+            Self::UnknownAircraftType => write!(f, "unknown aircraft type"),
+        }
+    }
+}
+
+/// Unplaced flights of a batch counted by cause.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct UnplacedReport {
+    pub total: usize,
+    pub by_cause: BTreeMap<UnplacedCause, usize>,
+}
+
+impl fmt::Display for UnplacedReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} flight(s) unplaced", self.total)?;
+        let mut causes: Vec<(&UnplacedCause, &usize)> = self.by_cause.iter().collect();
+        // Most common cause first; ties keep the enum order.
+        causes.sort_by_key(|&(_, n)| std::cmp::Reverse(*n));
+        for (i, (cause, n)) in causes.into_iter().enumerate() {
+            let sep = if i == 0 { ": " } else { ", " };
+            write!(f, "{}{} due to {}", sep, n, cause)?;
+        }
+        Ok(())
+    }
+}
+
+impl BatchResult {
+    /// Aggregate the unplaced flights by cause, e.g. "3 flight(s) unplaced: 2 due to no Large
+    /// gate in window, 1 due to terminal curfew".
+    pub fn unplaced_report(&self) -> UnplacedReport {
+        let mut report = UnplacedReport {
+            total: self.unplaced.len(),
+            ..Default::default()
+        };
+        for (flight, error) in &self.unplaced {
+            *report
+                .by_cause
+                .entry(UnplacedCause::of(flight, error))
+                .or_default() += 1;
+        }
+        report
+    }
+}
+
+impl AllocationEngine {
+    /// Allocate `flights` at `airport` one after another, greedily.  Flights go in order of
+    /// scheduled arrival; among flights arriving together, higher `BatchOptions::priorities`
+    /// first, then more passengers first, then input order.  Failures do not stop the run;
+    /// they are collected in `BatchResult::unplaced`.
+    pub fn allocate_batch(&mut self, flights: &[Flight], airport: &str) -> BatchResult {
+        self.allocate_batch_with(flights, airport, &BatchOptions::default())
+    }
+
+    /// `allocate_batch` with preferred gates and optional proportional fairness.  With
+    /// `fairness`, each airline may hold at most its movement share of the preferred gates at any
+    /// one time (at least one); beyond that they are withheld from it, usable only as a last
+    /// resort.
+    pub fn allocate_batch_with(
+        &mut self,
+        flights: &[Flight],
+        airport: &str,
+        options: &BatchOptions,
+    ) -> BatchResult {
+        self.tracked(|e| e.run_batch(flights, airport, options))
+    }
+
+    fn run_batch(
+        &mut self,
+        flights: &[Flight],
+        airport: &str,
+        options: &BatchOptions,
+    ) -> BatchResult {
+        let mut movements: HashMap<&str, usize> = HashMap::new();
+        for f in flights {
+            *movements.entry(f.airline.as_str()).or_default() += 1;
+        }
+
+        let mut order: Vec<&Flight> = flights.iter().collect();
+        order.sort_by_key(|f| {
+            (
+                f.scheduled_arrival,
+                Reverse(options.priorities.get(&f.airline).copied().unwrap_or(0)),
+                Reverse(f.passenger_count.unwrap_or(0)),
+            )
+        });
+
+        let mut result = BatchResult::default();
+        for flight in order {
+            let at_quota = options.fairness && {
+                let share = movements[flight.airline.as_str()] as f64 / flights.len() as f64;
+                let quota = ((share * options.preferred.len() as f64).round() as usize).max(1);
+                self.preferred_held(flight, &options.preferred) >= quota
+            };
+            let bias = PlanBias {
+                withheld: if at_quota { &options.preferred } else { &[] },
+                ..Default::default()
+            };
+            let r = self.allocate_with(flight, airport, &options.preferred, bias);
+            match (r.assignment, r.error) {
+                (Some(a), _) => result.assignments.push(a),
+                (None, Some(error)) => result.unplaced.push((flight.clone(), error)),
+                (None, None) => result
+                    .unplaced
+                    .push((flight.clone(), AllocationError::NoCompatibleGate)),
+            }
+        }
+        result
+    }
+
+    /// Preferred gates the flight's airline already occupies during the flight's window.
+    fn preferred_held(&self, flight: &Flight, preferred: &[String]) -> usize {
+        let until = self.hold_until(flight);
+        self.assignments
+            .iter()
+            .filter(|a| a.flight.airline == flight.airline && preferred.contains(&a.gate.gate_id))
+            .filter(|a| a.overlaps(flight.scheduled_arrival, until))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::{at, flight, gate};
+    use super::*;
+    use chrono::NaiveTime;
+
+    #[test]
+    fn unplaced_report_groups_by_cause() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("L1", "T3", AircraftSize::Large));
+        e.add_gate(gate("M1", "T3", AircraftSize::Medium));
+        e.set_terminal_curfew(
+            "T3",
+            Curfew {
+                start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+            },
+        );
+        let batch = e.allocate_batch(
+            &[
+                flight("F1", "B777", 10, 12),
+                flight("F2", "B777", 10, 12),
+                flight("F3", "B777", 11, 13),
+                flight("F4", "A320", 21, 22),
+            ],
+            "LHR",
+        );
+        assert_eq!(batch.assignments.len(), 1);
+
+        let report = batch.unplaced_report();
+        assert_eq!(report.total, 3);
+        assert_eq!(
+            report
+                .by_cause
+                .get(&UnplacedCause::NoGate(AircraftSize::Large)),
+            Some(&2)
+        );
+        assert_eq!(
+            report.by_cause.get(&UnplacedCause::TerminalCurfew),
+            Some(&1)
+        );
+        assert_eq!(
+            report.to_string(),
+            "3 flight(s) unplaced: 2 due to no Large gate in window, 1 due to terminal curfew"
+        );
+    }
+
+    #[test]
+    fn fairness_shares_preferred_gates_by_movement_share() {
+        let run = |fairness: bool| {
+            let mut e = AllocationEngine::new();
+            for id in ["P1", "P2", "P3"] {
+                e.add_gate(gate(id, "T5", AircraftSize::Medium));
+            }
+            for i in 0..12 {
+                e.add_gate(gate(&format!("O{}", i), "T2", AircraftSize::Medium));
+            }
+            // The larger airline files all its flights first.
+            let flights: Vec<Flight> = (0..8)
+                .map(|i| ("Alpha", i))
+                .chain((0..4).map(|i| ("Bravo", i)))
+                .map(|(airline, i)| {
+                    let mut f = flight(&format!("{}-{}", airline, i), "A320", 10, 12);
+                    f.airline = airline.into();
+                    f
+                })
+                .collect();
+            let options = BatchOptions {
+                preferred: vec!["P1".into(), "P2".into(), "P3".into()],
+                fairness,
+                ..Default::default()
+            };
+            let batch = e.allocate_batch_with(&flights, "LHR", &options);
+            assert!(batch.unplaced.is_empty());
+            let granted = |airline: &str| {
+                batch
+                    .assignments
+                    .iter()
+                    .filter(|a| a.flight.airline == airline && a.gate.gate_id.starts_with('P'))
+                    .count()
+            };
+            (granted("Alpha"), granted("Bravo"))
+        };
+        assert_eq!(run(false), (3, 0));
+        assert_eq!(run(true), (2, 1));
+    }
+
+    #[test]
+    fn arrival_banks_go_by_arrival_then_priority_then_passengers() {
+        let mut e = AllocationEngine::new();
+        for id in ["P1", "P2", "P3", "O1"] {
+            e.add_gate(gate(id, "T5", AircraftSize::Medium));
+        }
+        let in_bank = |id: &str, airline: &str, minute: u32, passengers: u32| Flight {
+            airline: airline.into(),
+            scheduled_arrival: at(10, minute),
+            passenger_count: Some(passengers),
+            ..flight(id, "A320", 10, 12)
+        };
+        let options = BatchOptions {
+            preferred: vec!["P1".into(), "P2".into(), "P3".into()],
+            priorities: HashMap::from([("Home".into(), 5), ("Late".into(), 9)]),
+            ..Default::default()
+        };
+
+        let batch = e.allocate_batch_with(
+            &[
+                in_bank("LATE", "Late", 30, 300),
+                in_bank("SMALL", "Guest", 0, 80),
+                in_bank("FULL", "Guest", 0, 180),
+                in_bank("HOME", "Home", 0, 80),
+            ],
+            "LHR",
+            &options,
+        );
+        let placed: Vec<(&str, &str)> = batch
+            .assignments
+            .iter()
+            .map(|a| (a.flight.flight_id.as_str(), a.gate.gate_id.as_str()))
+            .collect();
+        assert_eq!(
+            placed,
+            [
+                ("HOME", "P1"),
+                ("FULL", "P2"),
+                ("SMALL", "P3"),
+                ("LATE", "O1")
+            ]
+        );
+    }
+}
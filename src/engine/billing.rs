@@ -0,0 +1,124 @@
+//! Gate-use charges by size, duration and contact-vs-remote stand.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::AllocationEngine;
+use crate::domain::*;
+
+/// Hourly gate charges.  The default rates are illustrative; airports configure their own via
+/// `EngineConfig::tariff`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tariff {
+    /// Charge per hour of occupancy, by gate size.
+    pub hourly_rate: BTreeMap<AircraftSize, f64>,
+    /// Multiplier applied to remote stands (no jet bridge), e.g. `0.6` for a 40% discount.
+    pub remote_factor: f64,
+}
+
+impl Default for Tariff {
+    fn default() -> Self {
+        Self {
+            hourly_rate: BTreeMap::from([
+                (AircraftSize::Small, 50.0),
+                (AircraftSize::Medium, 80.0),
+                (AircraftSize::Large, 150.0),
+            ]),
+            remote_factor: 0.6,
+        }
+    }
+}
+
+impl Tariff {
+    /// Charge for the whole of `a`'s window on its gate, turnaround buffer included.
+    pub fn charge(&self, a: &GateAssignment) -> f64 {
+        let hours = (a.assigned_until - a.assigned_from).num_minutes() as f64 / 60.0;
+        let rate = self.hourly_rate.get(&a.gate.size).copied().unwrap_or(0.0);
+        let factor = if a.gate.remote {
+            self.remote_factor
+        } else {
+            1.0
+        };
+        rate * hours * factor
+    }
+}
+
+/// One gate use and what it cost.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Charge {
+    pub flight_id: String,
+    pub airline: String,
+    pub gate_id: String,
+    pub amount: f64,
+}
+
+/// Charges totalled by `AllocationEngine::billing_summary`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BillingSummary {
+    pub total: f64,
+    pub by_airline: BTreeMap<String, f64>,
+    pub by_gate: BTreeMap<String, f64>,
+}
+
+impl AllocationEngine {
+    /// Total gate charges for completed gate uses (expired by `advance_to`) and current
+    /// assignments at their present windows, optionally for one airline only.  Cancelled
+    /// flights are not billed.
+    pub fn billing_summary(&self, airline: Option<&str>) -> BillingSummary {
+        let current = self.assignments.iter().map(|a| self.charge_for(a));
+        let mut summary = BillingSummary::default();
+        for charge in self.completed_charges.iter().cloned().chain(current) {
+            if airline.is_some_and(|al| al != charge.airline) {
+                continue;
+            }
+            summary.total += charge.amount;
+            *summary.by_airline.entry(charge.airline).or_default() += charge.amount;
+            *summary.by_gate.entry(charge.gate_id).or_default() += charge.amount;
+        }
+        summary
+    }
+
+    pub(super) fn charge_for(&self, a: &GateAssignment) -> Charge {
+        Charge {
+            flight_id: a.flight.flight_id.clone(),
+            airline: a.flight.airline.clone(),
+            gate_id: a.gate.gate_id.clone(),
+            amount: self.config.tariff.charge(a),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::{at, flight, gate};
+    use super::*;
+
+    #[test]
+    fn large_contact_gate_costs_more_than_small_remote_stand() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-L1", "T5", AircraftSize::Large));
+        let mut remote = gate("R1", "T5", AircraftSize::Small);
+        remote.remote = true;
+        e.add_gate(remote);
+
+        let mut wide = flight("F1", "B777", 8, 10);
+        wide.airline = "Alpha".into();
+        let mut regional = flight("F2", "E190", 8, 9);
+        regional.airline = "Bravo".into();
+        e.allocate_gate(&wide, "LHR", &[]);
+        e.allocate_gate(&regional, "LHR", &["R1".into()]);
+
+        let alpha = e.billing_summary(Some("Alpha"));
+        let bravo = e.billing_summary(Some("Bravo"));
+        // 2h15 at 150/h against 1h15 at 50/h with the 0.6 remote factor.
+        assert_eq!(alpha.total, 337.5);
+        assert_eq!(bravo.total, 37.5);
+        assert!(alpha.total > bravo.total);
+        assert_eq!(e.billing_summary(None).total, alpha.total + bravo.total);
+
+        // Completed uses stay billed after the clock frees the gate.
+        e.advance_to(at(12, 0));
+        assert!(e.assignments.is_empty());
+        assert_eq!(e.billing_summary(None).by_gate["T5-L1"], 337.5);
+    }
+}
@@ -0,0 +1,191 @@
+//! Push notifications for assignment changes, for embedders that do not use the gRPC stream.
+
+use super::replica::Mirrored;
+use super::{
+    AllocationEngine, COST_GATE_DOWNGRADE, COST_PER_MINUTE_SHIFTED, PENALTY_TERMINAL_CHANGE,
+};
+use crate::domain::*;
+
+/// Callback registered via `AllocationEngine::on_assignment_change`.
+pub type AssignmentChangeListener = Box<dyn Fn(AssignmentChange) + Send>;
+
+/// How an engine operation changed one flight's assignment.
+#[derive(Debug, Clone)]
+pub enum AssignmentChange {
+    Added(GateAssignment),
+    Removed(GateAssignment),
+    /// The flight changed gate (possibly with a new window as well).
+    Moved {
+        from: GateAssignment,
+        to: GateAssignment,
+    },
+    /// The flight kept its gate but its window moved.
+    Shifted {
+        from: GateAssignment,
+        to: GateAssignment,
+    },
+}
+
+impl AllocationEngine {
+    /// Register a callback invoked for every assignment added, removed, moved or time-shifted by
+    /// an engine operation (allocation, plan commits, batches, disruptions, clock advances,
+    /// consolidation and orphan reaping).
+    pub fn on_assignment_change(&mut self, listener: AssignmentChangeListener) {
+        self.change_listeners.push(listener);
+    }
+
+    /// Run `op` and report how it changed the assignments to the change listeners, and the
+    /// mirrored state to the delta listeners.  Nested tracked operations are reported once, by
+    /// the outermost.
+    pub(super) fn tracked<R>(&mut self, op: impl FnOnce(&mut Self) -> R) -> R {
+        if (self.change_listeners.is_empty() && self.delta_listeners.is_empty())
+            || self.tracking_changes
+        {
+            return op(self);
+        }
+        let before = self.assignments.clone();
+        let mirrored = (!self.delta_listeners.is_empty()).then(|| Mirrored::of(self));
+        self.tracking_changes = true;
+        let out = op(self);
+        self.tracking_changes = false;
+        for change in diff(&before, &self.assignments) {
+            for listener in &self.change_listeners {
+                listener(change.clone());
+            }
+        }
+        for delta in mirrored.map(|m| m.deltas_to(self)).unwrap_or_default() {
+            for listener in &self.delta_listeners {
+                listener(delta.clone());
+            }
+        }
+        out
+    }
+}
+
+/// Weighted cost of the moves and shifts between two assignment lists; additions and removals
+/// are free.  Backs `DisruptionResult::estimated_reallocation_cost`.
+pub(super) fn reallocation_cost(before: &[GateAssignment], after: &[GateAssignment]) -> f64 {
+    diff(before, after)
+        .into_iter()
+        .map(|change| match change {
+            AssignmentChange::Moved { from, to } | AssignmentChange::Shifted { from, to } => {
+                let mut cost = COST_PER_MINUTE_SHIFTED
+                    * (to.assigned_from - from.assigned_from).num_minutes().abs() as f64;
+                if to.gate.terminal != from.gate.terminal {
+                    cost += PENALTY_TERMINAL_CHANGE;
+                }
+                let downgrade = from.gate.size.rank() as i32 - to.gate.size.rank() as i32;
+                if downgrade > 0 {
+                    cost += COST_GATE_DOWNGRADE * downgrade as f64;
+                }
+                cost
+            }
+            AssignmentChange::Added(_) | AssignmentChange::Removed(_) => 0.0,
+        })
+        .sum()
+}
+
+/// Changes between two assignment lists.  Re-allocation replaces an assignment with a new one,
+/// so a removal and an addition for the same flight at the same airport count as one move.
+fn diff(before: &[GateAssignment], after: &[GateAssignment]) -> Vec<AssignmentChange> {
+    let find = |list: &[GateAssignment], a: &GateAssignment| {
+        list.iter()
+            .find(|b| b.assignment_id == a.assignment_id)
+            .cloned()
+    };
+    let mut removed: Vec<GateAssignment> = before
+        .iter()
+        .filter(|a| find(after, a).is_none())
+        .cloned()
+        .collect();
+
+    let mut changes = Vec::new();
+    let mut added = Vec::new();
+    for a in after {
+        let previous = find(before, a).or_else(|| {
+            let i = removed
+                .iter()
+                .position(|r| r.flight.flight_id == a.flight.flight_id && r.airport == a.airport)?;
+            Some(removed.remove(i))
+        });
+        match previous {
+            None => added.push(AssignmentChange::Added(a.clone())),
+            Some(from) if from.gate.gate_id != a.gate.gate_id => {
+                changes.push(AssignmentChange::Moved {
+                    from,
+                    to: a.clone(),
+                })
+            }
+            Some(from)
+                if from.assigned_from != a.assigned_from
+                    || from.assigned_until != a.assigned_until =>
+            {
+                changes.push(AssignmentChange::Shifted {
+                    from,
+                    to: a.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    removed
+        .into_iter()
+        .map(AssignmentChange::Removed)
+        .chain(changes)
+        .chain(added)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use super::super::test_support::{flight, gate};
+    use super::*;
+
+    #[test]
+    fn listener_sees_allocation_and_delay_shift() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        e.on_assignment_change(Box::new(move |c| sink.lock().unwrap().push(c)));
+
+        e.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &[]);
+        e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::Delay,
+            affected_flight_id: "F1".into(),
+            description: "ATC".into(),
+            reported_at: Utc::now(),
+            delay_minutes: 20,
+            airport: None,
+        });
+        e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::Cancellation,
+            affected_flight_id: "F1".into(),
+            description: "Crew".into(),
+            reported_at: Utc::now(),
+            delay_minutes: 0,
+            airport: None,
+        });
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 3);
+        assert!(matches!(&seen[0], AssignmentChange::Added(a) if a.flight.flight_id == "F1"));
+        match &seen[1] {
+            AssignmentChange::Shifted { from, to } => {
+                assert_eq!(
+                    to.assigned_from - from.assigned_from,
+                    chrono::Duration::minutes(20)
+                );
+            }
+            other => panic!("expected a shift, got {:?}", other),
+        }
+        assert!(matches!(&seen[2], AssignmentChange::Removed(_)));
+    }
+}
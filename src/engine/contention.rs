@@ -0,0 +1,226 @@
+//! Where demand for gates is tightest, and which sizes run short, for capacity planning.
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::AllocationEngine;
+use crate::domain::*;
+
+/// The busiest stretch found by `AllocationEngine::contention_peak`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentionPeak {
+    /// Terminal holding every contended gate, or `None` when they span several.
+    pub terminal: Option<String>,
+    /// Gate size class under pressure: flights needing at least this size compete for it.
+    pub size: AircraftSize,
+    pub from: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    /// Flights on the ground in the window needing a gate of at least `size`.
+    pub flights: usize,
+    /// In-service gates of at least `size` any of those flights may use.
+    pub gates: usize,
+    /// `flights / gates`; above 1.0 some of the flights cannot be placed.  Infinite with no gate.
+    pub ratio: f64,
+}
+
+impl AllocationEngine {
+    /// The size class and time window where the most flights compete for the fewest compatible
+    /// gates, over the ground windows (arrival to departure plus turnaround) of `flights`.
+    /// Planners add capacity there first.  Cancelled, diverted and departed flights are
+    /// ignored; `None` when no flight remains.
+    pub fn contention_peak(&self, flights: &[Flight]) -> Option<ContentionPeak> {
+        let windows: Vec<(&Flight, DateTime<Utc>, DateTime<Utc>)> = flights
+            .iter()
+            .filter(|f| !f.status.is_terminal())
+            .map(|f| (f, f.scheduled_arrival, self.hold_until(f)))
+            .collect();
+        let mut points: Vec<DateTime<Utc>> = windows.iter().flat_map(|&(_, a, b)| [a, b]).collect();
+        points.sort();
+        points.dedup();
+
+        let mut peak: Option<ContentionPeak> = None;
+        for span in points.windows(2) {
+            let (from, until) = (span[0], span[1]);
+            for size in [
+                AircraftSize::Large,
+                AircraftSize::Medium,
+                AircraftSize::Small,
+            ] {
+                let competing: Vec<&Flight> = windows
+                    .iter()
+                    .filter(|&&(f, a, b)| {
+                        a < until && b > from && f.aircraft_size().rank() >= size.rank()
+                    })
+                    .map(|&(f, _, _)| f)
+                    .collect();
+                if competing.is_empty() {
+                    continue;
+                }
+                let gates: Vec<&Gate> = self
+                    .gates
+                    .iter()
+                    .filter(|g| {
+                        g.is_available
+                            && g.can_accommodate(size)
+                            && competing.iter().any(|f| {
+                                g.can_accommodate(f.aircraft_size()) && self.fits_flight(g, f)
+                            })
+                    })
+                    .collect();
+                let ratio = if gates.is_empty() {
+                    f64::INFINITY
+                } else {
+                    competing.len() as f64 / gates.len() as f64
+                };
+                // A peak continuing into the next span just grows its window.
+                if let Some(p) = peak
+                    .as_mut()
+                    .filter(|p| p.size == size && p.until == from && p.ratio == ratio)
+                {
+                    p.until = until;
+                    continue;
+                }
+                if peak.as_ref().is_some_and(|p| p.ratio >= ratio) {
+                    continue;
+                }
+                let terminal = gates
+                    .first()
+                    .map(|g| g.terminal.clone())
+                    .filter(|t| gates.iter().all(|g| &g.terminal == t));
+                peak = Some(ContentionPeak {
+                    terminal,
+                    size,
+                    from,
+                    until,
+                    flights: competing.len(),
+                    gates: gates.len(),
+                    ratio,
+                });
+            }
+        }
+        peak
+    }
+
+    /// For each gate size in the pool, the fewest gates of exactly that size free at any moment
+    /// of `[from, until)`, smallest size first.  A gate is free while it is in service, staffed,
+    /// not closed and neither assigned nor reserved.  Empty when the window is empty.
+    pub fn scarcity_report(
+        &self,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Vec<(AircraftSize, usize)> {
+        if until <= from {
+            return Vec::new();
+        }
+        // Free counts only change where a booking, closure or shift starts or ends.
+        let mut points = vec![from, until];
+        points.extend(
+            self.assignments
+                .iter()
+                .flat_map(|a| [a.assigned_from, a.assigned_until]),
+        );
+        points.extend(self.reservations.iter().flat_map(|r| [r.from, r.until]));
+        for g in &self.gates {
+            points.extend(g.unavailable_windows.iter().flat_map(|&(a, b)| [a, b]));
+            let mut day = from.date_naive() - Duration::days(1);
+            while day <= until.date_naive() {
+                for shift in &g.staffed_shifts {
+                    points.push(day.and_time(shift.start).and_utc());
+                    points.push(day.and_time(shift.end).and_utc());
+                }
+                day += Duration::days(1);
+            }
+        }
+        points.retain(|&p| from <= p && p <= until);
+        points.sort();
+        points.dedup();
+
+        let mut sizes: Vec<AircraftSize> = self.gates.iter().map(|g| g.size).collect();
+        sizes.sort();
+        sizes.dedup();
+        sizes
+            .into_iter()
+            .map(|size| {
+                let fewest = points
+                    .windows(2)
+                    .map(|span| {
+                        let (a, b) = (span[0], span[1]);
+                        self.gates
+                            .iter()
+                            .filter(|g| {
+                                g.size == size
+                                    && g.is_available
+                                    && !g.is_closed_during(a, b)
+                                    && g.is_staffed_during(a, b)
+                                    && !self
+                                        .assignments
+                                        .iter()
+                                        .any(|x| x.gate.gate_id == g.gate_id && x.overlaps(a, b))
+                                    && !self.reservations.iter().any(|r| {
+                                        r.gate_id == g.gate_id && r.from < b && r.until > a
+                                    })
+                            })
+                            .count()
+                    })
+                    .min()
+                    .unwrap_or(0);
+                (size, fewest)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::{at, flight, gate};
+    use super::*;
+
+    #[test]
+    fn single_large_gate_is_the_bottleneck() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-L1", "T5", AircraftSize::Large));
+        for id in ["T2-M1", "T2-M2", "T2-M3", "T2-M4"] {
+            e.add_gate(gate(id, "T2", AircraftSize::Medium));
+        }
+        let flights = [
+            flight("W1", "B777", 10, 12),
+            flight("W2", "A350", 11, 13),
+            flight("W3", "B787", 11, 12),
+            flight("N1", "A320", 10, 12),
+            flight("N2", "A320", 11, 12),
+        ];
+
+        let peak = e.contention_peak(&flights).unwrap();
+        assert_eq!(peak.size, AircraftSize::Large);
+        assert_eq!(peak.terminal.as_deref(), Some("T5"));
+        assert_eq!((peak.flights, peak.gates), (3, 1));
+        assert_eq!(peak.ratio, 3.0);
+        assert_eq!((peak.from, peak.until), (at(11, 0), at(12, 15)));
+        assert!(e.contention_peak(&[]).is_none());
+    }
+
+    #[test]
+    fn scarcity_report_flags_a_nearly_exhausted_size() {
+        let mut e = AllocationEngine::new();
+        for id in ["L1", "L2", "L3"] {
+            e.add_gate(gate(id, "T5", AircraftSize::Large));
+        }
+        for id in ["M1", "M2"] {
+            e.add_gate(gate(id, "T5", AircraftSize::Medium));
+        }
+        e.allocate_gate(&flight("W1", "B777", 7, 10), "LHR", &["L1".into()]);
+        e.allocate_gate(&flight("W2", "A350", 9, 11), "LHR", &["L2".into()]);
+        e.reserve_gate("L3", at(12, 0), at(13, 0), "Towing")
+            .unwrap();
+
+        // From 09:00 to 10:15 only L3 is free; over lunch only the reservation is left.
+        assert_eq!(
+            e.scarcity_report(at(6, 0), at(12, 0)),
+            [(AircraftSize::Medium, 2), (AircraftSize::Large, 1)]
+        );
+        assert_eq!(
+            e.scarcity_report(at(11, 30), at(14, 0)),
+            [(AircraftSize::Medium, 2), (AircraftSize::Large, 2)]
+        );
+        assert!(e.scarcity_report(at(12, 0), at(12, 0)).is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+//! Free-time queries over a single gate's schedule.
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::AllocationEngine;
+
+impl AllocationEngine {
+    /// Free intervals on `gate_id` within `[from, until)`, in time order.  Assignments,
+    /// reservations and planned closures all count as busy.  Empty for unknown or out-of-service
+    /// gates.
+    pub fn gate_gaps(
+        &self,
+        gate_id: &str,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let Some(gate) = self.gates.iter().find(|g| g.gate_id == gate_id) else {
+            return Vec::new();
+        };
+        if !gate.is_available || until <= from {
+            return Vec::new();
+        }
+        let mut busy = self.busy_intervals(gate_id);
+        busy.sort();
+
+        let mut gaps = Vec::new();
+        let mut cursor = from;
+        for (start, end) in busy {
+            if end <= cursor {
+                continue;
+            }
+            if start >= until {
+                break;
+            }
+            if start > cursor {
+                gaps.push((cursor, start));
+            }
+            cursor = cursor.max(end);
+        }
+        if cursor < until {
+            gaps.push((cursor, until));
+        }
+        gaps
+    }
+
+    /// The first gap on `gate_id` starting at or after `after` that lasts at least
+    /// `min_duration`.  A gap with no booking after it ends at `DateTime::<Utc>::MAX_UTC`.
+    pub fn next_free_window(
+        &self,
+        gate_id: &str,
+        after: DateTime<Utc>,
+        min_duration: Duration,
+    ) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let horizon = self
+            .busy_intervals(gate_id)
+            .into_iter()
+            .map(|(_, end)| end)
+            .max()
+            .map_or(after, |last| last.max(after))
+            + min_duration;
+        self.gate_gaps(gate_id, after, horizon)
+            .into_iter()
+            .find(|(start, end)| *end - *start >= min_duration)
+            .map(|(start, end)| {
+                let open_ended = end == horizon;
+                (
+                    start,
+                    if open_ended {
+                        DateTime::<Utc>::MAX_UTC
+                    } else {
+                        end
+                    },
+                )
+            })
+    }
+
+    fn busy_intervals(&self, gate_id: &str) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let closures = self
+            .gates
+            .iter()
+            .filter(|g| g.gate_id == gate_id)
+            .flat_map(|g| g.unavailable_windows.iter().copied());
+        self.assignments
+            .iter()
+            .filter(|a| a.gate.gate_id == gate_id)
+            .map(|a| (a.assigned_from, a.assigned_until))
+            .chain(
+                self.reservations
+                    .iter()
+                    .filter(|r| r.gate_id == gate_id)
+                    .map(|r| (r.from, r.until)),
+            )
+            .chain(closures)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::{at, flight, gate};
+    use super::*;
+    use crate::domain::AircraftSize;
+
+    fn engine_with_bookings() -> AllocationEngine {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-A2", "T5", AircraftSize::Medium));
+        // T5-A1 busy 07:00-08:15 and 09:30-10:15, free from 10:15.
+        e.allocate_gate(&flight("F1", "A320", 7, 8), "LHR", &["T5-A1".into()]);
+        let mut f2 = flight("F2", "A320", 9, 10);
+        f2.scheduled_arrival = at(9, 30);
+        e.allocate_gate(&f2, "LHR", &["T5-A1".into()]);
+        e
+    }
+
+    #[test]
+    fn next_free_window_skips_gaps_that_are_too_short() {
+        let e = engine_with_bookings();
+        let window = e.next_free_window("T5-A1", at(8, 0), Duration::hours(2));
+        assert_eq!(window, Some((at(10, 15), DateTime::<Utc>::MAX_UTC)));
+    }
+
+    #[test]
+    fn next_free_window_finds_gap_long_enough() {
+        let e = engine_with_bookings();
+        let window = e.next_free_window("T5-A1", at(8, 0), Duration::minutes(30));
+        assert_eq!(window, Some((at(8, 15), at(9, 30))));
+    }
+
+    #[test]
+    fn next_free_window_on_free_gate_starts_immediately() {
+        let e = engine_with_bookings();
+        let window = e.next_free_window("T5-A2", at(8, 0), Duration::hours(2));
+        assert_eq!(window, Some((at(8, 0), DateTime::<Utc>::MAX_UTC)));
+        assert_eq!(e.next_free_window("Z9", at(8, 0), Duration::hours(2)), None);
+    }
+}
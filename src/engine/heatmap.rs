@@ -0,0 +1,83 @@
+//! Per-gate occupancy over the day, bucketed for dashboards.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::AllocationEngine;
+
+impl AllocationEngine {
+    /// Fraction of each `bucket_minutes` slot every gate is occupied, from midnight UTC of the
+    /// operational day (the clock's, else the earliest assignment's) to the next midnight.
+    /// Keyed by gate id; every registered gate is present.  Turnaround buffers count as
+    /// occupied, and sub-stands sharing a MARS gate count towards one full gate.  Empty when
+    /// `bucket_minutes` is not positive.
+    pub fn utilization_heatmap(&self, bucket_minutes: i64) -> HashMap<String, Vec<f64>> {
+        if bucket_minutes <= 0 {
+            return HashMap::new();
+        }
+        let day_start = self
+            .clock
+            .or_else(|| self.assignments.iter().map(|a| a.assigned_from).min())
+            .unwrap_or_else(Utc::now)
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .expect("midnight is a valid time")
+            .and_utc();
+        let bucket = Duration::minutes(bucket_minutes);
+        let buckets = (24 * 60 + bucket_minutes - 1) / bucket_minutes;
+        let slot = |i: i64| -> (DateTime<Utc>, DateTime<Utc>) {
+            let from = day_start + bucket * i as i32;
+            (from, (from + bucket).min(day_start + Duration::days(1)))
+        };
+
+        let mut heatmap = HashMap::new();
+        for gate in &self.gates {
+            let on_gate: Vec<_> = self
+                .assignments
+                .iter()
+                .filter(|a| a.gate.gate_id == gate.gate_id)
+                .collect();
+            let row = (0..buckets)
+                .map(|i| {
+                    let (from, until) = slot(i);
+                    let busy: i64 = on_gate
+                        .iter()
+                        .map(|a| {
+                            (a.assigned_until.min(until) - a.assigned_from.max(from))
+                                .num_seconds()
+                                .max(0)
+                        })
+                        .sum();
+                    (busy as f64 / (until - from).num_seconds() as f64).min(1.0)
+                })
+                .collect();
+            heatmap.insert(gate.gate_id.clone(), row);
+        }
+        heatmap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::{flight, gate};
+    use super::*;
+    use crate::domain::*;
+
+    #[test]
+    fn busy_buckets_follow_the_assignment() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-A2", "T5", AircraftSize::Medium));
+        // 09:00 to 11:15 with the turnaround buffer.
+        e.allocate_gate(&flight("F1", "A320", 9, 11), "LHR", &["T5-A1".into()]);
+
+        let heatmap = e.utilization_heatmap(60);
+        let row = &heatmap["T5-A1"];
+        assert_eq!(row.len(), 24);
+        assert_eq!(&row[8..12], [0.0, 1.0, 1.0, 0.25]);
+        assert_eq!(row.iter().sum::<f64>(), 2.25);
+        assert!(heatmap["T5-A2"].iter().all(|&f| f == 0.0));
+        assert!(e.utilization_heatmap(0).is_empty());
+    }
+}
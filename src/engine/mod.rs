@@ -1,23 +1,431 @@
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, Timelike, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fmt;
 use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::domain::*;
 
+pub use batch::{BatchOptions, BatchResult, UnplacedCause, UnplacedReport};
+pub use billing::{BillingSummary, Charge, Tariff};
+pub use changes::{AssignmentChange, AssignmentChangeListener};
+pub use contention::ContentionPeak;
+pub use replica::{StateDelta, StateDeltaListener};
+pub use snapshot::EngineSnapshot;
+pub use validate::{validate_registrations, TailConflict};
+
+mod batch;
+mod billing;
+mod changes;
+mod contention;
+mod gaps;
+mod heatmap;
+mod optimize;
+mod persist;
+mod replica;
+mod snapshot;
+#[cfg(test)]
+mod test_support;
+mod validate;
+
 /// Minimum clearance added after scheduled departure to account for deboarding and pushback.
 const TURNAROUND_BUFFER_MINUTES: i64 = 15;
+/// Turnaround buffer for ferry flights, which have no passengers to deboard.
+const FERRY_TURNAROUND_BUFFER_MINUTES: i64 = 5;
 /// Score penalty per size class when a gate is larger than the aircraft requires.
 const PENALTY_OVERSIZED_GATE: f64 = 10.0;
 /// Score penalty when preferred gates were specified but this gate is not among them.
 const PENALTY_PREFERRED_MISS: f64 = 5.0;
 /// Score reward (negative penalty) when the gate is on the caller's preferred list.
 const REWARD_PREFERRED_GATE: f64 = -3.0;
+/// Reward given up for each place a gate sits below the top of the preferred list, so an
+/// airline's first choice beats its second when both are free.
+const PREFERRED_RANK_STEP: f64 = 0.5;
+/// Score penalty when disruption re-allocation moves a flight out of its original terminal.  Set
+/// above the worst oversize-plus-preference penalty so any same-terminal gate wins.
+const PENALTY_TERMINAL_CHANGE: f64 = 30.0;
+/// Score penalty for a preferred gate withheld from an airline by batch fairness: the gate is
+/// only used when nothing else fits.
+const PENALTY_WITHHELD_GATE: f64 = 50.0;
+/// Penalty for a gate outside the baggage zone a flight is tagged with.
+const PENALTY_BAGGAGE_ZONE_MISS: f64 = 4.0;
+/// Passenger load at which a flight is indifferent between a contact gate and a remote stand.
+const PASSENGER_PIVOT: f64 = 150.0;
+/// Contact-gate penalty per passenger below `PASSENGER_PIVOT` (reward per passenger above it),
+/// so busy flights take the jet bridges and light ones leave them free.
+const CONTACT_GATE_PER_PASSENGER: f64 = 0.02;
+/// Penalty for putting passengers on a remote stand (bussed to the terminal).
+const PENALTY_REMOTE_STAND: f64 = 1.0;
+/// Penalty for a ferry flight taking a contact gate that passenger flights could use.
+const PENALTY_FERRY_CONTACT_GATE: f64 = 2.0;
+/// Default `EngineConfig::terminal_locality_penalty`: above one oversize step, so a slightly
+/// large gate at home beats a snug one elsewhere.
+const PENALTY_LEAVING_NATURAL_TERMINAL: f64 = 12.0;
+/// Default `EngineConfig::confirmed_move_penalty`: one confirmed flight is worth several
+/// provisional moves.
+const PENALTY_MOVING_CONFIRMED: f64 = 10.0;
+/// Reallocation cost of each size step a displaced flight loses when moved to a smaller gate.
+const COST_GATE_DOWNGRADE: f64 = 10.0;
+/// Reallocation cost of each minute an assignment's window moves.
+const COST_PER_MINUTE_SHIFTED: f64 = 1.0;
+
+/// Whether two half-open `[from, until)` windows share any instant.  Windows that merely touch
+/// (one ends exactly when the other starts) do not overlap.
+fn overlaps(a: (DateTime<Utc>, DateTime<Utc>), b: (DateTime<Utc>, DateTime<Utc>)) -> bool {
+    a.0 < b.1 && a.1 > b.0
+}
+
+/// Criteria for `AllocationEngine::disruptions_matching`; unset fields match every event.
+#[derive(Debug, Clone, Default)]
+pub struct DisruptionQuery {
+    /// Match any of these types; empty matches all.
+    pub types: Vec<DisruptionType>,
+    pub flight_id: Option<String>,
+    /// Reported at or after.
+    pub from: Option<DateTime<Utc>>,
+    /// Reported before.
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl DisruptionQuery {
+    pub fn matches(&self, event: &DisruptionEvent) -> bool {
+        (self.types.is_empty() || self.types.contains(&event.disruption_type))
+            && self
+                .flight_id
+                .as_ref()
+                .is_none_or(|id| &event.affected_flight_id == id)
+            && self.from.is_none_or(|from| event.reported_at >= from)
+            && self.until.is_none_or(|until| event.reported_at < until)
+    }
+}
+
+/// Weights of optional scoring terms, set via `EngineConfig::scoring`.  Every term is off by
+/// default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScoringConfig {
+    /// Penalty per unit by which the candidate terminal's occupancy (share of its gates in use
+    /// during the flight's window) exceeds the whole airport's, spreading movements away from
+    /// crowded aprons.  Terminals at or below average are not penalised.
+    pub terminal_balance_weight: Option<f64>,
+    /// Reward for the gate a recurring flight (same flight id) last used, taken from the most
+    /// recent archived assignment before its arrival, so passengers find it where they expect.
+    pub gate_stickiness_reward: Option<f64>,
+}
+
+/// What a delay does when it makes the aircraft depart after the next leg on the same
+/// registration is due back, set via `EngineConfig::tail_delay_policy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TailDelayPolicy {
+    /// Leave the downstream leg alone.
+    #[default]
+    Ignore,
+    /// Delay the downstream leg by the same amount, and so on down the tail.
+    Cascade,
+    /// Leave the downstream leg alone and report `DisruptionNote::TailRescheduleRequired`.
+    Flag,
+}
+
+/// Behavioural switches for the engine.  Most defaults reproduce the engine's original
+/// behaviour; three do not: `reject_past_windows` is on, `terminal_locality_penalty` keeps
+/// airlines in their natural terminal, and `confirmed_move_penalty` prefers moving provisional
+/// flights over confirmed ones.  Set those explicitly to get the original behaviour back.
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    /// When an airport is missing from the country table, treat flights touching it as
+    /// international (the conservative choice, since international gates also have border control).
+    pub unknown_airport_is_international: bool,
+    /// Refuse to commit an allocation whose best score is above this value, leaving the flight
+    /// unplaced for manual handling instead of parking it on a poor gate.
+    pub max_acceptable_score: Option<f64>,
+    /// Fraction of the preferred-gate advantage kept per active assignment the same airline
+    /// already holds on that gate (e.g. `0.5` halves it each time).  Spreads an airline across its
+    /// preferred gates instead of piling onto the first one.  `None` disables decay.
+    pub preferred_reward_decay: Option<f64>,
+    /// How many following flights on the same gate a delay may push back when the delayed
+    /// flight cannot be moved to another gate.  `0` disables knock-on delays.
+    pub max_cascade_depth: usize,
+    /// Penalty for placing a flight on a gate that favours the other passenger flow (see
+    /// `Gate::favours` and `Flight::dominant_role`).  `None` disables directional scoring.
+    pub directional_penalty: Option<f64>,
+    /// Extra minutes a gate stays blocked after a departure of the given size (jet blast,
+    /// fuelling residue), on top of the turnaround buffer.  Sizes not listed need none.
+    pub post_departure_cooldown: BTreeMap<AircraftSize, i64>,
+    /// Gate charges used by `billing_summary`.
+    pub tariff: Tariff,
+    /// Refuse allocations whose window has already ended by the operational clock.  Has no
+    /// effect until the clock is first advanced; turn off for historical backfill or replay.
+    pub reject_past_windows: bool,
+    /// Completed assignments kept in `AllocationEngine::history`; the oldest are dropped first.
+    pub history_limit: usize,
+    /// Refuse a flight whose preferred list names a gate it can never use (unknown, too small,
+    /// out of service or not permitted) instead of allocating elsewhere with a warning.
+    pub strict_preferences: bool,
+    /// Minutes that must separate consecutive assignments on one gate (or MARS sub-stand).  `0`
+    /// lets one flight's window end exactly when the next begins.
+    pub min_gap_minutes: i64,
+    /// Penalty for leaving a flight's natural terminal when the caller names no preferred gate:
+    /// the airline's leased terminal, or else the terminal holding most of its current
+    /// assignments.  `None` lets such flights go to any terminal.
+    pub terminal_locality_penalty: Option<f64>,
+    /// Refuse a disruption reported before the latest one already applied, instead of applying
+    /// it out of order.  Use `handle_disruptions` to apply a set of late arrivals in order.
+    pub strict_disruption_order: bool,
+    /// Put the outbound leg of a turnaround link on the gate its assigned inbound leg holds,
+    /// from the inbound's end to the outbound's, or refuse it if that gate cannot take it.
+    pub same_gate_turnarounds: bool,
+    /// Let `reinstate_gate` pull flights over from other terminals, not just the restored
+    /// gate's own.
+    pub reinstatement_crosses_terminals: bool,
+    /// Cost of moving a `Confirmed` assignment when resolving a delay conflict, against `1.0`
+    /// for each move.  When moving the provisional flights in the way costs less than moving
+    /// the delayed flight, they are moved instead.  `0.0` always moves the delayed flight.
+    pub confirmed_move_penalty: f64,
+    /// Refuse flights whose aircraft type is not a known designator (and that carry no
+    /// wingspan to size them by) instead of assuming a Medium gate will do.
+    pub strict_aircraft_types: bool,
+    /// Minutes before arrival inside which disruption handling avoids gate changes.  A delayed
+    /// flight arriving that soon keeps its gate and the flights in its way are moved instead,
+    /// whatever that costs; a move that happens anyway is flagged with
+    /// `DisruptionNote::ShortNoticeGateChange`.  `None` disables the rule.
+    pub gate_change_notice_minutes: Option<i64>,
+    pub scoring: ScoringConfig,
+    /// Handling of a delay that overruns the next leg flown by the same aircraft registration.
+    /// Flights without a registration are never checked.
+    pub tail_delay_policy: TailDelayPolicy,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            unknown_airport_is_international: true,
+            max_acceptable_score: None,
+            preferred_reward_decay: None,
+            max_cascade_depth: 0,
+            directional_penalty: None,
+            post_departure_cooldown: BTreeMap::new(),
+            tariff: Tariff::default(),
+            reject_past_windows: true,
+            history_limit: 10_000,
+            strict_preferences: false,
+            min_gap_minutes: 0,
+            terminal_locality_penalty: Some(PENALTY_LEAVING_NATURAL_TERMINAL),
+            strict_disruption_order: false,
+            same_gate_turnarounds: false,
+            reinstatement_crosses_terminals: false,
+            confirmed_move_penalty: PENALTY_MOVING_CONFIRMED,
+            strict_aircraft_types: false,
+            gate_change_notice_minutes: None,
+            scoring: ScoringConfig::default(),
+            tail_delay_policy: TailDelayPolicy::Ignore,
+        }
+    }
+}
 
 pub struct AllocationEngine {
     pub gates: Vec<Gate>,
     pub assignments: Vec<GateAssignment>,
     pub disruptions: Vec<DisruptionEvent>,
+    pub reservations: Vec<GateReservation>,
+    pub config: EngineConfig,
+    /// IATA airport code -> country code, used to classify flights as domestic/international.
+    pub airport_countries: HashMap<String, String>,
+    pub leases: Vec<GateLease>,
+    /// Terminal -> daily curfew during which no gate in that terminal may be occupied.
+    pub terminal_curfews: HashMap<String, Curfew>,
+    /// Aircraft type (upper-case) -> daily noise-quota window during which that type may not
+    /// occupy any gate.
+    pub night_restricted_types: HashMap<String, Curfew>,
+    /// Operating airline -> most gates it may hold at the same time (contractual limit).
+    pub airline_caps: HashMap<String, usize>,
+    /// Operational clock, moved forward by `advance_to` / `tick`.  `None` until first advanced.
+    pub clock: Option<DateTime<Utc>>,
+    /// Terminal -> ordered overflow terminals tried when the terminal itself has no gate.
+    pub overflow_chains: HashMap<String, Vec<String>>,
+    /// Adjacent gates whose departures must not push back at the same time.
+    pub pushback_conflicts: Vec<PushbackConflict>,
+    /// Inbound flight id -> the outbound flight operated by the same aircraft.
+    pub turnaround_links: HashMap<String, String>,
+    /// Physically adjacent gate pairs that can be combined for double-stand operations.
+    pub adjacent_gates: Vec<(String, String)>,
+    /// Charges for gate uses that have completed and left `assignments`.
+    pub completed_charges: Vec<Charge>,
+    /// Assignments that completed and left `assignments`, oldest first, up to
+    /// `EngineConfig::history_limit`.
+    pub history: VecDeque<GateAssignment>,
+    /// Airport used by `allocate` when the caller does not name one.
+    pub default_airport: Option<String>,
+    /// Disruptions that left flights unplaced or stuck (locked, or past the cascade limit),
+    /// with those flights, oldest first, until a dispatcher clears them.
+    pub pending_manual: Vec<(DisruptionEvent, Vec<Flight>)>,
+    gate_freed_listeners: Vec<GateFreedListener>,
+    change_listeners: Vec<AssignmentChangeListener>,
+    delta_listeners: Vec<StateDeltaListener>,
+    /// Source of assignment and reservation ids; `None` uses random v4 UUIDs.
+    id_generator: Option<IdGenerator>,
+    /// Set while a tracked operation runs, so nested operations are reported once.
+    tracking_changes: bool,
+}
+
+/// Id source installed with `AllocationEngine::set_id_generator`.
+pub type IdGenerator = Box<dyn FnMut() -> Uuid + Send>;
+
+/// A generator yielding `00000000-0000-0000-0000-000000000001`, `...0002` and so on, for
+/// reproducible ids in tests and replays.
+pub fn sequential_ids() -> IdGenerator {
+    let mut next = 0u128;
+    Box::new(move || {
+        next += 1;
+        Uuid::from_u128(next)
+    })
+}
+
+/// Callback registered via `AllocationEngine::on_gate_freed`.
+pub type GateFreedListener = Box<dyn Fn(&GateFreed) + Send>;
+
+/// Notification that an assignment ran to completion and its gate is free again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GateFreed {
+    pub gate_id: String,
+    pub terminal: String,
+    pub flight_id: String,
+    pub assignment_id: Uuid,
+    pub freed_at: DateTime<Utc>,
+}
+
+/// Why an allocation was not committed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AllocationError {
+    /// The engine has no gates at all: a setup error rather than a capacity problem.
+    NoGatesRegistered { airport: String },
+    /// The flight is cancelled, diverted or already departed, so it needs no gate.
+    InactiveFlight { status: FlightStatus },
+    /// The requested window ended before the operational clock's current time.
+    WindowInPast {
+        until: DateTime<Utc>,
+        now: DateTime<Utc>,
+    },
+    /// No gate passed the size, availability and conflict filters.
+    NoCompatibleGate,
+    /// A gate was found but its score exceeds `EngineConfig::max_acceptable_score`.
+    ScoreTooHigh {
+        gate_id: String,
+        score: f64,
+        threshold: f64,
+    },
+    /// Gates were free, but only in terminals whose curfew overlaps the flight's window.
+    TerminalCurfew { terminals: Vec<String> },
+    /// The aircraft type is banned from the airport's gates during night hours that overlap the
+    /// flight's window.
+    NightRestricted { aircraft_type: String },
+    /// The operating airline already holds as many gates as it may at once during the window.
+    AirlineCapReached { airline: String, cap: usize },
+    /// Under `EngineConfig::strict_aircraft_types`, the type cannot be sized.
+    UnknownAircraftType { aircraft_type: String },
+    /// The gate named in `try_allocate_at` cannot take the flight in the requested window.
+    GateRefused {
+        gate_id: String,
+        reason: GateRefusal,
+    },
+}
+
+/// Why a specific gate was refused for a specific window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateRefusal {
+    UnknownGate,
+    /// The window ends before it starts.
+    InvalidWindow,
+    TooSmall,
+    OutOfService,
+    /// The window is shorter or longer than the gate may be booked for.
+    OccupancyLimit,
+    /// A planned closure overlaps the window.
+    Closed,
+    /// Part of the window falls outside the gate's staffed shifts.
+    Unstaffed,
+    /// Another assignment or a reservation overlaps the window.
+    Occupied,
+    /// A neighbouring gate's departure pushes back too close to this one.
+    PushbackClash,
+    /// The flight needs a double stand and no gate adjacent to this one is free.
+    NoAdjacentPair,
+    /// Aircraft-type restriction, gate class or lease rules out the flight.
+    NotPermitted,
+    TerminalCurfew,
+}
+
+impl From<RejectionReason> for GateRefusal {
+    fn from(reason: RejectionReason) -> Self {
+        match reason {
+            RejectionReason::TooSmall => Self::TooSmall,
+            RejectionReason::OutOfService => Self::OutOfService,
+            RejectionReason::OccupancyLimit => Self::OccupancyLimit,
+            RejectionReason::Closed => Self::Closed,
+            RejectionReason::Unstaffed => Self::Unstaffed,
+            RejectionReason::TypeExcluded
+            | RejectionReason::DoorMismatch
+            | RejectionReason::TypeDesignatedElsewhere
+            | RejectionReason::ClassMismatch
+            | RejectionReason::NotLeased => Self::NotPermitted,
+        }
+    }
+}
+
+impl fmt::Display for GateRefusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Self::UnknownGate => "unknown gate",
+            Self::InvalidWindow => "window ends before it starts",
+            Self::TooSmall => "gate too small for aircraft",
+            Self::OutOfService => "gate out of service",
+            Self::OccupancyLimit => "window outside gate occupancy limits",
+            Self::Closed => "gate closed during window",
+            Self::Unstaffed => "gate not staffed during window",
+            Self::Occupied => "gate occupied during window",
+            Self::PushbackClash => "pushback clashes with a neighbouring gate",
+            Self::NoAdjacentPair => "no free adjacent gate for double stand",
+            Self::NotPermitted => "flight not permitted at gate",
+            Self::TerminalCurfew => "window falls in terminal curfew",
+        };
+        f.write_str(text)
+    }
+}
+
+impl fmt::Display for AllocationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoGatesRegistered { airport } => write!(f, "no gates registered for {}", airport),
+            Self::InactiveFlight { status } => write!(f, "flight is {}", status),
+            Self::WindowInPast { until, now } => write!(
+                f,
+                "window ended {} before now ({})",
+                until.format("%H:%M"),
+                now.format("%H:%M")
+            ),
+            Self::NoCompatibleGate => write!(f, "no compatible gate"),
+            Self::ScoreTooHigh {
+                gate_id,
+                score,
+                threshold,
+            } => write!(
+                f,
+                "best gate {} scored {:.1}, above the acceptable {:.1}",
+                gate_id, score, threshold
+            ),
+            Self::TerminalCurfew { terminals } => {
+                write!(f, "window falls in curfew at {}", terminals.join(", "))
+            }
+            Self::NightRestricted { aircraft_type } => {
+                write!(f, "{} is restricted at night", aircraft_type)
+            }
+            Self::AirlineCapReached { airline, cap } => {
+                write!(f, "{} already holds its cap of {} gate(s)", airline, cap)
+            }
+            Self::UnknownAircraftType { aircraft_type } => {
+                write!(f, "unknown aircraft type {}", aircraft_type)
+            }
+            Self::GateRefused { gate_id, reason } => write!(f, "gate {}: {}", gate_id, reason),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -25,13 +433,163 @@ pub struct AllocationResult {
     pub success: bool,
     pub assignment: Option<GateAssignment>,
     pub message: String,
+    pub error: Option<AllocationError>,
+    /// Set when the flight's home terminal was full and it spilled into this overflow terminal.
+    pub overflow_terminal: Option<String>,
+    /// Preferred gates the flight could never have used, and why.
+    pub infeasible_preferred: Vec<(String, GateRefusal)>,
+}
+
+impl AllocationResult {
+    pub(crate) fn failed(error: AllocationError, message: String) -> Self {
+        Self {
+            success: false,
+            assignment: None,
+            message,
+            error: Some(error),
+            overflow_terminal: None,
+            infeasible_preferred: Vec::new(),
+        }
+    }
+}
+
+/// Situational score adjustments on top of `score_gate`, used by re-allocation and batches.
+#[derive(Debug, Default, Clone, Copy)]
+struct PlanBias<'a> {
+    /// Terminal the flight was already in; other terminals are penalised.
+    original_terminal: Option<&'a str>,
+    /// Gates to use only as a last resort.
+    withheld: &'a [String],
+}
+
+/// A gate choice made by `plan_allocation` that has not been committed yet.
+#[derive(Debug, Clone)]
+pub struct AllocationPlan {
+    pub flight: Flight,
+    pub airport: String,
+    pub preferred: Vec<String>,
+    pub gate_id: String,
+    pub score: f64,
+    pub from: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub overflow_terminal: Option<String>,
+    /// Second gate held alongside `gate_id` for a double-stand flight.
+    pub partner_gate: Option<String>,
+    /// Preferred gates ruled out whatever the schedule, reported on the result.
+    pub infeasible_preferred: Vec<(String, GateRefusal)>,
+}
+
+/// The components of a gate's base score; lower totals are better.  Situational penalties
+/// applied during re-allocation and batches (terminal change, withheld gates) are not included.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScoreBreakdown {
+    /// Penalty for each size step the gate is larger than the aircraft needs.
+    pub oversize: f64,
+    /// Reward for a preferred gate (after decay), or penalty for missing every preferred gate.
+    pub preference: f64,
+    /// Penalty for a gate kept for the other passenger flow.
+    pub direction: f64,
+    /// Penalty for a gate outside the flight's baggage zone.
+    pub baggage: f64,
+    /// Contact-gate penalty for a lightly loaded flight, or reward for a busy one; remote-stand
+    /// penalty for passenger flights; contact-gate penalty for ferry flights.
+    pub passengers: f64,
+    /// Penalty for a terminal busier than average (`ScoringConfig::terminal_balance_weight`).
+    pub balance: f64,
+    /// Reward for the gate the flight last used (`ScoringConfig::gate_stickiness_reward`).
+    pub stickiness: f64,
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> f64 {
+        self.oversize
+            + self.preference
+            + self.direction
+            + self.baggage
+            + self.passengers
+            + self.balance
+            + self.stickiness
+    }
 }
 
 #[derive(Debug)]
 pub struct DisruptionResult {
     pub acknowledged: bool,
     pub reassignments: Vec<GateAssignment>,
+    /// Prior state of every assignment the disruption changed or removed, in schedule order
+    /// (window start), for before/after views.  A window shifted in place keeps its
+    /// `assignment_id` in `reassignments`; a re-allocated one gets a new id and can only be
+    /// matched by flight id, with one entry per gate for a double-stand flight.
+    pub previous: Vec<GateAssignment>,
     pub summary: String,
+    pub notes: Vec<DisruptionNote>,
+    /// How disruptive the outcome was: terminal changes, gate downgrades and minutes shifted,
+    /// weighted and summed over every assignment the disruption moved.  Lower is better.
+    pub estimated_reallocation_cost: f64,
+}
+
+/// Something the operator should know about a disruption that was only partly handled.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisruptionNote {
+    /// A knock-on delay chain was longer than `EngineConfig::max_cascade_depth` (or hit a locked
+    /// assignment).  The chain was rolled back; these flights still need a manual decision.
+    CascadeLimitReached { unresolved: Vec<String> },
+    /// A flight was moved to another gate less than `EngineConfig::gate_change_notice_minutes`
+    /// before its arrival.
+    ShortNoticeGateChange {
+        flight_id: String,
+        from_gate: String,
+        to_gate: String,
+        minutes_to_arrival: i64,
+    },
+    /// A delay makes `flight_id` depart `overrun_minutes` after the next leg on its
+    /// registration is due, so the tail's schedule needs rebuilding.
+    TailRescheduleRequired {
+        registration: String,
+        flight_id: String,
+        next_flight_id: String,
+        overrun_minutes: i64,
+    },
+}
+
+impl fmt::Display for DisruptionNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CascadeLimitReached { unresolved } => {
+                write!(
+                    f,
+                    "cascade limit reached, unresolved: {}",
+                    unresolved.join(", ")
+                )
+            }
+            Self::ShortNoticeGateChange {
+                flight_id,
+                from_gate,
+                to_gate,
+                minutes_to_arrival,
+            } => write!(
+                f,
+                "short-notice gate change: {} {} -> {}, {}min before arrival",
+                flight_id, from_gate, to_gate, minutes_to_arrival
+            ),
+            Self::TailRescheduleRequired {
+                registration,
+                flight_id,
+                next_flight_id,
+                overrun_minutes,
+            } => write!(
+                f,
+                "tail {} needs rescheduling: {} now departs {}min after {} is due",
+                registration, flight_id, overrun_minutes, next_flight_id
+            ),
+        }
+    }
+}
+
+impl Default for AllocationEngine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AllocationEngine {
@@ -40,354 +598,4893 @@ impl AllocationEngine {
             gates: Vec::new(),
             assignments: Vec::new(),
             disruptions: Vec::new(),
+            reservations: Vec::new(),
+            config: EngineConfig::default(),
+            airport_countries: AIRPORT_COUNTRIES
+                .iter()
+                .map(|&(airport, country)| (airport.to_string(), country.to_string()))
+                .collect(),
+            leases: Vec::new(),
+            terminal_curfews: HashMap::new(),
+            night_restricted_types: HashMap::new(),
+            airline_caps: HashMap::new(),
+            clock: None,
+            overflow_chains: HashMap::new(),
+            pushback_conflicts: Vec::new(),
+            turnaround_links: HashMap::new(),
+            adjacent_gates: Vec::new(),
+            completed_charges: Vec::new(),
+            history: VecDeque::new(),
+            default_airport: None,
+            pending_manual: Vec::new(),
+            gate_freed_listeners: Vec::new(),
+            change_listeners: Vec::new(),
+            delta_listeners: Vec::new(),
+            id_generator: None,
+            tracking_changes: false,
+        }
+    }
+
+    pub fn with_config(config: EngineConfig) -> Self {
+        Self {
+            config,
+            ..Self::new()
         }
     }
 
+    /// Set the airport `allocate` records assignments at, for single-airport deployments.
+    pub fn with_default_airport(mut self, iata: &str) -> Self {
+        self.default_airport = Some(iata.to_uppercase());
+        self
+    }
+
+    /// Generate assignment and reservation ids with `generator` instead of random v4 UUIDs.
+    pub fn set_id_generator(&mut self, generator: IdGenerator) {
+        self.id_generator = Some(generator);
+    }
+
+    fn next_id(&mut self) -> Uuid {
+        self.id_generator
+            .as_mut()
+            .map_or_else(Uuid::new_v4, |g| g())
+    }
+
     pub fn add_gate(&mut self, gate: Gate) {
         info!(gate_id = %gate.gate_id, terminal = %gate.terminal, "Gate registered");
-        self.gates.push(gate);
+        self.tracked(|e| e.gates.push(gate));
     }
 
-    /// Allocate the best available gate for a flight using constraint-based scoring.
-    pub fn allocate_gate(
-        &mut self,
-        flight: &Flight,
-        airport: &str,
-        preferred: &[String],
-    ) -> AllocationResult {
-        info!(flight = %flight.flight_id, aircraft = %flight.aircraft_type, airport, "Attempting allocation");
-
-        let size = flight.aircraft_size();
-        let need_from = flight.scheduled_arrival;
-        let need_until = flight.scheduled_departure + Duration::minutes(TURNAROUND_BUFFER_MINUTES);
+    /// Replace an existing gate with `gate`, keeping its scheduled closures, and return the gate
+    /// as stored.  Copies held by assignments are refreshed.  Refused if the new gate would not
+    /// accept a flight already assigned to it for that flight's window.
+    pub fn update_gate(&mut self, gate: Gate) -> Result<Gate, String> {
+        self.tracked(|e| e.replace_gate(gate))
+    }
 
-        let mut candidates: Vec<(&Gate, f64)> = self
+    fn replace_gate(&mut self, mut gate: Gate) -> Result<Gate, String> {
+        let idx = self
             .gates
             .iter()
-            .filter(|g| g.is_available && g.can_accommodate(size))
-            .filter(|g| !self.has_conflict(&g.gate_id, need_from, need_until))
-            .map(|g| (g, self.score_gate(g, size, preferred)))
+            .position(|g| g.gate_id == gate.gate_id)
+            .ok_or_else(|| format!("Unknown gate {}", gate.gate_id))?;
+        gate.unavailable_windows = self.gates[idx].unavailable_windows.clone();
+        // Leases are not re-checked: an assignment may sit in an overflow terminal.
+        let refused: Vec<String> = self
+            .assignments
+            .iter()
+            .filter(|a| a.gate.gate_id == gate.gate_id)
+            .filter_map(|a| {
+                let window = Some((a.assigned_from, a.assigned_until));
+                gate.accepts(&a.flight, &self.gate_context(&a.flight, window, true))
+                    .err()
+                    .map(|reason| format!("{} ({})", a.flight.flight_id, reason))
+            })
             .collect();
+        if !refused.is_empty() {
+            return Err(format!(
+                "Gate {} would refuse {} active assignment(s): {}",
+                gate.gate_id,
+                refused.len(),
+                refused.join(", ")
+            ));
+        }
+        self.gates[idx] = gate.clone();
+        for a in self
+            .assignments
+            .iter_mut()
+            .filter(|a| a.gate.gate_id == gate.gate_id)
+        {
+            a.gate = gate.clone();
+        }
+        info!(%gate, "Gate updated");
+        Ok(gate)
+    }
 
-        candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+    /// Take a gate out of the pool.  Refused while assignments or reservations still use it;
+    /// pushback and adjacency relationships involving the gate are dropped with it.
+    pub fn remove_gate(&mut self, gate_id: &str) -> Result<Gate, String> {
+        self.tracked(|e| e.drop_gate(gate_id))
+    }
 
-        match candidates.first() {
-            Some((gate, score)) => {
-                let assignment = GateAssignment {
-                    assignment_id: Uuid::new_v4(),
-                    flight: flight.clone(),
-                    gate: (*gate).clone(),
-                    assigned_from: need_from,
-                    assigned_until: need_until,
-                };
-                info!(flight = %flight.flight_id, gate = %gate.gate_id, score, "Allocated");
-                self.assignments.push(assignment.clone());
-                AllocationResult {
-                    success: true,
-                    assignment: Some(assignment),
-                    message: format!(
-                        "Allocated {} -> {} (score: {:.1})",
-                        flight.flight_id, gate.gate_id, score
-                    ),
-                }
-            }
-            None => {
-                warn!(flight = %flight.flight_id, "No available gates");
-                AllocationResult {
-                    success: false,
-                    assignment: None,
-                    message: format!(
-                        "No compatible gate for {} ({}) at {}",
-                        flight.flight_id, flight.aircraft_type, airport
-                    ),
-                }
-            }
+    fn drop_gate(&mut self, gate_id: &str) -> Result<Gate, String> {
+        let idx = self
+            .gates
+            .iter()
+            .position(|g| g.gate_id == gate_id)
+            .ok_or_else(|| format!("Unknown gate {}", gate_id))?;
+        let active = self
+            .assignments
+            .iter()
+            .filter(|a| a.gate.gate_id == gate_id)
+            .count();
+        let held = self
+            .reservations
+            .iter()
+            .filter(|r| r.gate_id == gate_id)
+            .count();
+        if active + held > 0 {
+            return Err(format!(
+                "Gate {} has {} active assignment(s) and {} reservation(s)",
+                gate_id, active, held
+            ));
         }
+        self.pushback_conflicts
+            .retain(|c| c.gate_a != gate_id && c.gate_b != gate_id);
+        self.adjacent_gates
+            .retain(|(a, b)| a != gate_id && b != gate_id);
+        let removed = self.gates.remove(idx);
+        info!(gate_id, "Gate removed");
+        Ok(removed)
     }
 
-    /// Returns true if any existing assignment on `gate_id` overlaps the half-open interval
-    /// `[from, until)`.  Two windows overlap when neither ends before the other starts.
-    fn has_conflict(&self, gate_id: &str, from: DateTime<Utc>, until: DateTime<Utc>) -> bool {
-        self.assignments.iter().any(|a| {
-            a.gate.gate_id == gate_id && a.assigned_from < until && a.assigned_until > from
+    /// Remove assignments whose gate is no longer registered (e.g. the gate list was edited
+    /// directly or a gate was renamed) and return them so their flights can be re-allocated.
+    pub fn reap_orphans(&mut self) -> Vec<GateAssignment> {
+        self.tracked(|e| {
+            let (orphans, kept): (Vec<GateAssignment>, Vec<GateAssignment>) = e
+                .assignments
+                .drain(..)
+                .partition(|a| !e.gates.iter().any(|g| g.gate_id == a.gate.gate_id));
+            e.assignments = kept;
+            for a in &orphans {
+                warn!(flight = %a.flight.flight_id, gate = %a.gate.gate_id, "Reaped orphaned assignment");
+            }
+            orphans
         })
     }
 
-    /// Compute a score for `gate`; lower is better.  The algorithm penalises oversized gates
-    /// (waste of capacity) and gates outside the caller's preferred list, and rewards preferred ones.
-    fn score_gate(&self, gate: &Gate, aircraft_size: AircraftSize, preferred: &[String]) -> f64 {
-        let mut score = 0.0;
-        let size_diff = (gate.size as i32) - (aircraft_size as i32);
-        if size_diff > 0 {
-            score += PENALTY_OVERSIZED_GATE * size_diff as f64;
-        }
-        if !preferred.is_empty() {
-            if preferred.contains(&gate.gate_id) {
-                score += REWARD_PREFERRED_GATE;
-            } else {
-                score += PENALTY_PREFERRED_MISS;
-            }
+    /// Register a planned closure for `gate_id` over `[from, until)`.  Unlike a reactive
+    /// `GateUnavailable` disruption, the gate stays in service outside the window and existing
+    /// assignments are left untouched.
+    pub fn schedule_closure(
+        &mut self,
+        gate_id: &str,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<(), String> {
+        if until <= from {
+            return Err(format!("Closure for {} ends before it starts", gate_id));
         }
-        score
+        self.tracked(|e| {
+            let gate = e
+                .gates
+                .iter_mut()
+                .find(|g| g.gate_id == gate_id)
+                .ok_or_else(|| format!("Unknown gate {}", gate_id))?;
+            gate.unavailable_windows.push((from, until));
+            info!(gate_id, %from, %until, "Closure scheduled");
+            Ok(())
+        })
     }
 
-    /// Handle a disruption event with automatic re-allocation.
-    pub fn handle_disruption(&mut self, event: DisruptionEvent) -> DisruptionResult {
-        info!(event = %event.event_id, kind = %event.disruption_type, flight = %event.affected_flight_id, "Disruption");
-        self.disruptions.push(event.clone());
-        let mut reassignments = Vec::new();
+    /// Planned closures registered for `gate_id`; empty for unknown gates.
+    pub fn closures(&self, gate_id: &str) -> &[(DateTime<Utc>, DateTime<Utc>)] {
+        self.gates
+            .iter()
+            .find(|g| g.gate_id == gate_id)
+            .map(|g| g.unavailable_windows.as_slice())
+            .unwrap_or(&[])
+    }
 
-        match event.disruption_type {
-            DisruptionType::Delay => {
-                let delay = Duration::minutes(event.delay_minutes as i64);
-                let indices: Vec<usize> = self
-                    .assignments
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, a)| a.flight.flight_id == event.affected_flight_id)
-                    .map(|(i, _)| i)
-                    .collect();
+    /// Hold `gate_id` over `[from, until)` without a concrete flight.  Fails if the gate is
+    /// unknown or the window is already booked or held.
+    pub fn reserve_gate(
+        &mut self,
+        gate_id: &str,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+        reason: &str,
+    ) -> Result<Uuid, String> {
+        if until <= from {
+            return Err(format!("Reservation for {} ends before it starts", gate_id));
+        }
+        if !self.gates.iter().any(|g| g.gate_id == gate_id) {
+            return Err(format!("Unknown gate {}", gate_id));
+        }
+        if self.has_conflict(gate_id, from, until) {
+            return Err(format!(
+                "Gate {} is already occupied in that window",
+                gate_id
+            ));
+        }
+        let reservation = GateReservation {
+            reservation_id: self.next_id(),
+            gate_id: gate_id.to_string(),
+            from,
+            until,
+            reason: reason.to_string(),
+        };
+        info!(%reservation, "Gate reserved");
+        let id = reservation.reservation_id;
+        self.tracked(|e| e.reservations.push(reservation));
+        Ok(id)
+    }
 
-                for &idx in &indices {
-                    let new_from = self.assignments[idx].assigned_from + delay;
-                    let new_until = self.assignments[idx].assigned_until + delay;
-                    let gate_id = self.assignments[idx].gate.gate_id.clone();
-                    let flight_clone = self.assignments[idx].flight.clone();
+    /// Reserve every gate in `gate_ids` over `[from, until)`, e.g. for a state visit.  All or
+    /// nothing: if any gate is unknown, listed twice or occupied, no reservation is made and
+    /// every offending gate is reported.  Returns the reservation ids in `gate_ids` order.
+    pub fn reserve_block(
+        &mut self,
+        gate_ids: &[String],
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+        reason: &str,
+    ) -> Result<Vec<Uuid>, String> {
+        if gate_ids.is_empty() {
+            return Err("Empty gate block".to_string());
+        }
+        if until <= from {
+            return Err("Block reservation ends before it starts".to_string());
+        }
+        let problems: Vec<String> = gate_ids
+            .iter()
+            .enumerate()
+            .filter_map(|(i, id)| {
+                if gate_ids[..i].contains(id) {
+                    Some(format!("{} listed twice", id))
+                } else if !self.gates.iter().any(|g| &g.gate_id == id) {
+                    Some(format!("unknown gate {}", id))
+                } else if self.has_conflict(id, from, until) {
+                    Some(format!("{} occupied", id))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        if !problems.is_empty() {
+            warn!(problems = ?problems, "Block reservation refused");
+            return Err(format!("Cannot reserve block: {}", problems.join(", ")));
+        }
+        gate_ids
+            .iter()
+            .map(|id| self.reserve_gate(id, from, until, reason))
+            .collect()
+    }
 
-                    let conflict = self.assignments.iter().enumerate().any(|(i, other)| {
-                        i != idx
-                            && other.gate.gate_id == gate_id
-                            && other.assigned_from < new_until
-                            && other.assigned_until > new_from
-                    });
+    /// Release the reservations made by `reserve_block`, returning those that still existed.
+    pub fn release_block(&mut self, reservation_ids: &[Uuid]) -> Vec<GateReservation> {
+        reservation_ids
+            .iter()
+            .filter_map(|&id| self.release_reservation(id))
+            .collect()
+    }
 
-                    if conflict {
-                        info!(flight = %flight_clone.flight_id, gate = %gate_id, "Delay conflict - re-allocating");
-                        let mut shifted = flight_clone;
-                        shifted.scheduled_arrival += delay;
-                        shifted.scheduled_departure += delay;
-                        shifted.status = FlightStatus::Delayed;
-                        if let Some(new_a) = self
-                            .allocate_gate(&shifted, &shifted.destination, &[gate_id])
-                            .assignment
-                        {
-                            reassignments.push(new_a);
-                        }
-                    } else {
-                        let a = &mut self.assignments[idx];
-                        a.assigned_from = new_from;
-                        a.assigned_until = new_until;
-                        a.flight.status = FlightStatus::Delayed;
-                        info!(flight = %a.flight.flight_id, gate = %a.gate.gate_id, "Window shifted");
-                        reassignments.push(a.clone());
-                    }
-                }
-                DisruptionResult {
-                    acknowledged: true,
-                    reassignments,
-                    summary: format!(
-                        "{} delayed {}min - {} assignment(s) adjusted",
-                        event.affected_flight_id,
-                        event.delay_minutes,
-                        indices.len()
-                    ),
-                }
-            }
-            DisruptionType::Cancellation => {
-                let before = self.assignments.len();
-                self.assignments
-                    .retain(|a| a.flight.flight_id != event.affected_flight_id);
-                let freed = before - self.assignments.len();
-                info!(flight = %event.affected_flight_id, freed, "Cancelled - gates freed");
-                DisruptionResult {
-                    acknowledged: true,
-                    reassignments: Vec::new(),
-                    summary: format!(
-                        "{} cancelled - {} gate(s) freed",
-                        event.affected_flight_id, freed
-                    ),
-                }
+    /// Drop a reservation, returning it if it existed.
+    pub fn release_reservation(&mut self, reservation_id: Uuid) -> Option<GateReservation> {
+        let idx = self
+            .reservations
+            .iter()
+            .position(|r| r.reservation_id == reservation_id)?;
+        let released = self.tracked(|e| e.reservations.remove(idx));
+        info!(reservation = %released, "Reservation released");
+        Some(released)
+    }
+
+    /// Mark a provisional assignment as confirmed by a dispatcher.
+    pub fn confirm_assignment(&mut self, assignment_id: Uuid) -> Result<(), String> {
+        self.tracked(|e| {
+            let a = e.assignment_mut(assignment_id)?;
+            if a.is_locked() {
+                return Err(format!("Assignment {} is locked", assignment_id));
             }
-            DisruptionType::GateUnavailable => {
-                // The gate identifier is carried in `description` (e.g. "T5-A1").
-                let gate_id = event.description.clone();
-                let affected: Vec<Flight> = self
-                    .assignments
-                    .iter()
-                    .filter(|a| a.gate.gate_id == gate_id)
-                    .map(|a| a.flight.clone())
-                    .collect();
+            a.status = AssignmentStatus::Confirmed;
+            info!(flight = %a.flight.flight_id, gate = %a.gate.gate_id, "Assignment confirmed");
+            Ok(())
+        })
+    }
 
-                if let Some(g) = self.gates.iter_mut().find(|g| g.gate_id == gate_id) {
-                    g.is_available = false;
-                }
-                self.assignments.retain(|a| a.gate.gate_id != gate_id);
+    /// Lock an assignment so automatic re-allocation and optimisation never move it.
+    pub fn lock_assignment(&mut self, assignment_id: Uuid) -> Result<(), String> {
+        self.tracked(|e| {
+            let a = e.assignment_mut(assignment_id)?;
+            a.status = AssignmentStatus::Locked;
+            info!(flight = %a.flight.flight_id, gate = %a.gate.gate_id, "Assignment locked");
+            Ok(())
+        })
+    }
 
-                for flight in &affected {
-                    if let Some(a) = self
-                        .allocate_gate(flight, &flight.destination, &[])
-                        .assignment
-                    {
-                        reassignments.push(a);
-                    } else {
-                        warn!(flight = %flight.flight_id, "Re-allocation failed after gate loss");
-                    }
-                }
-                DisruptionResult {
-                    acknowledged: true,
-                    reassignments,
-                    summary: format!(
-                        "Gate {} unavailable - {} flight(s) re-allocated",
-                        gate_id,
-                        affected.len()
-                    ),
-                }
-            }
-            // Diversion removes the flight's assignment (it is landing elsewhere).
-            // Weather and Mechanical are informational — no gate changes are made here;
-            // the operator handles them manually via subsequent AllocateGate / ReportDisruption calls.
-            _ => {
-                if event.disruption_type == DisruptionType::Diversion {
-                    self.assignments
-                        .retain(|a| a.flight.flight_id != event.affected_flight_id);
-                }
-                DisruptionResult {
-                    acknowledged: true,
-                    reassignments: Vec::new(),
-                    summary: format!(
-                        "{} event for {}",
-                        event.disruption_type, event.affected_flight_id
-                    ),
-                }
-            }
+    /// Attach a free-text note to an assignment, e.g. "VIP handling".
+    pub fn annotate_assignment(&mut self, assignment_id: Uuid, note: &str) -> Result<(), String> {
+        let note = note.trim();
+        if note.is_empty() {
+            return Err("Empty note".to_string());
         }
+        self.tracked(|e| {
+            let a = e.assignment_mut(assignment_id)?;
+            a.notes.push(note.to_string());
+            info!(flight = %a.flight.flight_id, gate = %a.gate.gate_id, note, "Assignment annotated");
+            Ok(())
+        })
     }
 
-    pub fn get_assignments(&self, terminal: Option<&str>) -> Vec<&GateAssignment> {
+    fn assignment_mut(&mut self, assignment_id: Uuid) -> Result<&mut GateAssignment, String> {
         self.assignments
-            .iter()
-            .filter(|a| terminal.map(|t| a.gate.terminal == t).unwrap_or(true))
-            .collect()
+            .iter_mut()
+            .find(|a| a.assignment_id == assignment_id)
+            .ok_or_else(|| format!("Unknown assignment {}", assignment_id))
     }
 
-    pub fn stats(&self) -> EngineStats {
-        EngineStats {
-            total_gates: self.gates.len(),
-            available_gates: self.gates.iter().filter(|g| g.is_available).count(),
-            occupied_gates: self.assignments.len(),
-            total_disruptions: self.disruptions.len(),
-        }
+    /// Register a callback invoked for every assignment that `advance_to` expires.
+    pub fn on_gate_freed(&mut self, listener: GateFreedListener) {
+        self.gate_freed_listeners.push(listener);
     }
-}
 
-#[derive(Debug)]
-pub struct EngineStats {
-    pub total_gates: usize,
-    pub available_gates: usize,
-    pub occupied_gates: usize,
-    pub total_disruptions: usize,
-}
+    /// Move the operational clock to `now`, removing every assignment whose window has ended and
+    /// notifying `on_gate_freed` listeners.  Moving the clock backwards is a no-op.
+    pub fn advance_to(&mut self, now: DateTime<Utc>) -> Vec<GateFreed> {
+        self.tracked(|e| e.expire(now))
+    }
 
-impl fmt::Display for EngineStats {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "Gates: {}/{} available | Assignments: {} | Disruptions: {}",
-            self.available_gates, self.total_gates, self.occupied_gates, self.total_disruptions
+    fn expire(&mut self, now: DateTime<Utc>) -> Vec<GateFreed> {
+        if self.clock.is_some_and(|c| now < c) {
+            return Vec::new();
+        }
+        self.clock = Some(now);
+
+        let (expired, active): (Vec<GateAssignment>, Vec<GateAssignment>) = self
+            .assignments
+            .drain(..)
+            .partition(|a| a.assigned_until <= now);
+        self.assignments = active;
+        for a in &expired {
+            let charge = self.charge_for(a);
+            self.completed_charges.push(charge);
+            self.history.push_back(a.clone());
+        }
+        let overflow = self.history.len().saturating_sub(self.config.history_limit);
+        self.history.drain(..overflow);
+
+        let freed: Vec<GateFreed> = expired
+            .into_iter()
+            .map(|a| GateFreed {
+                gate_id: a.gate.gate_id,
+                terminal: a.gate.terminal,
+                flight_id: a.flight.flight_id,
+                assignment_id: a.assignment_id,
+                freed_at: a.assigned_until,
+            })
+            .collect();
+        for event in &freed {
+            info!(gate = %event.gate_id, flight = %event.flight_id, "Gate freed");
+            for listener in &self.gate_freed_listeners {
+                listener(event);
+            }
+        }
+        freed
+    }
+
+    /// Archived assignments of `flight_id`, oldest first.
+    pub fn history_for(&self, flight_id: &str) -> Vec<&GateAssignment> {
+        self.history
+            .iter()
+            .filter(|a| a.flight.flight_id == flight_id)
+            .collect()
+    }
+
+    /// Gate of the latest archived assignment of the same flight id arriving before `flight`.
+    fn previous_gate(&self, flight: &Flight) -> Option<&String> {
+        self.history
+            .iter()
+            .rev()
+            .find(|a| {
+                a.flight.flight_id == flight.flight_id
+                    && a.flight.scheduled_arrival < flight.scheduled_arrival
+            })
+            .map(|a| &a.gate.gate_id)
+    }
+
+    /// Archived assignments overlapping `[from, until)`, oldest first.
+    pub fn history_in(&self, from: DateTime<Utc>, until: DateTime<Utc>) -> Vec<&GateAssignment> {
+        self.history
+            .iter()
+            .filter(|a| a.overlaps(from, until))
+            .collect()
+    }
+
+    /// Advance the clock to the current wall-clock time.
+    pub fn tick(&mut self) -> Vec<GateFreed> {
+        self.advance_to(Utc::now())
+    }
+
+    /// Lease the gates of `terminal` to `airline`.
+    pub fn add_lease(&mut self, lease: GateLease) {
+        info!(airline = %lease.airline, terminal = %lease.terminal, "Lease registered");
+        self.leases.push(lease);
+    }
+
+    /// The engine-wide facts `Gate::accepts` checks `flight` against: its domestic or
+    /// international status, type designations across the pool and, unless `ignore_leases`
+    /// (overflow terminals), the airline's leased terminals.
+    pub fn gate_context(
+        &self,
+        flight: &Flight,
+        window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+        ignore_leases: bool,
+    ) -> GateContext {
+        let leased_terminals = if ignore_leases {
+            Vec::new()
+        } else {
+            // Leases only bind the operating carrier: a codeshare partner's leases never apply,
+            // and nor do leases out of hours when the flight arrives.
+            self.leases
+                .iter()
+                .filter(|l| l.airline == flight.airline && l.applies_at(flight.scheduled_arrival))
+                .map(|l| l.terminal.clone())
+                .collect()
+        };
+        GateContext {
+            international: self.is_international(flight),
+            window,
+            type_designated_in_pool: self
+                .gates
+                .iter()
+                .any(|g| g.designates_type(&flight.aircraft_type)),
+            leased_terminals,
+        }
+    }
+
+    /// Flight-specific static constraints (type, doors, gate class, leases) on top of
+    /// `is_usable`.
+    fn fits_flight(&self, gate: &Gate, flight: &Flight) -> bool {
+        gate.accepts(flight, &self.gate_context(flight, None, false))
+            .is_ok()
+    }
+
+    /// Forbid departures from `gate_a` and `gate_b` from pushing back within `window_minutes` of
+    /// each other.  A departure pushes back when its assignment ends.
+    pub fn add_pushback_conflict(
+        &mut self,
+        gate_a: &str,
+        gate_b: &str,
+        window_minutes: i64,
+    ) -> Result<(), String> {
+        if let Some(unknown) = self
+            .unknown_gates(&[gate_a.to_string(), gate_b.to_string()])
+            .first()
+        {
+            return Err(format!("Unknown gate {}", unknown));
+        }
+        if gate_a == gate_b || window_minutes <= 0 {
+            return Err(format!(
+                "Invalid pushback conflict {} / {} ({}min)",
+                gate_a, gate_b, window_minutes
+            ));
+        }
+        info!(
+            gate_a,
+            gate_b, window_minutes, "Pushback conflict registered"
+        );
+        self.pushback_conflicts.push(PushbackConflict {
+            gate_a: gate_a.to_string(),
+            gate_b: gate_b.to_string(),
+            window_minutes,
+        });
+        Ok(())
+    }
+
+    /// Pushback window shared by `gate` and `other`, if they conflict.
+    fn pushback_window(&self, gate: &str, other: &str) -> Option<Duration> {
+        self.pushback_conflicts
+            .iter()
+            .find_map(|c| c.window_between(gate, other))
+    }
+
+    /// True if a departure from `gate_id` ending at `until` would push back too close to a
+    /// departure already booked on a conflicting neighbour.
+    fn pushback_blocked(&self, gate_id: &str, until: DateTime<Utc>) -> bool {
+        self.assignments.iter().any(|a| {
+            self.pushback_window(gate_id, &a.gate.gate_id)
+                .is_some_and(|w| (a.assigned_until - until).abs() < w)
+        })
+    }
+
+    /// Set (or replace) the daily curfew for `terminal`.
+    pub fn set_terminal_curfew(&mut self, terminal: &str, curfew: Curfew) {
+        info!(terminal, %curfew, "Terminal curfew set");
+        self.terminal_curfews.insert(terminal.to_string(), curfew);
+    }
+
+    /// Ban `aircraft_type` from every gate during `hours` each day (UTC, like terminal curfews),
+    /// e.g. noisy types overnight.  Replaces any earlier window for the type.
+    pub fn restrict_type_at_night(&mut self, aircraft_type: &str, hours: Curfew) {
+        info!(aircraft_type, %hours, "Night restriction set");
+        self.night_restricted_types
+            .insert(aircraft_type.to_uppercase(), hours);
+    }
+
+    /// Refuse a window overlapping the night restriction on the flight's aircraft type.
+    fn check_night_quota(
+        &self,
+        flight: &Flight,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<(), (AllocationError, String)> {
+        let aircraft_type = flight.aircraft_type.to_uppercase();
+        match self.night_restricted_types.get(&aircraft_type) {
+            Some(hours) if hours.overlaps(from, until) => {
+                warn!(flight = %flight.flight_id, aircraft = %aircraft_type, "Allocation refused - night restriction");
+                let message = format!(
+                    "{} ({}) may not use a gate during {}",
+                    flight.flight_id, aircraft_type, hours
+                );
+                Err((AllocationError::NightRestricted { aircraft_type }, message))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Limit `airline` (the operating carrier) to `max_gates` concurrent gates.  Replaces any
+    /// earlier cap; existing assignments are not touched.
+    pub fn cap_airline(&mut self, airline: &str, max_gates: usize) {
+        info!(airline, max_gates, "Airline gate cap set");
+        self.airline_caps.insert(airline.to_string(), max_gates);
+    }
+
+    /// Refuse a window in which the flight's airline would exceed its concurrent-gate cap.
+    fn check_airline_cap(
+        &self,
+        flight: &Flight,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<(), (AllocationError, String)> {
+        let Some(&cap) = self.airline_caps.get(&flight.airline) else {
+            return Ok(());
+        };
+        let held: Vec<&GateAssignment> = self
+            .assignments
+            .iter()
+            .filter(|a| {
+                a.flight.airline == flight.airline
+                    && a.flight.flight_id != flight.flight_id
+                    && a.overlaps(from, until)
+            })
+            .collect();
+        // Peak concurrency is reached at the start of some held window (or of the new one).
+        let peak = std::iter::once(from)
+            .chain(held.iter().map(|a| a.assigned_from.max(from)))
+            .map(|t| {
+                held.iter()
+                    .filter(|a| a.assigned_from <= t && t < a.assigned_until)
+                    .count()
+            })
+            .max()
+            .unwrap_or(0);
+        let needed = if flight.requires_double_stand { 2 } else { 1 };
+        if peak + needed <= cap {
+            return Ok(());
+        }
+        warn!(flight = %flight.flight_id, airline = %flight.airline, cap, "Allocation refused - airline gate cap");
+        let message = format!(
+            "{} refused: {} already holds {} of its {} gate(s) in the window",
+            flight.flight_id, flight.airline, peak, cap
+        );
+        Err((
+            AllocationError::AirlineCapReached {
+                airline: flight.airline.clone(),
+                cap,
+            },
+            message,
+        ))
+    }
+
+    fn in_curfew(&self, terminal: &str, from: DateTime<Utc>, until: DateTime<Utc>) -> bool {
+        self.terminal_curfews
+            .get(terminal)
+            .is_some_and(|c| c.overlaps(from, until))
+    }
+
+    /// Set (or replace) the terminals `terminal` overflows into, in the order they are tried.
+    pub fn set_overflow_chain(&mut self, terminal: &str, fallbacks: &[&str]) {
+        info!(terminal, fallbacks = ?fallbacks, "Overflow chain set");
+        self.overflow_chains.insert(
+            terminal.to_string(),
+            fallbacks.iter().map(|t| t.to_string()).collect(),
+        );
+    }
+
+    /// Link an inbound flight to the outbound flight its aircraft operates next, so that a late
+    /// inbound also delays the outbound.  Refused if the link would form a cycle.
+    pub fn link_turnaround(&mut self, inbound: &str, outbound: &str) -> Result<(), String> {
+        let mut next = Some(outbound);
+        while let Some(id) = next {
+            if id == inbound {
+                return Err(format!(
+                    "Linking {} -> {} would form a cycle",
+                    inbound, outbound
+                ));
+            }
+            next = self.turnaround_links.get(id).map(String::as_str);
+        }
+        info!(inbound, outbound, "Turnaround linked");
+        self.turnaround_links
+            .insert(inbound.to_string(), outbound.to_string());
+        Ok(())
+    }
+
+    /// Declare `gate_a` and `gate_b` physically adjacent, so they can serve as a double stand.
+    pub fn add_adjacent_gates(&mut self, gate_a: &str, gate_b: &str) -> Result<(), String> {
+        if let Some(unknown) = self
+            .unknown_gates(&[gate_a.to_string(), gate_b.to_string()])
+            .first()
+        {
+            return Err(format!("Unknown gate {}", unknown));
+        }
+        if gate_a == gate_b {
+            return Err(format!("Gate {} cannot be adjacent to itself", gate_a));
+        }
+        info!(gate_a, gate_b, "Adjacent gates registered");
+        self.adjacent_gates
+            .push((gate_a.to_string(), gate_b.to_string()));
+        Ok(())
+    }
+
+    /// For a double-stand flight, the first gate adjacent to `gate` that is free over
+    /// `[from, until)` and allowed for the flight.  `None` for other flights.
+    fn double_stand_partner(
+        &self,
+        gate: &Gate,
+        flight: &Flight,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Option<String> {
+        if !flight.requires_double_stand {
+            return None;
+        }
+        self.adjacent_gates
+            .iter()
+            .filter_map(|(a, b)| {
+                if *a == gate.gate_id {
+                    Some(b)
+                } else if *b == gate.gate_id {
+                    Some(a)
+                } else {
+                    None
+                }
+            })
+            .find(|id| {
+                self.partner_free(id, from, until)
+                    && self
+                        .gates
+                        .iter()
+                        .any(|g| &g.gate_id == *id && self.fits_flight(g, flight))
+            })
+            .cloned()
+    }
+
+    /// The partner of a double stand only lends its space, so its size does not matter.
+    fn partner_free(&self, gate_id: &str, from: DateTime<Utc>, until: DateTime<Utc>) -> bool {
+        self.gates
+            .iter()
+            .find(|g| g.gate_id == gate_id)
+            .is_some_and(|g| {
+                g.is_available
+                    && g.is_staffed_during(from, until)
+                    && !g.is_closed_during(from, until)
+                    && !self.has_conflict(gate_id, from, until)
+                    && !self.pushback_blocked(gate_id, until)
+                    && !self.in_curfew(&g.terminal, from, until)
+            })
+    }
+
+    /// Terminal tiers searched in turn by `plan_allocation`.  A flight's home terminal is that of
+    /// its first known preferred gate, else its carrier's first lease; when the home terminal has
+    /// an overflow chain the search is confined to it and then to each fallback.  Otherwise there
+    /// is a single unrestricted tier (`None`).
+    fn terminal_tiers<'a>(&'a self, flight: &Flight, preferred: &[String]) -> Vec<Option<&'a str>> {
+        let home = preferred
+            .iter()
+            .find_map(|id| self.gates.iter().find(|g| &g.gate_id == id))
+            .map(|g| g.terminal.as_str())
+            .or_else(|| {
+                self.leases
+                    .iter()
+                    .find(|l| l.airline == flight.airline && l.applies_at(flight.scheduled_arrival))
+                    .map(|l| l.terminal.as_str())
+            });
+        match home.and_then(|h| self.overflow_chains.get_key_value(h)) {
+            Some((home, chain)) => std::iter::once(home)
+                .chain(chain)
+                .map(|t| Some(t.as_str()))
+                .collect(),
+            None => vec![None],
+        }
+    }
+
+    /// Where `flight` belongs absent any preference: its airline's leased terminal, else the
+    /// terminal where the airline holds the most current assignments.
+    fn natural_terminal(&self, flight: &Flight) -> Option<&str> {
+        if let Some(lease) = self
+            .leases
+            .iter()
+            .find(|l| l.airline == flight.airline && l.applies_at(flight.scheduled_arrival))
+        {
+            return Some(&lease.terminal);
+        }
+        let mut held: BTreeMap<&str, usize> = BTreeMap::new();
+        for a in self
+            .assignments
+            .iter()
+            .filter(|a| a.flight.airline == flight.airline)
+        {
+            *held.entry(&a.gate.terminal).or_default() += 1;
+        }
+        held.into_iter()
+            .max_by_key(|&(_, n)| n)
+            .map(|(terminal, _)| terminal)
+    }
+
+    /// Register (or override) the country an airport belongs to.
+    pub fn register_airport(&mut self, iata: &str, country: &str) {
+        self.airport_countries
+            .insert(iata.to_uppercase(), country.to_uppercase());
+    }
+
+    /// True when the flight's origin and destination are in different countries.  Airports missing
+    /// from the table fall back to `config.unknown_airport_is_international`.
+    pub fn is_international(&self, flight: &Flight) -> bool {
+        let origin = self.airport_countries.get(&flight.origin.to_uppercase());
+        let destination = self
+            .airport_countries
+            .get(&flight.destination.to_uppercase());
+        match (origin, destination) {
+            (Some(o), Some(d)) => o != d,
+            _ => self.config.unknown_airport_is_international,
+        }
+    }
+
+    /// Allocate the best available gate for a flight using constraint-based scoring.
+    pub fn allocate_gate(
+        &mut self,
+        flight: &Flight,
+        airport: &str,
+        preferred: &[String],
+    ) -> AllocationResult {
+        self.tracked(|e| match e.plan_allocation(flight, airport, preferred) {
+            Ok(plan) => e.commit_unchecked(plan),
+            Err((error, message)) => AllocationResult::failed(error, message),
+        })
+    }
+
+    /// `allocate_gate` at the default airport (see `with_default_airport`), or at the flight's
+    /// destination, where it arrives onto the gate, when none is set.
+    pub fn allocate(&mut self, flight: &Flight, preferred: &[String]) -> AllocationResult {
+        let airport = self
+            .default_airport
+            .clone()
+            .unwrap_or_else(|| flight.destination.clone());
+        self.allocate_gate(flight, &airport, preferred)
+    }
+
+    /// Put `flight` on exactly `gate_id` at `airport` over `[from, until)`, bypassing scoring,
+    /// or report why that gate cannot take it.
+    pub fn try_allocate_at(
+        &mut self,
+        flight: &Flight,
+        airport: &str,
+        gate_id: &str,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> AllocationResult {
+        match self.plan_at(flight, airport, gate_id, from, until) {
+            Ok(plan) => self.tracked(|e| e.commit_unchecked(plan)),
+            Err((error, message)) => AllocationResult::failed(error, message),
+        }
+    }
+
+    /// The plan behind `try_allocate_at`: `gate_id` over exactly `[from, until)`, unscored
+    /// against other gates.
+    fn plan_at(
+        &self,
+        flight: &Flight,
+        airport: &str,
+        gate_id: &str,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<AllocationPlan, (AllocationError, String)> {
+        Self::check_status(flight)?;
+        self.check_aircraft_type(flight)?;
+        self.check_not_past(flight, until)?;
+        self.check_night_quota(flight, from, until)?;
+        self.check_airline_cap(flight, from, until)?;
+        let gate = match self.check_gate_at(flight, gate_id, from, until) {
+            Ok(gate) => gate,
+            Err(reason) => {
+                warn!(flight = %flight.flight_id, gate = gate_id, %reason, "Forced allocation refused");
+                let message = format!(
+                    "Cannot place {} on {} {} - {}: {}",
+                    flight.flight_id,
+                    gate_id,
+                    from.format("%H:%M"),
+                    until.format("%H:%M"),
+                    reason
+                );
+                let error = AllocationError::GateRefused {
+                    gate_id: gate_id.to_string(),
+                    reason,
+                };
+                return Err((error, message));
+            }
+        };
+        Ok(AllocationPlan {
+            flight: flight.clone(),
+            airport: airport.to_string(),
+            preferred: Vec::new(),
+            gate_id: gate_id.to_string(),
+            score: self.score_gate(gate, flight, &[]),
+            from,
+            until,
+            overflow_terminal: None,
+            partner_gate: self.double_stand_partner(gate, flight, from, until),
+            infeasible_preferred: Vec::new(),
+        })
+    }
+
+    /// Under `EngineConfig::same_gate_turnarounds`, the gate of the inbound leg linked to
+    /// `flight` assigned at `airport`, and when the aircraft's stay there continues from.
+    fn linked_inbound_gate(
+        &self,
+        flight: &Flight,
+        airport: &str,
+    ) -> Option<(String, DateTime<Utc>)> {
+        if !self.config.same_gate_turnarounds {
+            return None;
+        }
+        let (inbound, _) = self
+            .turnaround_links
+            .iter()
+            .find(|(_, outbound)| **outbound == flight.flight_id)?;
+        let a = self
+            .assignments
+            .iter()
+            .find(|a| &a.flight.flight_id == inbound && a.airport == airport)?;
+        Some((
+            a.gate.gate_id.clone(),
+            a.assigned_until.min(flight.scheduled_arrival),
+        ))
+    }
+
+    /// Only flights that will still use a gate can be allocated.
+    fn check_status(flight: &Flight) -> Result<(), (AllocationError, String)> {
+        if !flight.status.is_terminal() {
+            return Ok(());
+        }
+        warn!(flight = %flight.flight_id, status = %flight.status, "Allocation refused for inactive flight");
+        Err((
+            AllocationError::InactiveFlight {
+                status: flight.status,
+            },
+            format!(
+                "{} is {} and cannot be allocated a gate",
+                flight.flight_id, flight.status
+            ),
+        ))
+    }
+
+    /// With `config.strict_aircraft_types`, a type the engine cannot size is refused rather
+    /// than treated as Medium.
+    fn check_aircraft_type(&self, flight: &Flight) -> Result<(), (AllocationError, String)> {
+        if !self.config.strict_aircraft_types
+            || is_known_aircraft(&flight.aircraft_type)
+            || flight.wingspan_m.is_some()
+        {
+            return Ok(());
+        }
+        warn!(flight = %flight.flight_id, aircraft = %flight.aircraft_type, "Allocation refused for unknown aircraft type");
+        Err((
+            AllocationError::UnknownAircraftType {
+                aircraft_type: flight.aircraft_type.clone(),
+            },
+            format!(
+                "{} has unknown aircraft type {} and no wingspan to size it by",
+                flight.flight_id, flight.aircraft_type
+            ),
+        ))
+    }
+
+    /// With `config.reject_past_windows`, a window ending at or before the clock is refused.
+    fn check_not_past(
+        &self,
+        flight: &Flight,
+        until: DateTime<Utc>,
+    ) -> Result<(), (AllocationError, String)> {
+        match self.clock {
+            Some(now) if self.config.reject_past_windows && until <= now => {
+                warn!(flight = %flight.flight_id, %until, %now, "Allocation refused - window in the past");
+                Err((
+                    AllocationError::WindowInPast { until, now },
+                    format!(
+                        "{}'s window ended at {}, before the current time {}",
+                        flight.flight_id,
+                        until.format("%Y-%m-%d %H:%M"),
+                        now.format("%Y-%m-%d %H:%M")
+                    ),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The hard constraints of `plan_allocation`, checked for one gate and window in turn.
+    fn check_gate_at(
+        &self,
+        flight: &Flight,
+        gate_id: &str,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<&Gate, GateRefusal> {
+        let gate = self
+            .gates
+            .iter()
+            .find(|g| g.gate_id == gate_id)
+            .ok_or(GateRefusal::UnknownGate)?;
+        let size = flight.aircraft_size();
+        if until <= from {
+            return Err(GateRefusal::InvalidWindow);
+        }
+        gate.accepts(
+            flight,
+            &self.gate_context(flight, Some((from, until)), false),
+        )?;
+        let refusal = if self.free_stand(gate, size, from, until).is_none() {
+            GateRefusal::Occupied
+        } else if self.pushback_blocked(gate_id, until) {
+            GateRefusal::PushbackClash
+        } else if self.in_curfew(&gate.terminal, from, until) {
+            GateRefusal::TerminalCurfew
+        } else if flight.requires_double_stand
+            && self
+                .double_stand_partner(gate, flight, from, until)
+                .is_none()
+        {
+            GateRefusal::NoAdjacentPair
+        } else {
+            return Ok(gate);
+        };
+        Err(refusal)
+    }
+
+    /// Preferred gates `flight` could not use at any time: unknown, too small, out of service
+    /// or ruled out by type, class or lease.  Gates merely busy or closed for the window are not
+    /// reported.
+    fn infeasible_preferences(
+        &self,
+        flight: &Flight,
+        preferred: &[String],
+    ) -> Vec<(String, GateRefusal)> {
+        preferred
+            .iter()
+            .filter_map(|id| {
+                let reason = match self.gates.iter().find(|g| &g.gate_id == id) {
+                    None => GateRefusal::UnknownGate,
+                    Some(g) if !g.can_accommodate(flight.aircraft_size()) => GateRefusal::TooSmall,
+                    Some(g) if !g.is_available => GateRefusal::OutOfService,
+                    Some(g) if !self.fits_flight(g, flight) => GateRefusal::NotPermitted,
+                    Some(_) => return None,
+                };
+                Some((id.clone(), reason))
+            })
+            .collect()
+    }
+
+    /// When the gate becomes free again after `flight`: its departure plus the turnaround buffer
+    /// plus any post-departure cool-down for its size.
+    fn hold_until(&self, flight: &Flight) -> DateTime<Utc> {
+        let cooldown = self
+            .config
+            .post_departure_cooldown
+            .get(&flight.aircraft_size())
+            .copied()
+            .unwrap_or(0);
+        let buffer = if flight.is_ferry {
+            FERRY_TURNAROUND_BUFFER_MINUTES
+        } else {
+            TURNAROUND_BUFFER_MINUTES
+        };
+        flight
+            .occupancy_window(Duration::minutes(buffer + cooldown))
+            .1
+    }
+
+    /// Choose a gate for `flight` without mutating the engine.  Pair with `commit_plan`, which
+    /// re-validates the choice, to run the scoring on a `planning_copy` outside any lock.  A
+    /// linked outbound leg under `EngineConfig::same_gate_turnarounds` is planned onto its
+    /// inbound's gate at `airport` instead of being scored.
+    pub fn plan_allocation(
+        &self,
+        flight: &Flight,
+        airport: &str,
+        preferred: &[String],
+    ) -> Result<AllocationPlan, (AllocationError, String)> {
+        if let Some((gate_id, from)) = self.linked_inbound_gate(flight, airport) {
+            info!(flight = %flight.flight_id, gate = %gate_id, "Keeping turnaround on inbound gate");
+            return self.plan_at(flight, airport, &gate_id, from, self.hold_until(flight));
+        }
+        self.plan_with(flight, airport, preferred, PlanBias::default())
+    }
+
+    /// Re-allocate a flight displaced by a disruption, penalising gates outside `terminal` (the
+    /// one its passengers were already sent to).
+    fn reallocate(
+        &mut self,
+        flight: &Flight,
+        airport: &str,
+        preferred: &[String],
+        terminal: &str,
+    ) -> AllocationResult {
+        let bias = PlanBias {
+            original_terminal: Some(terminal),
+            ..Default::default()
+        };
+        self.allocate_with(flight, airport, preferred, bias)
+    }
+
+    fn allocate_with(
+        &mut self,
+        flight: &Flight,
+        airport: &str,
+        preferred: &[String],
+        bias: PlanBias,
+    ) -> AllocationResult {
+        match self.plan_with(flight, airport, preferred, bias) {
+            Ok(plan) => self.commit_unchecked(plan),
+            Err((error, message)) => AllocationResult::failed(error, message),
+        }
+    }
+
+    fn plan_with(
+        &self,
+        flight: &Flight,
+        airport: &str,
+        preferred: &[String],
+        bias: PlanBias,
+    ) -> Result<AllocationPlan, (AllocationError, String)> {
+        info!(flight = %flight.flight_id, aircraft = %flight.aircraft_type, airport, "Attempting allocation");
+        Self::check_status(flight)?;
+        self.check_aircraft_type(flight)?;
+        self.check_not_past(flight, self.hold_until(flight))?;
+        self.check_night_quota(flight, flight.scheduled_arrival, self.hold_until(flight))?;
+        self.check_airline_cap(flight, flight.scheduled_arrival, self.hold_until(flight))?;
+        if self.gates.is_empty() {
+            warn!(flight = %flight.flight_id, airport, "No gates registered - check engine setup");
+            return Err((
+                AllocationError::NoGatesRegistered {
+                    airport: airport.to_string(),
+                },
+                format!(
+                    "No gates registered for {} - cannot place {}",
+                    airport, flight.flight_id
+                ),
+            ));
+        }
+
+        let infeasible_preferred = self.infeasible_preferences(flight, preferred);
+        if let Some((gate_id, reason)) = infeasible_preferred.first() {
+            warn!(flight = %flight.flight_id, gate = %gate_id, %reason, "Preferred gate infeasible");
+            if self.config.strict_preferences {
+                return Err((
+                    AllocationError::GateRefused {
+                        gate_id: gate_id.clone(),
+                        reason: *reason,
+                    },
+                    format!(
+                        "Refused {}: preferred gate {} is infeasible ({})",
+                        flight.flight_id, gate_id, reason
+                    ),
+                ));
+            }
+        }
+
+        let size = flight.aircraft_size();
+        let need_from = flight.scheduled_arrival;
+        let need_until = self.hold_until(flight);
+
+        let mut curfewed: Vec<&Gate> = Vec::new();
+        let mut open: Vec<&Gate> = Vec::new();
+        let mut overflow_terminal = None;
+        for (tier, terminal) in self
+            .terminal_tiers(flight, preferred)
+            .into_iter()
+            .enumerate()
+        {
+            let overflow = tier > 0;
+            let ctx = self.gate_context(flight, Some((need_from, need_until)), overflow);
+            let (shut, free): (Vec<&Gate>, Vec<&Gate>) = self
+                .gates
+                .iter()
+                .filter(|g| terminal.is_none_or(|t| g.terminal == t))
+                .filter(|g| g.accepts(flight, &ctx).is_ok())
+                .filter(|g| {
+                    self.free_stand(g, size, need_from, need_until).is_some()
+                        && !self.pushback_blocked(&g.gate_id, need_until)
+                })
+                .filter(|g| {
+                    !flight.requires_double_stand
+                        || self
+                            .double_stand_partner(g, flight, need_from, need_until)
+                            .is_some()
+                })
+                .partition(|g| self.in_curfew(&g.terminal, need_from, need_until));
+            curfewed.extend(shut);
+            if !free.is_empty() {
+                if overflow {
+                    info!(flight = %flight.flight_id, terminal = ?terminal, "Home terminal full - overflowing");
+                    overflow_terminal = terminal.map(str::to_string);
+                }
+                open = free;
+                break;
+            }
+        }
+
+        if open.is_empty() && !curfewed.is_empty() {
+            let mut terminals: Vec<String> = curfewed.iter().map(|g| g.terminal.clone()).collect();
+            terminals.sort();
+            terminals.dedup();
+            warn!(flight = %flight.flight_id, terminals = ?terminals, "Window falls in terminal curfew");
+            let message = format!(
+                "No gate for {} at {}: window {} - {} falls in curfew at {}",
+                flight.flight_id,
+                airport,
+                need_from.format("%H:%M"),
+                need_until.format("%H:%M"),
+                terminals.join(", ")
+            );
+            return Err((AllocationError::TerminalCurfew { terminals }, message));
+        }
+
+        let locality = self
+            .config
+            .terminal_locality_penalty
+            .filter(|_| {
+                preferred.is_empty() && bias.original_terminal.is_none() && !flight.is_ferry
+            })
+            .and_then(|penalty| Some((self.natural_terminal(flight)?, penalty)));
+        let mut candidates: Vec<(&Gate, f64)> = open
+            .into_iter()
+            .map(|g| {
+                let mut score = self.score_gate(g, flight, preferred);
+                if let Some((_, penalty)) = locality.filter(|&(home, _)| home != g.terminal) {
+                    score += penalty;
+                }
+                if bias.original_terminal.is_some_and(|t| t != g.terminal) {
+                    score += PENALTY_TERMINAL_CHANGE;
+                }
+                if bias.withheld.contains(&g.gate_id) {
+                    score += PENALTY_WITHHELD_GATE;
+                }
+                (g, score)
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        let Some(&(gate, score)) = candidates.first() else {
+            warn!(flight = %flight.flight_id, "No available gates");
+            return Err((
+                AllocationError::NoCompatibleGate,
+                format!(
+                    "No compatible gate for {} ({}) at {}",
+                    flight.flight_id, flight.aircraft_type, airport
+                ),
+            ));
+        };
+
+        if let Some((home, _)) = locality.filter(|&(home, _)| home != gate.terminal) {
+            warn!(flight = %flight.flight_id, home, terminal = %gate.terminal, "Terminal locality broken - home terminal full");
+        }
+
+        if let Some(threshold) = self.config.max_acceptable_score {
+            if score > threshold {
+                warn!(flight = %flight.flight_id, gate = %gate.gate_id, score, threshold, "Best gate above score threshold");
+                return Err((
+                    AllocationError::ScoreTooHigh {
+                        gate_id: gate.gate_id.clone(),
+                        score,
+                        threshold,
+                    },
+                    format!(
+                        "Refused {} at {}: best gate {} scored {:.1} (max {:.1})",
+                        flight.flight_id, airport, gate.gate_id, score, threshold
+                    ),
+                ));
+            }
+        }
+
+        Ok(AllocationPlan {
+            flight: flight.clone(),
+            airport: airport.to_string(),
+            preferred: preferred.to_vec(),
+            gate_id: gate.gate_id.clone(),
+            score,
+            from: need_from,
+            until: need_until,
+            overflow_terminal,
+            partner_gate: self.double_stand_partner(gate, flight, need_from, need_until),
+            infeasible_preferred,
+        })
+    }
+
+    /// Commit a plan produced by `plan_allocation`, possibly against an older `planning_copy`.
+    /// The flight-level rules (clock, night quota, airline cap) are re-checked against the live
+    /// state and refuse outright.  The chosen gate is re-checked too; if it has since been taken
+    /// (or closed, unstaffed, or removed) the flight is re-planned here so the engine never
+    /// double-books.
+    pub fn commit_plan(&mut self, plan: AllocationPlan) -> AllocationResult {
+        self.tracked(|e| e.commit_checked(plan))
+    }
+
+    fn commit_checked(&mut self, plan: AllocationPlan) -> AllocationResult {
+        if let Err((error, message)) = self
+            .check_not_past(&plan.flight, plan.until)
+            .and_then(|_| self.check_night_quota(&plan.flight, plan.from, plan.until))
+            .and_then(|_| self.check_airline_cap(&plan.flight, plan.from, plan.until))
+        {
+            return AllocationResult::failed(error, message);
+        }
+        let size = plan.flight.aircraft_size();
+        // An overflow terminal is open to the flight regardless of leases.
+        let ctx = self.gate_context(
+            &plan.flight,
+            Some((plan.from, plan.until)),
+            plan.overflow_terminal.is_some(),
+        );
+        let still_valid = self.gates.iter().any(|g| {
+            g.gate_id == plan.gate_id
+                && self.is_usable(g, size, plan.from, plan.until)
+                && g.accepts(&plan.flight, &ctx).is_ok()
+        }) && plan
+            .partner_gate
+            .as_deref()
+            .is_none_or(|p| self.partner_free(p, plan.from, plan.until));
+        if still_valid {
+            self.commit_unchecked(plan)
+        } else {
+            warn!(flight = %plan.flight.flight_id, gate = %plan.gate_id, "Plan stale on commit - re-planning");
+            self.allocate_gate(&plan.flight, &plan.airport, &plan.preferred)
+        }
+    }
+
+    fn commit_unchecked(&mut self, plan: AllocationPlan) -> AllocationResult {
+        let Some(gate) = self.gates.iter().find(|g| g.gate_id == plan.gate_id) else {
+            return AllocationResult::failed(
+                AllocationError::NoCompatibleGate,
+                format!("Gate {} no longer exists", plan.gate_id),
+            );
+        };
+        // Both gates of a double stand are taken together or not at all.
+        let partner = match plan.partner_gate.as_deref() {
+            Some(p) if !self.partner_free(p, plan.from, plan.until) => {
+                return AllocationResult::failed(
+                    AllocationError::NoCompatibleGate,
+                    format!(
+                        "Double stand {} + {} no longer free for {}",
+                        plan.gate_id, p, plan.flight.flight_id
+                    ),
+                );
+            }
+            Some(p) => self.gates.iter().find(|g| g.gate_id == p).cloned(),
+            None => None,
+        };
+        let sub_stand = self
+            .free_stand(gate, plan.flight.aircraft_size(), plan.from, plan.until)
+            .flatten();
+        // Ids are only drawn once the plan is known to commit, so a refusal consumes none.
+        let gate = gate.clone();
+        let assignment_id = self.next_id();
+        let assignment = GateAssignment {
+            assignment_id,
+            flight: plan.flight,
+            gate,
+            assigned_from: plan.from,
+            assigned_until: plan.until,
+            status: AssignmentStatus::Provisional,
+            airport: plan.airport,
+            scheduled: Some((plan.from, plan.until)),
+            sub_stand,
+            notes: Vec::new(),
+        };
+        info!(flight = %assignment.flight.flight_id, gate = %plan.gate_id, score = plan.score, "Allocated");
+        self.assignments.push(assignment.clone());
+        let mut message = format!(
+            "Allocated {} -> {} (score: {:.1})",
+            assignment.flight.flight_id, plan.gate_id, plan.score
+        );
+        if let Some(partner) = partner {
+            message.push_str(&format!(" with adjacent {}", partner.gate_id));
+            let assignment_id = self.next_id();
+            self.assignments.push(GateAssignment {
+                assignment_id,
+                gate: partner,
+                sub_stand: None,
+                ..assignment.clone()
+            });
+        }
+        if let Some(terminal) = &plan.overflow_terminal {
+            message.push_str(&format!(" via overflow terminal {}", terminal));
+        }
+        for (gate_id, reason) in &plan.infeasible_preferred {
+            message.push_str(&format!("; preferred {} infeasible ({})", gate_id, reason));
+        }
+        AllocationResult {
+            success: true,
+            message,
+            assignment: Some(assignment),
+            error: None,
+            overflow_terminal: plan.overflow_terminal,
+            infeasible_preferred: plan.infeasible_preferred,
+        }
+    }
+
+    /// A detached copy of everything `plan_allocation` reads, including the history archive that
+    /// gate stickiness scores against, for planning outside the engine lock.  Listeners and the
+    /// disruption log are not copied.
+    pub fn planning_copy(&self) -> AllocationEngine {
+        AllocationEngine {
+            gates: self.gates.clone(),
+            assignments: self.assignments.clone(),
+            disruptions: Vec::new(),
+            reservations: self.reservations.clone(),
+            leases: self.leases.clone(),
+            config: self.config.clone(),
+            airport_countries: self.airport_countries.clone(),
+            terminal_curfews: self.terminal_curfews.clone(),
+            night_restricted_types: self.night_restricted_types.clone(),
+            airline_caps: self.airline_caps.clone(),
+            clock: self.clock,
+            overflow_chains: self.overflow_chains.clone(),
+            pushback_conflicts: self.pushback_conflicts.clone(),
+            turnaround_links: self.turnaround_links.clone(),
+            adjacent_gates: self.adjacent_gates.clone(),
+            completed_charges: Vec::new(),
+            history: self.history.clone(),
+            default_airport: self.default_airport.clone(),
+            pending_manual: Vec::new(),
+            gate_freed_listeners: Vec::new(),
+            change_listeners: Vec::new(),
+            delta_listeners: Vec::new(),
+            id_generator: None,
+            tracking_changes: false,
+        }
+    }
+
+    /// The subset of `gate_ids` that does not match any registered gate, in input order.
+    pub fn unknown_gates(&self, gate_ids: &[String]) -> Vec<String> {
+        gate_ids
+            .iter()
+            .filter(|id| !self.gates.iter().any(|g| &g.gate_id == *id))
+            .cloned()
+            .collect()
+    }
+
+    /// Cheap pre-check: is at least one gate able to take an aircraft of `size` over
+    /// `[from, until)`, optionally restricted to `terminal`?  Stops at the first usable gate and
+    /// skips scoring entirely.
+    pub fn has_capacity(
+        &self,
+        size: AircraftSize,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+        terminal: Option<&str>,
+    ) -> bool {
+        self.gates
+            .iter()
+            .filter(|g| terminal.map(|t| g.terminal == t).unwrap_or(true))
+            .any(|g| self.is_usable(g, size, from, until))
+    }
+
+    /// Flight-independent hard constraints shared by `commit_plan`, `has_capacity` and the
+    /// repacking operations, matching what `allocate_gate` applies: the gate is in service, large
+    /// enough, within its occupancy limits, staffed, not closed for maintenance, not already
+    /// booked, its terminal is not in curfew and its pushback does not clash with a neighbour's.
+    fn is_usable(
+        &self,
+        gate: &Gate,
+        size: AircraftSize,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> bool {
+        gate.is_available
+            && gate.can_accommodate(size)
+            && gate.allows_occupancy(from, until)
+            && gate.is_staffed_during(from, until)
+            && !gate.is_closed_during(from, until)
+            && self.free_stand(gate, size, from, until).is_some()
+            && !self.pushback_blocked(&gate.gate_id, until)
+            && !self.in_curfew(&gate.terminal, from, until)
+    }
+
+    /// Whether `[from, until)` comes within `config.min_gap_minutes` of assignment `a`'s window
+    /// (with no gap, whether the two overlap).  Callers check that the gates match.
+    fn crowds(&self, a: &GateAssignment, from: DateTime<Utc>, until: DateTime<Utc>) -> bool {
+        let gap = Duration::minutes(self.config.min_gap_minutes);
+        overlaps(
+            (a.assigned_from - gap, a.assigned_until + gap),
+            (from, until),
         )
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::TimeZone;
+    /// Returns true if any existing assignment on `gate_id` overlaps the half-open interval
+    /// `[from, until)`, or comes closer to it than the minimum gap.  Reservations count as
+    /// occupancy too.
+    fn has_conflict(&self, gate_id: &str, from: DateTime<Utc>, until: DateTime<Utc>) -> bool {
+        self.assignments
+            .iter()
+            .any(|a| a.gate.gate_id == gate_id && self.crowds(a, from, until))
+            || self
+                .reservations
+                .iter()
+                .any(|r| r.gate_id == gate_id && overlaps((r.from, r.until), (from, until)))
+    }
+
+    /// The part of `gate` an aircraft of `size` can use over `[from, until)`: `Some(None)` for
+    /// the whole stand, `Some(Some(n))` for sub-stand `n` of a MARS gate, `None` if it is taken.
+    /// Narrow-bodies on a MARS gate only need a sub-stand nobody overlapping holds; wide-bodies,
+    /// reservations and whole-stand assignments block the gate entirely.
+    fn free_stand(
+        &self,
+        gate: &Gate,
+        size: AircraftSize,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Option<Option<u8>> {
+        if !gate.mars || size == AircraftSize::Large {
+            return (!self.has_conflict(&gate.gate_id, from, until)).then_some(None);
+        }
+        if self
+            .reservations
+            .iter()
+            .any(|r| r.gate_id == gate.gate_id && overlaps((r.from, r.until), (from, until)))
+        {
+            return None;
+        }
+        let mut held = [false; 2];
+        for a in self
+            .assignments
+            .iter()
+            .filter(|a| a.gate.gate_id == gate.gate_id && self.crowds(a, from, until))
+        {
+            match a.sub_stand {
+                Some(n) if (n as usize) < held.len() => held[n as usize] = true,
+                _ => return None,
+            }
+        }
+        held.iter().position(|h| !h).map(|n| Some(n as u8))
+    }
+
+    /// Compute a score for `gate`; lower is better.  The algorithm penalises oversized gates
+    /// (waste of capacity) and gates outside the caller's preferred list, and rewards preferred ones.
+    fn score_gate(&self, gate: &Gate, flight: &Flight, preferred: &[String]) -> f64 {
+        self.score_breakdown(gate, flight, preferred).total()
+    }
+
+    /// Explain the base score `gate_id` would get for `flight`; `None` for an unknown gate.
+    pub fn explain_score(
+        &self,
+        gate_id: &str,
+        flight: &Flight,
+        preferred: &[String],
+    ) -> Option<ScoreBreakdown> {
+        let gate = self.gates.iter().find(|g| g.gate_id == gate_id)?;
+        Some(self.score_breakdown(gate, flight, preferred))
+    }
+
+    fn score_breakdown(
+        &self,
+        gate: &Gate,
+        flight: &Flight,
+        preferred: &[String],
+    ) -> ScoreBreakdown {
+        let mut breakdown = ScoreBreakdown::default();
+        let size_diff = gate.size.rank() as i32 - flight.aircraft_size().rank() as i32;
+        if size_diff > 0 {
+            breakdown.oversize = PENALTY_OVERSIZED_GATE * size_diff as f64;
+        }
+        if let Some(weight) = self.config.scoring.terminal_balance_weight {
+            let excess =
+                self.occupancy(Some(&gate.terminal), flight) - self.occupancy(None, flight);
+            breakdown.balance = weight * excess.max(0.0);
+        }
+        if flight.is_ferry {
+            // Only size matters to an empty aircraft; keep it off the jet bridges.
+            if !gate.remote {
+                breakdown.passengers = PENALTY_FERRY_CONTACT_GATE;
+            }
+            return breakdown;
+        }
+        if let Some(reward) = self.config.scoring.gate_stickiness_reward {
+            if self.previous_gate(flight) == Some(&gate.gate_id) {
+                breakdown.stickiness = -reward;
+            }
+        }
+        if !preferred.is_empty() {
+            breakdown.preference = match preferred.iter().position(|p| *p == gate.gate_id) {
+                Some(rank) => {
+                    let reward = (REWARD_PREFERRED_GATE + PREFERRED_RANK_STEP * rank as f64)
+                        .min(PENALTY_PREFERRED_MISS);
+                    // Decay moves the preferred score towards the miss penalty, so a heavily
+                    // used preferred gate ends up no more attractive than a non-preferred one.
+                    let retained = self.preferred_reward_retention(gate, &flight.airline);
+                    PENALTY_PREFERRED_MISS + (reward - PENALTY_PREFERRED_MISS) * retained
+                }
+                None => PENALTY_PREFERRED_MISS,
+            };
+        }
+        if let (Some(penalty), Some(favours)) = (self.config.directional_penalty, gate.favours) {
+            if favours != flight.dominant_role() {
+                breakdown.direction = penalty;
+            }
+        }
+        if flight
+            .baggage_zone
+            .as_ref()
+            .is_some_and(|zone| gate.baggage_zone.as_ref() != Some(zone))
+        {
+            breakdown.baggage = PENALTY_BAGGAGE_ZONE_MISS;
+        }
+        breakdown.passengers = match (flight.passenger_count, gate.remote) {
+            (_, true) => PENALTY_REMOTE_STAND,
+            (Some(pax), false) => (PASSENGER_PIVOT - pax as f64) * CONTACT_GATE_PER_PASSENGER,
+            (None, false) => 0.0,
+        };
+        breakdown
+    }
+
+    /// Share of the gates (in `terminal`, or everywhere) held by other flights at some point of
+    /// `flight`'s window.
+    fn occupancy(&self, terminal: Option<&str>, flight: &Flight) -> f64 {
+        let (from, until) = (flight.scheduled_arrival, self.hold_until(flight));
+        let gates: Vec<&Gate> = self
+            .gates
+            .iter()
+            .filter(|g| terminal.is_none_or(|t| g.terminal == t))
+            .collect();
+        let busy = gates
+            .iter()
+            .filter(|g| {
+                self.assignments.iter().any(|a| {
+                    a.gate.gate_id == g.gate_id
+                        && a.flight.flight_id != flight.flight_id
+                        && a.overlaps(from, until)
+                })
+            })
+            .count();
+        busy as f64 / gates.len().max(1) as f64
+    }
+
+    /// Share of the preferred-gate advantage still granted to `airline` on `gate`, in `[0, 1]`.
+    fn preferred_reward_retention(&self, gate: &Gate, airline: &str) -> f64 {
+        let Some(decay) = self.config.preferred_reward_decay else {
+            return 1.0;
+        };
+        let uses = self
+            .assignments
+            .iter()
+            .filter(|a| a.gate.gate_id == gate.gate_id && a.flight.airline == airline)
+            .count();
+        decay.clamp(0.0, 1.0).powi(uses as i32)
+    }
+
+    /// Record that `event` left `flights` for a dispatcher to place by hand.
+    fn queue_for_manual(&mut self, event: &DisruptionEvent, flights: Vec<Flight>) {
+        if flights.is_empty() {
+            return;
+        }
+        warn!(event = %event.event_id, flights = flights.len(), "Disruption needs manual handling");
+        self.pending_manual.push((event.clone(), flights));
+    }
+
+    /// Flights some disruption left for manual handling, oldest first.
+    pub fn pending_manual_flights(&self) -> impl Iterator<Item = &Flight> {
+        self.pending_manual.iter().flat_map(|(_, flights)| flights)
+    }
+
+    /// Take the disruptions awaiting manual handling, e.g. once a dispatcher has dealt with
+    /// them.
+    pub fn clear_pending_manual(&mut self) -> Vec<(DisruptionEvent, Vec<Flight>)> {
+        std::mem::take(&mut self.pending_manual)
+    }
+
+    /// Give a re-allocated assignment the schedule of the one it replaces, so delay metrics keep
+    /// measuring against the original plan.
+    fn inherit_schedule(
+        &mut self,
+        new: &mut GateAssignment,
+        scheduled: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    ) {
+        new.scheduled = scheduled;
+        if let Some(stored) = self
+            .assignments
+            .iter_mut()
+            .find(|a| a.assignment_id == new.assignment_id)
+        {
+            stored.scheduled = scheduled;
+        }
+    }
+
+    /// Indices of the assignments held together with the one at `idx`: just `idx`, or for a
+    /// double stand both gates of the pair, the primary (committed first) ahead of its partner.
+    fn stand_group(&self, idx: usize) -> Vec<usize> {
+        let a = &self.assignments[idx];
+        if !a.flight.requires_double_stand {
+            return vec![idx];
+        }
+        self.assignments
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| {
+                o.flight.flight_id == a.flight.flight_id
+                    && o.airport == a.airport
+                    && o.assigned_from == a.assigned_from
+                    && o.assigned_until == a.assigned_until
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// The flight whose assignment on `gate_id` starts first at or after `event` was reported,
+    /// among the airports the event covers.
+    fn next_on_gate(&self, gate_id: &str, event: &DisruptionEvent) -> Option<String> {
+        self.assignments
+            .iter()
+            .filter(|a| {
+                a.gate.gate_id == gate_id
+                    && event.covers_airport(&a.airport)
+                    && a.assigned_from >= event.reported_at
+            })
+            .min_by_key(|a| a.assigned_from)
+            .map(|a| a.flight.flight_id.clone())
+    }
+
+    /// The next leg flown by `registration` after `flight_id` departs at `departure`: the
+    /// earliest-arriving other assigned flight on the tail arriving no earlier, with its
+    /// arrival.  A turnaround-linked outbound is left out; the link already carries the delay.
+    fn next_leg(
+        &self,
+        registration: &str,
+        flight_id: &str,
+        departure: DateTime<Utc>,
+    ) -> Option<(String, DateTime<Utc>)> {
+        let linked = self.turnaround_links.get(flight_id);
+        self.assignments
+            .iter()
+            .map(|a| &a.flight)
+            .filter(|f| {
+                f.registration.as_deref() == Some(registration)
+                    && f.flight_id != flight_id
+                    && Some(&f.flight_id) != linked
+                    && f.scheduled_arrival >= departure
+            })
+            .min_by_key(|f| f.scheduled_arrival)
+            .map(|f| (f.flight_id.clone(), f.scheduled_arrival))
+    }
+
+    /// A note for moving `flight_id` from `from_gate` to `to_gate` when its arrival is within
+    /// `config.gate_change_notice_minutes` of `now`; `None` otherwise.
+    fn short_notice_move(
+        &self,
+        flight_id: &str,
+        from_gate: &str,
+        to_gate: &str,
+        arrival: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Option<DisruptionNote> {
+        let minutes_to_arrival = (arrival - now).num_minutes();
+        let notice = self.config.gate_change_notice_minutes?;
+        (from_gate != to_gate && minutes_to_arrival < notice).then(|| {
+            warn!(
+                flight = flight_id,
+                from = from_gate,
+                to = to_gate,
+                minutes_to_arrival,
+                "Short-notice gate change"
+            );
+            DisruptionNote::ShortNoticeGateChange {
+                flight_id: flight_id.to_string(),
+                from_gate: from_gate.to_string(),
+                to_gate: to_gate.to_string(),
+                minutes_to_arrival,
+            }
+        })
+    }
+
+    /// Keep the delayed assignment at `idx` on its gate with the new window and re-allocate the
+    /// assignments in its way instead, when that is cheaper under
+    /// `EngineConfig::confirmed_move_penalty` (typically: the delayed flight is confirmed and
+    /// the others are provisional) or, with `force`, whenever possible.  The displaced
+    /// assignments are added to `superseded`.  `None`, with nothing changed, when moving the
+    /// delayed flight is cheaper or a displaced flight finds no other gate.  Double stands are
+    /// neither displaced nor displaced for, since a pair cannot move one gate at a time.
+    fn displace_blockers(
+        &mut self,
+        idx: usize,
+        new_from: DateTime<Utc>,
+        new_until: DateTime<Utc>,
+        is_late: bool,
+        force: bool,
+        superseded: &mut Vec<Uuid>,
+    ) -> Option<Vec<GateAssignment>> {
+        let delayed = &self.assignments[idx];
+        let blockers: Vec<GateAssignment> = self
+            .assignments
+            .iter()
+            .filter(|o| {
+                o.flight.flight_id != delayed.flight.flight_id
+                    && o.gate.gate_id == delayed.gate.gate_id
+                    && self.crowds(o, new_from, new_until)
+            })
+            .cloned()
+            .collect();
+        let cost = |a: &GateAssignment| {
+            1.0 + match a.status {
+                AssignmentStatus::Confirmed => self.config.confirmed_move_penalty,
+                _ => 0.0,
+            }
+        };
+        if delayed.flight.requires_double_stand
+            || blockers
+                .iter()
+                .any(|b| b.is_locked() || b.flight.requires_double_stand)
+            || (!force && blockers.iter().map(cost).sum::<f64>() >= cost(delayed))
+        {
+            return None;
+        }
+
+        let saved = self.assignments.clone();
+        let a = &mut self.assignments[idx];
+        a.assigned_from = new_from;
+        a.assigned_until = new_until;
+        if is_late {
+            a.flight.status = FlightStatus::Delayed;
+        }
+        let mut moved = vec![a.clone()];
+        for old in &blockers {
+            let Some(mut new_a) = self
+                .reallocate(&old.flight, &old.airport, &[], &old.gate.terminal)
+                .assignment
+            else {
+                self.assignments = saved;
+                return None;
+            };
+            info!(flight = %old.flight.flight_id, from = %old.gate.gate_id, to = %new_a.gate.gate_id, "Moved out of a confirmed flight's way");
+            self.inherit_schedule(&mut new_a, old.scheduled);
+            moved.push(new_a);
+        }
+        superseded.extend(blockers.iter().map(|b| b.assignment_id));
+        Some(moved)
+    }
+
+    /// Keep the delayed assignments in `group` (see `stand_group`) on their gates with the new
+    /// window and push each following flight on those gates back just far enough to clear the
+    /// ones before it by `config.min_gap_minutes`; a pushed double stand takes both its gates
+    /// along.  Fails, restoring every assignment, once more than `config.max_cascade_depth`
+    /// flights would be pushed or the chain reaches a locked assignment.  Only assignments are
+    /// considered; reservations and closures are not.
+    fn cascade_delay(
+        &mut self,
+        group: &[usize],
+        new_from: DateTime<Utc>,
+        new_until: DateTime<Utc>,
+        is_late: bool,
+    ) -> Result<Vec<GateAssignment>, DisruptionNote> {
+        let saved = self.assignments.clone();
+        let gate_id = self.assignments[group[0]].gate.gate_id.clone();
+        let gap = Duration::minutes(self.config.min_gap_minutes);
+        let mut chain = group.to_vec();
+        let mut pushed = 0;
+        for &i in group {
+            self.assignments[i].assigned_from = new_from;
+            self.assignments[i].assigned_until = new_until;
+        }
+        // How far `a` must move to clear the moved assignment at `c`, if `c` is in its way.
+        let overlap = |e: &Self, a: &GateAssignment, c: usize| {
+            let moved = &e.assignments[c];
+            (a.gate.gate_id == moved.gate.gate_id
+                && e.crowds(a, moved.assigned_from, moved.assigned_until))
+            .then(|| moved.assigned_until + gap - a.assigned_from)
+        };
+
+        loop {
+            let next = self
+                .assignments
+                .iter()
+                .enumerate()
+                .filter(|(i, a)| {
+                    !chain.contains(i) && chain.iter().any(|&c| overlap(self, a, c).is_some())
+                })
+                .min_by_key(|(_, a)| a.assigned_from)
+                .map(|(i, _)| i);
+            let Some(next) = next else { break };
+            let next_group = self.stand_group(next);
+            if pushed >= self.config.max_cascade_depth
+                || next_group.iter().any(|&i| self.assignments[i].is_locked())
+            {
+                let mut unresolved: Vec<String> = chain
+                    .iter()
+                    .chain(&next_group)
+                    .map(|&i| self.assignments[i].flight.flight_id.clone())
+                    .collect();
+                unresolved.dedup();
+                self.assignments = saved;
+                warn!(gate = %gate_id, unresolved = ?unresolved, "Delay cascade aborted");
+                return Err(DisruptionNote::CascadeLimitReached { unresolved });
+            }
+            let push = next_group
+                .iter()
+                .flat_map(|&n| chain.iter().map(move |&c| (n, c)))
+                .filter_map(|(n, c)| overlap(self, &self.assignments[n], c))
+                .fold(Duration::zero(), std::cmp::max);
+            for &n in &next_group {
+                let a = &mut self.assignments[n];
+                a.assigned_from += push;
+                a.assigned_until += push;
+                a.flight.status = FlightStatus::Delayed;
+                info!(flight = %a.flight.flight_id, gate = %a.gate.gate_id, minutes = push.num_minutes(), "Knock-on delay");
+            }
+            chain.extend(next_group);
+            pushed += 1;
+        }
+        if is_late {
+            for &i in group {
+                self.assignments[i].flight.status = FlightStatus::Delayed;
+            }
+        }
+        Ok(chain.iter().map(|&i| self.assignments[i].clone()).collect())
+    }
+
+    /// Handle a disruption event with automatic re-allocation.
+    pub fn handle_disruption(&mut self, event: DisruptionEvent) -> DisruptionResult {
+        let last_applied = self.disruptions.iter().map(|d| d.reported_at).max();
+        if let Some(last) = last_applied
+            .filter(|&last| self.config.strict_disruption_order && event.reported_at < last)
+        {
+            warn!(event = %event.event_id, reported_at = %event.reported_at, %last, "Stale disruption refused");
+            return DisruptionResult {
+                acknowledged: false,
+                reassignments: Vec::new(),
+                previous: Vec::new(),
+                summary: format!(
+                    "Refused {}: reported at {} before the last applied disruption ({})",
+                    event.event_id,
+                    event.reported_at.format("%H:%M:%S"),
+                    last.format("%H:%M:%S")
+                ),
+                notes: Vec::new(),
+                estimated_reallocation_cost: 0.0,
+            };
+        }
+        let before = self.assignments.clone();
+        let mut result = self.tracked(|e| e.apply_disruption(event));
+        result.estimated_reallocation_cost = changes::reallocation_cost(&before, &self.assignments);
+        result.previous = before
+            .into_iter()
+            .filter(|a| !self.assignments.contains(a))
+            .collect();
+        result.previous.sort_by_key(|a| a.assigned_from);
+        result
+    }
+
+    /// What `handle_disruption` would do with `event`, worked out on a copy so a dispatcher can
+    /// review the reassignments before applying them.  The engine is left untouched; new
+    /// assignment ids in the preview are not the ones a real apply will issue.
+    pub fn preview_disruption(&self, event: DisruptionEvent) -> DisruptionResult {
+        let mut copy = self.planning_copy();
+        copy.disruptions = self.disruptions.clone();
+        copy.handle_disruption(event)
+    }
+
+    /// Apply `events` in `reported_at` order, whatever order they arrived in; events reported
+    /// at the same instant keep their arrival order.  Returns one result per event, in the
+    /// order applied.
+    pub fn handle_disruptions(
+        &mut self,
+        mut events: Vec<DisruptionEvent>,
+    ) -> Vec<DisruptionResult> {
+        events.sort_by_key(|e| e.reported_at);
+        events
+            .into_iter()
+            .map(|event| self.handle_disruption(event))
+            .collect()
+    }
+
+    fn apply_disruption(&mut self, mut event: DisruptionEvent) -> DisruptionResult {
+        if event.disruption_type == DisruptionType::Delay && event.affected_flight_id.is_empty() {
+            // A gate-driven delay: `description` names the gate, and the flight due on it next
+            // is the one held up.
+            if let Some(next) = self.next_on_gate(&event.description, &event) {
+                info!(gate = %event.description, flight = %next, "Gate delay applies to next flight");
+                event.affected_flight_id = next;
+            }
+        }
+        info!(event = %event.event_id, kind = %event.disruption_type, flight = %event.affected_flight_id, "Disruption");
+        self.disruptions.push(event.clone());
+        self.disrupt(event)
+    }
+
+    /// Apply `event` to the schedule without recording it in the disruption log.  Knock-on
+    /// delays go through here too, so they are reported as part of the disruption that caused
+    /// them rather than as disruptions of their own.
+    fn disrupt(&mut self, event: DisruptionEvent) -> DisruptionResult {
+        let mut reassignments = Vec::new();
+
+        match event.disruption_type {
+            DisruptionType::Delay => {
+                // A negative delay brings the flight forward (expedited departure / early arrival).
+                let delay = Duration::minutes(event.delay_minutes as i64);
+                let is_late = event.delay_minutes > 0;
+                let indices: Vec<usize> = self
+                    .assignments
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, a)| {
+                        a.flight.flight_id == event.affected_flight_id
+                            && event.covers_airport(&a.airport)
+                    })
+                    .map(|(i, _)| i)
+                    .collect();
+                let tail = indices.first().and_then(|&i| {
+                    let f = &self.assignments[i].flight;
+                    f.registration.clone().map(|r| (r, f.scheduled_departure))
+                });
+                // Assignments replaced by a re-allocation, dropped once every index is processed.
+                let mut superseded = Vec::new();
+                let mut notes = Vec::new();
+                let mut stuck = Vec::new();
+                let mut handled = Vec::new();
+
+                for &idx in &indices {
+                    if handled.contains(&idx) {
+                        continue;
+                    }
+                    // Both gates of a double stand move, or stay, together.
+                    let group = self.stand_group(idx);
+                    handled.extend(&group);
+                    let new_from = self.assignments[idx].assigned_from + delay;
+                    let new_until = self.assignments[idx].assigned_until + delay;
+                    let gate_id = self.assignments[idx].gate.gate_id.clone();
+                    let gate_ids: Vec<String> = group
+                        .iter()
+                        .map(|&i| self.assignments[i].gate.gate_id.clone())
+                        .collect();
+                    let terminal = self.assignments[idx].gate.terminal.clone();
+                    let flight_clone = self.assignments[idx].flight.clone();
+                    let airport = self.assignments[idx].airport.clone();
+
+                    // A reservation or closure cannot be moved out of the way like a flight.
+                    let held = self.reservations.iter().any(|r| {
+                        gate_ids.contains(&r.gate_id)
+                            && overlaps((r.from, r.until), (new_from, new_until))
+                    }) || self
+                        .gates
+                        .iter()
+                        .filter(|g| gate_ids.contains(&g.gate_id))
+                        .any(|g| g.is_closed_during(new_from, new_until));
+                    // The flight's own assignments (other legs, or ones moving with it) are
+                    // never in its way.
+                    let conflict = held
+                        || self.assignments.iter().any(|other| {
+                            other.flight.flight_id != event.affected_flight_id
+                                && gate_ids.contains(&other.gate.gate_id)
+                                && self.crowds(other, new_from, new_until)
+                        });
+                    // Too close to arrival to move the flight comfortably: keep its gate.
+                    let hold_gate = self
+                        .config
+                        .gate_change_notice_minutes
+                        .is_some_and(|notice| {
+                            (new_from - event.reported_at).num_minutes() < notice
+                        });
+
+                    if conflict && group.iter().any(|&i| self.assignments[i].is_locked()) {
+                        warn!(flight = %flight_clone.flight_id, gate = %gate_id, "Delay conflict on locked assignment - left unchanged");
+                        stuck.push(flight_clone);
+                    } else if let Some(moved) = (conflict && !held)
+                        .then(|| {
+                            self.displace_blockers(
+                                idx,
+                                new_from,
+                                new_until,
+                                is_late,
+                                hold_gate,
+                                &mut superseded,
+                            )
+                        })
+                        .flatten()
+                    {
+                        reassignments.extend(moved);
+                    } else if conflict {
+                        info!(flight = %flight_clone.flight_id, gate = %gate_id, "Delay conflict - re-allocating");
+                        let mut shifted = flight_clone;
+                        shifted.scheduled_arrival += delay;
+                        shifted.scheduled_departure += delay;
+                        if is_late {
+                            shifted.status = FlightStatus::Delayed;
+                        }
+                        let committed = self.assignments.len();
+                        if let Some(new_a) = self
+                            .reallocate(
+                                &shifted,
+                                &airport,
+                                std::slice::from_ref(&gate_id),
+                                &terminal,
+                            )
+                            .assignment
+                        {
+                            let old = &self.assignments[idx];
+                            let scheduled = old
+                                .scheduled
+                                .or(Some((old.assigned_from, old.assigned_until)));
+                            superseded
+                                .extend(group.iter().map(|&i| self.assignments[i].assignment_id));
+                            notes.extend(self.short_notice_move(
+                                &new_a.flight.flight_id,
+                                &gate_id,
+                                &new_a.gate.gate_id,
+                                new_from,
+                                event.reported_at,
+                            ));
+                            // The new assignment, followed by its partner for a double stand.
+                            for a in &mut self.assignments[committed..] {
+                                a.scheduled = scheduled;
+                                reassignments.push(a.clone());
+                            }
+                        } else {
+                            match self.cascade_delay(&group, new_from, new_until, is_late) {
+                                Ok(shifted) => reassignments.extend(shifted),
+                                Err(note) => {
+                                    if let DisruptionNote::CascadeLimitReached { unresolved } =
+                                        &note
+                                    {
+                                        stuck.extend(unresolved.iter().filter_map(|id| {
+                                            self.assignments
+                                                .iter()
+                                                .find(|a| &a.flight.flight_id == id)
+                                                .map(|a| a.flight.clone())
+                                        }));
+                                    }
+                                    notes.push(note);
+                                }
+                            }
+                        }
+                    } else {
+                        for &i in &group {
+                            let a = &mut self.assignments[i];
+                            a.assigned_from = new_from;
+                            a.assigned_until = new_until;
+                            if is_late {
+                                a.flight.status = FlightStatus::Delayed;
+                            }
+                            info!(flight = %a.flight.flight_id, gate = %a.gate.gate_id, "Window shifted");
+                            reassignments.push(a.clone());
+                        }
+                    }
+                }
+                self.assignments
+                    .retain(|a| !superseded.contains(&a.assignment_id));
+                let mut summary = format!(
+                    "{} {} {}min - {} assignment(s) adjusted",
+                    event.affected_flight_id,
+                    if event.delay_minutes < 0 {
+                        "brought forward"
+                    } else {
+                        "delayed"
+                    },
+                    event.delay_minutes.abs(),
+                    indices.len()
+                );
+                // The aircraft turns around late, so the linked outbound leaves late too.
+                if let Some(outbound) = self
+                    .turnaround_links
+                    .get(&event.affected_flight_id)
+                    .filter(|_| is_late && !indices.is_empty())
+                    .cloned()
+                {
+                    let knock_on = self.disrupt(DisruptionEvent {
+                        event_id: event.event_id,
+                        disruption_type: DisruptionType::Delay,
+                        affected_flight_id: outbound,
+                        description: format!("Late inbound {}", event.affected_flight_id),
+                        reported_at: event.reported_at,
+                        delay_minutes: event.delay_minutes,
+                        airport: event.airport.clone(),
+                    });
+                    summary.push_str(&format!("; {}", knock_on.summary));
+                    reassignments.extend(knock_on.reassignments);
+                    notes.extend(knock_on.notes);
+                }
+                if let Some((registration, departure)) = tail
+                    .filter(|_| is_late && self.config.tail_delay_policy != TailDelayPolicy::Ignore)
+                {
+                    // The aircraft now leaves after it is due back for its next leg.
+                    if let Some((next_flight_id, due)) = self
+                        .next_leg(&registration, &event.affected_flight_id, departure)
+                        .filter(|&(_, due)| departure + delay > due)
+                    {
+                        if self.config.tail_delay_policy == TailDelayPolicy::Cascade {
+                            let knock_on = self.disrupt(DisruptionEvent {
+                                event_id: event.event_id,
+                                disruption_type: DisruptionType::Delay,
+                                affected_flight_id: next_flight_id,
+                                description: format!(
+                                    "Late aircraft {} on {}",
+                                    registration, event.affected_flight_id
+                                ),
+                                reported_at: event.reported_at,
+                                delay_minutes: event.delay_minutes,
+                                airport: None,
+                            });
+                            summary.push_str(&format!("; {}", knock_on.summary));
+                            reassignments.extend(knock_on.reassignments);
+                            notes.extend(knock_on.notes);
+                        } else {
+                            warn!(tail = %registration, flight = %event.affected_flight_id, next = %next_flight_id, "Delay overruns the next leg");
+                            notes.push(DisruptionNote::TailRescheduleRequired {
+                                registration,
+                                flight_id: event.affected_flight_id.clone(),
+                                next_flight_id,
+                                overrun_minutes: (departure + delay - due).num_minutes(),
+                            });
+                        }
+                    }
+                }
+                for note in &notes {
+                    summary.push_str(&format!(" - {}", note));
+                }
+                self.queue_for_manual(&event, stuck);
+                DisruptionResult {
+                    acknowledged: true,
+                    reassignments,
+                    previous: Vec::new(),
+                    summary,
+                    notes,
+                    estimated_reallocation_cost: 0.0,
+                }
+            }
+            DisruptionType::Cancellation => {
+                let before = self.assignments.len();
+                self.assignments.retain(|a| {
+                    a.flight.flight_id != event.affected_flight_id
+                        || !event.covers_airport(&a.airport)
+                });
+                let freed = before - self.assignments.len();
+                info!(flight = %event.affected_flight_id, freed, "Cancelled - gates freed");
+                DisruptionResult {
+                    acknowledged: true,
+                    reassignments: Vec::new(),
+                    previous: Vec::new(),
+                    summary: format!(
+                        "{} cancelled - {} gate(s) freed",
+                        event.affected_flight_id, freed
+                    ),
+                    notes: Vec::new(),
+                    estimated_reallocation_cost: 0.0,
+                }
+            }
+            DisruptionType::GateUnavailable => {
+                // The gate identifier is carried in `description` (e.g. "T5-A1").
+                let gate_id = event.description.clone();
+                // A double stand losing either gate gives up both and is re-allocated as a pair.
+                let mut groups: Vec<Vec<usize>> = Vec::new();
+                for (i, a) in self.assignments.iter().enumerate() {
+                    if a.gate.gate_id == gate_id
+                        && !a.is_locked()
+                        && !groups.iter().any(|g| g.contains(&i))
+                    {
+                        groups.push(self.stand_group(i));
+                    }
+                }
+                let released: Vec<Uuid> = groups
+                    .iter()
+                    .flatten()
+                    .map(|&i| self.assignments[i].assignment_id)
+                    .collect();
+                let affected: Vec<GateAssignment> = groups
+                    .iter()
+                    .map(|g| self.assignments[g[0]].clone())
+                    .collect();
+                let mut stuck: Vec<Flight> = self
+                    .assignments
+                    .iter()
+                    .filter(|a| a.gate.gate_id == gate_id && a.is_locked())
+                    .map(|a| a.flight.clone())
+                    .collect();
+                let locked = stuck.len();
+
+                if let Some(g) = self.gates.iter_mut().find(|g| g.gate_id == gate_id) {
+                    g.is_available = false;
+                }
+                // Locked assignments stay put for the dispatcher to resolve by hand.
+                self.assignments.retain(|a| {
+                    (a.gate.gate_id != gate_id || a.is_locked())
+                        && !released.contains(&a.assignment_id)
+                });
+                if locked > 0 {
+                    warn!(gate = %gate_id, locked, "Locked assignment(s) left on unavailable gate");
+                }
+
+                let mut notes = Vec::new();
+                for old in &affected {
+                    let terminal = &old.gate.terminal;
+                    let committed = self.assignments.len();
+                    if let Some(a) = self
+                        .reallocate(&old.flight, &old.airport, &[], terminal)
+                        .assignment
+                    {
+                        notes.extend(self.short_notice_move(
+                            &old.flight.flight_id,
+                            &gate_id,
+                            &a.gate.gate_id,
+                            old.assigned_from,
+                            event.reported_at,
+                        ));
+                        for a in &mut self.assignments[committed..] {
+                            a.scheduled = old.scheduled;
+                            reassignments.push(a.clone());
+                        }
+                    } else {
+                        warn!(flight = %old.flight.flight_id, "Re-allocation failed after gate loss");
+                        stuck.push(old.flight.clone());
+                    }
+                }
+                self.queue_for_manual(&event, stuck);
+                DisruptionResult {
+                    acknowledged: true,
+                    reassignments,
+                    previous: Vec::new(),
+                    summary: if locked > 0 {
+                        format!(
+                            "Gate {} unavailable - {} flight(s) re-allocated, {} locked left in place",
+                            gate_id,
+                            affected.len(),
+                            locked
+                        )
+                    } else {
+                        format!(
+                            "Gate {} unavailable - {} flight(s) re-allocated",
+                            gate_id,
+                            affected.len()
+                        )
+                    },
+                    notes,
+                    estimated_reallocation_cost: 0.0,
+                }
+            }
+            // Diversion removes the flight's assignment (it is landing elsewhere).
+            // Weather and Mechanical are informational — no gate changes are made here;
+            // the operator handles them manually via subsequent AllocateGate / ReportDisruption calls.
+            _ => {
+                if event.disruption_type == DisruptionType::Diversion {
+                    self.assignments.retain(|a| {
+                        a.flight.flight_id != event.affected_flight_id
+                            || !event.covers_airport(&a.airport)
+                    });
+                }
+                DisruptionResult {
+                    acknowledged: true,
+                    reassignments: Vec::new(),
+                    previous: Vec::new(),
+                    summary: format!(
+                        "{} event for {}",
+                        event.disruption_type, event.affected_flight_id
+                    ),
+                    notes: Vec::new(),
+                    estimated_reallocation_cost: 0.0,
+                }
+            }
+        }
+    }
+
+    pub fn get_assignments(&self, terminal: Option<&str>) -> Vec<&GateAssignment> {
+        snapshot::assignments_in(&self.assignments, terminal)
+    }
+
+    /// Assignments on `gate_id` in start-time order.
+    pub fn gate_timeline(&self, gate_id: &str) -> Vec<&GateAssignment> {
+        snapshot::timeline(&self.assignments, gate_id)
+    }
+
+    /// Gates that could ever take an aircraft of `size`, optionally only in `terminal`, having
+    /// every one of `features`.  Static attributes only: current availability, closures and
+    /// assignments are ignored.  In registration order.
+    pub fn compatible_gates(
+        &self,
+        size: AircraftSize,
+        terminal: Option<&str>,
+        features: &HashSet<GateFeature>,
+    ) -> Vec<&Gate> {
+        self.gates
+            .iter()
+            .filter(|g| g.can_accommodate(size))
+            .filter(|g| terminal.is_none_or(|t| g.terminal == t))
+            .filter(|g| features.iter().all(|&f| g.has_feature(f)))
+            .collect()
+    }
+
+    /// Gate counts (total and currently in service) overall, by terminal, by size and by
+    /// terminal × size.
+    pub fn inventory(&self) -> GateInventory {
+        snapshot::inventory_of(&self.gates)
+    }
+
+    pub fn stats(&self) -> EngineStats {
+        snapshot::stats_of(&self.gates, &self.assignments, self.disruptions.len())
+    }
+
+    /// Every disruption applied so far, in the order applied.
+    pub fn disruption_log(&self) -> &[DisruptionEvent] {
+        &self.disruptions
+    }
+
+    /// The logged disruptions `query` matches, in the order applied.
+    pub fn disruptions_matching(&self, query: &DisruptionQuery) -> Vec<&DisruptionEvent> {
+        self.disruptions
+            .iter()
+            .filter(|e| query.matches(e))
+            .collect()
+    }
+
+    /// Recorded disruptions counted by type and by hour of day (UTC) of `reported_at`.
+    pub fn disruption_stats(&self) -> DisruptionStats {
+        let mut stats = DisruptionStats::default();
+        for event in &self.disruptions {
+            *stats.by_type.entry(event.disruption_type).or_default() += 1;
+            *stats.by_hour.entry(event.reported_at.hour()).or_default() += 1;
+        }
+        stats
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InventoryCount {
+    pub total: usize,
+    pub available: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct GateInventory {
+    pub overall: InventoryCount,
+    pub by_terminal: BTreeMap<String, InventoryCount>,
+    pub by_size: BTreeMap<AircraftSize, InventoryCount>,
+    pub by_terminal_size: BTreeMap<(String, AircraftSize), InventoryCount>,
+}
+
+#[derive(Debug)]
+pub struct EngineStats {
+    pub total_gates: usize,
+    pub available_gates: usize,
+    pub occupied_gates: usize,
+    pub total_disruptions: usize,
+    /// Assignments currently running later than scheduled.
+    pub delayed_assignments: usize,
+    /// Mean delay over `delayed_assignments`, in minutes (0 when none are delayed).
+    pub average_delay_minutes: f64,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct DisruptionStats {
+    pub by_type: BTreeMap<DisruptionType, usize>,
+    /// Hour of day (0-23, UTC) -> disruptions reported in that hour.
+    pub by_hour: BTreeMap<u32, usize>,
+}
+
+impl fmt::Display for EngineStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Gates: {}/{} available | Assignments: {} | Disruptions: {} | Delayed: {} (avg {:.0}min)",
+            self.available_gates,
+            self.total_gates,
+            self.occupied_gates,
+            self.total_disruptions,
+            self.delayed_assignments,
+            self.average_delay_minutes
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::{at, flight, gate};
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn allocates_right_sized_gate() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("A2", "T5", AircraftSize::Large));
+        assert_eq!(
+            e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &[])
+                .assignment
+                .unwrap()
+                .gate
+                .gate_id,
+            "A1"
+        );
+    }
+
+    #[test]
+    fn rejects_undersized_gate() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Small));
+        assert!(
+            !e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &[])
+                .success
+        );
+    }
+
+    #[test]
+    fn detects_time_conflicts() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        assert!(
+            e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &[])
+                .success
+        );
+        assert!(
+            !e.allocate_gate(&flight("F2", "A320", 11, 13), "LHR", &[])
+                .success
+        );
+    }
+
+    #[test]
+    fn cancellation_frees_gate() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &[]);
+        e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::Cancellation,
+            affected_flight_id: "F1".into(),
+            description: "Cancelled".into(),
+            reported_at: Utc::now(),
+            delay_minutes: 0,
+            airport: None,
+        });
+        assert!(
+            e.allocate_gate(&flight("F2", "A320", 11, 13), "LHR", &[])
+                .success
+        );
+    }
+
+    #[test]
+    fn prefers_requested_gate() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("B1", "T5", AircraftSize::Medium));
+        let r = e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &["B1".into()]);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "B1");
+    }
+
+    #[test]
+    fn scheduled_closure_blocks_only_its_window() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        let d = |h| Utc.with_ymd_and_hms(2026, 3, 1, h, 0, 0).unwrap();
+        e.schedule_closure("A1", d(10), d(14)).unwrap();
+        assert_eq!(e.closures("A1"), &[(d(10), d(14))]);
+
+        assert!(
+            !e.allocate_gate(&flight("F1", "A320", 11, 12), "LHR", &[])
+                .success
+        );
+        assert!(
+            e.allocate_gate(&flight("F2", "A320", 7, 9), "LHR", &[])
+                .success
+        );
+        assert!(
+            e.allocate_gate(&flight("F3", "A320", 14, 16), "LHR", &[])
+                .success
+        );
+    }
+
+    #[test]
+    fn schedule_closure_rejects_unknown_gate() {
+        let mut e = AllocationEngine::new();
+        let d = |h| Utc.with_ymd_and_hms(2026, 3, 1, h, 0, 0).unwrap();
+        assert!(e.schedule_closure("Z9", d(10), d(14)).is_err());
+    }
+
+    #[test]
+    fn classifies_international_and_domestic_flights() {
+        let e = AllocationEngine::new();
+        let mut f = flight("BA-117", "B777", 10, 12);
+        f.origin = "JFK".into();
+        f.destination = "LHR".into();
+        assert!(e.is_international(&f));
+        f.origin = "LHR".into();
+        f.destination = "EDI".into();
+        assert!(!e.is_international(&f));
+    }
+
+    #[test]
+    fn default_airport_applies_unless_one_is_given() {
+        let mut e = AllocationEngine::new().with_default_airport("lhr");
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-A2", "T5", AircraftSize::Medium));
+
+        let implicit = e.allocate(&flight("F1", "A320", 8, 10), &[]);
+        assert_eq!(implicit.assignment.unwrap().airport, "LHR");
+        let explicit = e.allocate_gate(&flight("F2", "A320", 8, 10), "LGW", &[]);
+        assert_eq!(explicit.assignment.unwrap().airport, "LGW");
+
+        // Without a default the flight is handled where it lands.
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        let landed = e.allocate(&flight("F3", "A320", 8, 10), &[]);
+        assert_eq!(landed.assignment.unwrap().airport, "JFK");
+    }
+
+    #[test]
+    fn busy_flight_gets_the_contact_gate() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        let mut remote = gate("R1", "T5", AircraftSize::Medium);
+        remote.remote = true;
+        e.add_gate(remote);
+        let with_pax = |id: &str, pax| Flight {
+            passenger_count: Some(pax),
+            ..flight(id, "A321", 8, 10)
+        };
+
+        let light = e.allocate_gate(&with_pax("F1", 50), "LHR", &[]);
+        let busy = e.allocate_gate(&with_pax("F2", 300), "LHR", &[]);
+        assert_eq!(light.assignment.unwrap().gate.gate_id, "R1");
+        assert_eq!(busy.assignment.unwrap().gate.gate_id, "T5-A1");
+    }
+
+    #[test]
+    fn flights_stay_in_airline_terminal_until_it_fills() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T2-A1", "T2", AircraftSize::Medium));
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-A2", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 8, 10), "LHR", &["T5-A1".into()]);
+        let gate_of = |r: AllocationResult| r.assignment.unwrap().gate.gate_id;
+
+        assert_eq!(
+            gate_of(e.allocate_gate(&flight("F2", "A320", 8, 10), "LHR", &[])),
+            "T5-A2"
+        );
+        // T5 is full now, so locality gives way.
+        assert_eq!(
+            gate_of(e.allocate_gate(&flight("F3", "A320", 8, 10), "LHR", &[])),
+            "T2-A1"
+        );
+
+        let mut anywhere = AllocationEngine::with_config(EngineConfig {
+            terminal_locality_penalty: None,
+            ..EngineConfig::default()
+        });
+        for id in ["T2-A1", "T5-A1", "T5-A2"] {
+            anywhere.add_gate(gate(id, &id[..2], AircraftSize::Medium));
+        }
+        anywhere.allocate_gate(&flight("F1", "A320", 8, 10), "LHR", &["T5-A1".into()]);
+        let f2 = anywhere.allocate_gate(&flight("F2", "A320", 8, 10), "LHR", &[]);
+        assert_eq!(gate_of(f2), "T2-A1");
+    }
+
+    #[test]
+    fn ferry_flight_takes_remote_stand_with_short_buffer() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        let mut remote = gate("R1", "T5", AircraftSize::Medium);
+        remote.remote = true;
+        e.add_gate(remote);
+        let ferry = Flight {
+            is_ferry: true,
+            ..flight("P1", "A320", 8, 10)
+        };
+
+        let placed = e.allocate_gate(&ferry, "LHR", &[]).assignment.unwrap();
+        assert_eq!(placed.gate.gate_id, "R1");
+        assert_eq!(placed.assigned_until, at(10, 5));
+        let normal = e.allocate_gate(&flight("F1", "A320", 12, 14), "LHR", &[]);
+        assert_eq!(normal.assignment.unwrap().gate.gate_id, "T5-A1");
+    }
+
+    #[test]
+    fn linked_outbound_stays_on_inbound_gate() {
+        let mut e = AllocationEngine::with_config(EngineConfig {
+            same_gate_turnarounds: true,
+            ..EngineConfig::default()
+        });
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("A2", "T5", AircraftSize::Medium));
+        e.link_turnaround("IN1", "OUT1").unwrap();
+        e.link_turnaround("IN2", "OUT2").unwrap();
+        e.allocate_gate(&flight("IN1", "A320", 8, 10), "LHR", &["A1".into()]);
+
+        let out = e.allocate_gate(&flight("OUT1", "A320", 11, 13), "LHR", &["A2".into()]);
+        let out = out.assignment.unwrap();
+        assert_eq!(out.gate.gate_id, "A1");
+        assert_eq!(out.assigned_from, at(10, 15));
+
+        // Another flight uses A2 while the second aircraft would be waiting there.
+        e.allocate_gate(&flight("IN2", "A320", 8, 9), "LHR", &["A2".into()]);
+        e.allocate_gate(&flight("F3", "A320", 10, 11), "LHR", &["A2".into()]);
+        let refused = e.allocate_gate(&flight("OUT2", "A320", 12, 13), "LHR", &[]);
+        assert!(refused.assignment.is_none());
+        assert_eq!(
+            refused.error,
+            Some(AllocationError::GateRefused {
+                gate_id: "A2".into(),
+                reason: GateRefusal::Occupied,
+            })
+        );
+    }
+
+    #[test]
+    fn linked_outbound_is_planned_onto_the_inbound_gate_at_its_airport() {
+        let mut e = AllocationEngine::with_config(EngineConfig {
+            same_gate_turnarounds: true,
+            ..EngineConfig::default()
+        });
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("A2", "T5", AircraftSize::Medium));
+        e.link_turnaround("IN1", "OUT1").unwrap();
+        e.allocate_gate(&flight("IN1", "A320", 8, 10), "LGW", &["A1".into()]);
+
+        // The inbound landed at LGW, so it does not pin the outbound at LHR.
+        let lhr = e.plan_allocation(&flight("OUT1", "A320", 11, 13), "LHR", &["A2".into()]);
+        assert_eq!(lhr.unwrap().gate_id, "A2");
+
+        // Planned and committed separately, as the gRPC service does.
+        let plan = e
+            .plan_allocation(&flight("OUT1", "A320", 11, 13), "LGW", &["A2".into()])
+            .unwrap();
+        assert_eq!((plan.gate_id.as_str(), plan.from), ("A1", at(10, 15)));
+        let out = e.commit_plan(plan).assignment.unwrap();
+        assert_eq!(
+            (out.gate.gate_id.as_str(), out.airport.as_str()),
+            ("A1", "LGW")
+        );
+    }
+
+    #[test]
+    fn unknown_type_is_sized_by_wingspan() {
+        assert_eq!(AircraftSize::from_wingspan(52.0), AircraftSize::Large);
+        assert_eq!(AircraftSize::from_wingspan(35.8), AircraftSize::Medium);
+        assert_eq!(AircraftSize::from_wingspan(20.0), AircraftSize::Small);
+
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-M1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-L1", "T5", AircraftSize::Large));
+        let custom = Flight {
+            wingspan_m: Some(52.0),
+            ..flight("X1", "C919X", 8, 10)
+        };
+        assert_eq!(custom.aircraft_size(), AircraftSize::Large);
+        let placed = e.allocate_gate(&custom, "LHR", &[]).assignment.unwrap();
+        assert_eq!(placed.gate.gate_id, "T5-L1");
+        // A known designator is not overridden by a stated wingspan.
+        let a320 = Flight {
+            wingspan_m: Some(52.0),
+            ..flight("F1", "A320", 8, 10)
+        };
+        assert_eq!(a320.aircraft_size(), AircraftSize::Medium);
+    }
+
+    #[test]
+    fn gate_block_is_reserved_all_or_nothing() {
+        let mut e = AllocationEngine::new();
+        for id in ["T5-A1", "T5-A2", "T5-A3"] {
+            e.add_gate(gate(id, "T5", AircraftSize::Medium));
+        }
+        e.allocate_gate(&flight("F1", "A320", 9, 10), "LHR", &["T5-A3".into()]);
+        let block: Vec<String> = ["T5-A1", "T5-A2", "T5-A3"].map(String::from).to_vec();
+
+        let err = e.reserve_block(&block, at(8, 0), at(12, 0), "State visit");
+        assert_eq!(err.unwrap_err(), "Cannot reserve block: T5-A3 occupied");
+        assert!(e.reservations.is_empty());
+
+        let ids = e
+            .reserve_block(&block[..2], at(8, 0), at(12, 0), "State visit")
+            .unwrap();
+        assert_eq!(ids.len(), 2);
+        let r = e.allocate_gate(&flight("F2", "A320", 10, 11), "LHR", &[]);
+        assert_eq!(r.error, Some(AllocationError::NoCompatibleGate));
+
+        assert_eq!(e.release_block(&ids).len(), 2);
+        assert!(e.reservations.is_empty());
+    }
+
+    #[test]
+    fn delay_moves_provisional_flight_before_confirmed_one() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("A2", "T5", AircraftSize::Medium));
+        let confirmed = e
+            .allocate_gate(&flight("C1", "A320", 8, 10), "LHR", &["A1".into()])
+            .assignment
+            .unwrap();
+        e.confirm_assignment(confirmed.assignment_id).unwrap();
+        e.allocate_gate(&flight("P1", "A320", 11, 12), "LHR", &["A1".into()]);
+
+        // Either flight could move to A2; the provisional one does.
+        let r = e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::Delay,
+            affected_flight_id: "C1".into(),
+            description: String::new(),
+            reported_at: Utc::now(),
+            delay_minutes: 60,
+            airport: None,
+        });
+        let gate_of = |id: &str| {
+            e.assignments
+                .iter()
+                .find(|a| a.flight.flight_id == id)
+                .map(|a| (a.gate.gate_id.clone(), a.assigned_from))
+                .unwrap()
+        };
+        assert_eq!(gate_of("C1"), ("A1".to_string(), at(9, 0)));
+        assert_eq!(gate_of("P1"), ("A2".to_string(), at(11, 0)));
+        assert_eq!(e.assignments.len(), 2);
+        assert_eq!(r.reassignments.len(), 2);
+    }
+
+    #[test]
+    fn gate_delay_shifts_next_flight_on_the_gate() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 8, 10), "LHR", &[]);
+        e.allocate_gate(&flight("F2", "A320", 11, 12), "LHR", &[]);
+        e.allocate_gate(&flight("F3", "A320", 13, 14), "LHR", &[]);
+
+        // F1's aircraft has not pushed back; F2 is next on A1.
+        let r = e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::Delay,
+            affected_flight_id: String::new(),
+            description: "A1".into(),
+            reported_at: at(10, 30),
+            delay_minutes: 30,
+            airport: None,
+        });
+        assert_eq!(r.reassignments.len(), 1);
+        assert_eq!(r.reassignments[0].flight.flight_id, "F2");
+        assert_eq!(r.reassignments[0].assigned_from, at(11, 30));
+        assert_eq!(e.disruptions[0].affected_flight_id, "F2");
+    }
+
+    #[test]
+    fn capped_airline_is_refused_a_third_concurrent_gate() {
+        let mut e = AllocationEngine::new();
+        for id in ["A1", "A2", "A3"] {
+            e.add_gate(gate(id, "T5", AircraftSize::Medium));
+        }
+        e.cap_airline("Test", 2);
+        e.allocate_gate(&flight("F1", "A320", 8, 10), "LHR", &[]);
+        e.allocate_gate(&flight("F2", "A320", 9, 11), "LHR", &[]);
+
+        let third = e.allocate_gate(&flight("F3", "A320", 9, 12), "LHR", &[]);
+        assert_eq!(
+            third.error,
+            Some(AllocationError::AirlineCapReached {
+                airline: "Test".into(),
+                cap: 2,
+            })
+        );
+        // Once F1 is off its gate the airline is back under its cap.
+        assert!(
+            e.allocate_gate(&flight("F4", "A320", 11, 12), "LHR", &[])
+                .success
+        );
+        let other = Flight {
+            airline: "Other".into(),
+            ..flight("F5", "A320", 9, 10)
+        };
+        assert!(e.allocate_gate(&other, "LHR", &[]).success);
+    }
+
+    #[test]
+    fn gate_accepts_reports_the_first_failing_constraint() {
+        let mut e = AllocationEngine::new();
+        let f = flight("F1", "A320", 10, 12);
+        let window = Some((at(10, 0), at(12, 15)));
+        let check =
+            |e: &AllocationEngine, g: Gate| g.accepts(&f, &e.gate_context(&f, window, false));
+        let base = || gate("G1", "T1", AircraftSize::Medium);
+
+        assert_eq!(check(&e, base()), Ok(()));
+        assert_eq!(
+            check(&e, gate("G1", "T1", AircraftSize::Small)),
+            Err(RejectionReason::TooSmall)
+        );
+        assert_eq!(
+            check(
+                &e,
+                Gate {
+                    is_available: false,
+                    ..base()
+                }
+            ),
+            Err(RejectionReason::OutOfService)
+        );
+        assert_eq!(
+            check(
+                &e,
+                Gate {
+                    max_occupancy_minutes: Some(60),
+                    ..base()
+                }
+            ),
+            Err(RejectionReason::OccupancyLimit)
+        );
+        assert_eq!(
+            check(
+                &e,
+                Gate {
+                    unavailable_windows: vec![(at(11, 0), at(13, 0))],
+                    ..base()
+                }
+            ),
+            Err(RejectionReason::Closed)
+        );
+        assert_eq!(
+            check(
+                &e,
+                Gate {
+                    excluded_types: vec!["a320".into()],
+                    ..base()
+                }
+            ),
+            Err(RejectionReason::TypeExcluded)
+        );
+        assert_eq!(
+            check(
+                &e,
+                Gate {
+                    jetbridge_config: JetbridgeConfig::RightOnly,
+                    staffed_shifts: Vec::new(),
+                    ..base()
+                }
+            ),
+            Err(RejectionReason::DoorMismatch)
+        );
+        assert_eq!(
+            check(
+                &e,
+                Gate {
+                    gate_class: GateClass::Domestic,
+                    ..base()
+                }
+            ),
+            Err(RejectionReason::ClassMismatch)
+        );
+        e.add_lease(GateLease {
+            airline: "Test".into(),
+            terminal: "T2".into(),
+            hours: None,
+        });
+        assert_eq!(check(&e, base()), Err(RejectionReason::NotLeased));
+        assert!(base()
+            .accepts(&f, &e.gate_context(&f, window, true))
+            .is_ok());
+        e.add_gate(Gate {
+            permitted_types: vec!["A320".into()],
+            ..gate("G2", "T2", AircraftSize::Medium)
+        });
+        assert_eq!(
+            check(&e, base()),
+            Err(RejectionReason::TypeDesignatedElsewhere)
+        );
+
+        // The engine's own refusals come from the same check.
+        e.add_gate(Gate {
+            gate_class: GateClass::Domestic,
+            ..gate("G3", "T2", AircraftSize::Medium)
+        });
+        assert_eq!(
+            e.check_gate_at(&f, "G3", at(10, 0), at(12, 15))
+                .unwrap_err(),
+            GateRefusal::NotPermitted
+        );
+    }
+
+    #[test]
+    fn previewing_a_disruption_leaves_the_engine_unchanged() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("A2", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 8, 10), "LHR", &["A1".into()]);
+        e.allocate_gate(&flight("F2", "A320", 11, 13), "LHR", &["A1".into()]);
+        let outage = DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::GateUnavailable,
+            affected_flight_id: String::new(),
+            description: "A1".into(),
+            reported_at: at(7, 0),
+            delay_minutes: 0,
+            airport: None,
+        };
+        let moves = |r: &DisruptionResult| -> Vec<(String, String)> {
+            r.reassignments
+                .iter()
+                .map(|a| (a.flight.flight_id.clone(), a.gate.gate_id.clone()))
+                .collect()
+        };
+        let state = |e: &AllocationEngine| -> Vec<(String, String, DateTime<Utc>)> {
+            e.assignments
+                .iter()
+                .map(|a| {
+                    (
+                        a.flight.flight_id.clone(),
+                        a.gate.gate_id.clone(),
+                        a.assigned_from,
+                    )
+                })
+                .collect()
+        };
+        let before = state(&e);
+
+        let preview = e.preview_disruption(outage.clone());
+        assert_eq!(state(&e), before);
+        assert!(e.disruptions.is_empty());
+        assert!(e.gates.iter().all(|g| g.is_available));
+
+        let applied = e.handle_disruption(outage);
+        assert_eq!(moves(&preview), moves(&applied));
+        assert_eq!(moves(&applied).len(), 2);
+        assert_eq!(preview.summary, applied.summary);
+    }
+
+    #[test]
+    fn interval_helpers_treat_touching_windows_as_free() {
+        let f = flight("F1", "A320", 10, 12);
+        assert_eq!(f.occupancy_window(Duration::zero()), (at(10, 0), at(12, 0)));
+        let (from, until) = f.occupancy_window(Duration::minutes(15));
+        assert_eq!((from, until), (at(10, 0), at(12, 15)));
+
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        let a = e.allocate_gate(&f, "LHR", &[]).assignment.unwrap();
+        assert_eq!((a.assigned_from, a.assigned_until), (from, until));
+        // Touching at either end is not an overlap.
+        assert!(!a.overlaps(at(8, 0), at(10, 0)));
+        assert!(!a.overlaps(at(12, 15), at(13, 0)));
+        // One minute across either boundary is.
+        assert!(a.overlaps(at(8, 0), at(10, 1)));
+        assert!(a.overlaps(at(12, 14), at(13, 0)));
+        assert!(a.overlaps(at(11, 0), at(11, 30)));
+        assert!(a.overlaps(at(6, 0), at(18, 0)));
+    }
+
+    #[test]
+    fn unknown_aircraft_types_follow_config() {
+        let novel = flight("F1", "X99Z", 10, 12);
+        let mut lenient = AllocationEngine::new();
+        lenient.add_gate(gate("M1", "T5", AircraftSize::Medium));
+        let r = lenient.allocate_gate(&novel, "LHR", &[]);
+        assert!(r.success);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "M1");
+
+        let mut strict = AllocationEngine::with_config(EngineConfig {
+            strict_aircraft_types: true,
+            ..EngineConfig::default()
+        });
+        strict.add_gate(gate("M1", "T5", AircraftSize::Medium));
+        let r = strict.allocate_gate(&novel, "LHR", &[]);
+        assert_eq!(
+            r.error,
+            Some(AllocationError::UnknownAircraftType {
+                aircraft_type: "X99Z".into()
+            })
+        );
+        assert!(r.message.contains("X99Z"));
+        let forced = strict.try_allocate_at(&novel, "LHR", "M1", at(10, 0), at(12, 0));
+        assert!(!forced.success);
+        assert!(strict.assignments.is_empty());
+
+        // A wingspan sizes the type even in strict mode; known types are unaffected.
+        let sized = Flight {
+            wingspan_m: Some(34.0),
+            ..novel.clone()
+        };
+        assert!(strict.allocate_gate(&sized, "LHR", &[]).success);
+        assert!(
+            strict
+                .allocate_gate(&flight("F2", "A320", 13, 14), "LHR", &[])
+                .success
+        );
+    }
+
+    #[test]
+    fn late_disruptions_keep_the_gate_near_arrival() {
+        let run = |reported_at: DateTime<Utc>| {
+            let mut e = AllocationEngine::with_config(EngineConfig {
+                gate_change_notice_minutes: Some(60),
+                ..EngineConfig::default()
+            });
+            e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+            e.add_gate(gate("A2", "T5", AircraftSize::Medium));
+            e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &["A1".into()]);
+            e.allocate_gate(&flight("F2", "A320", 13, 15), "LHR", &["A1".into()]);
+            let r = e.handle_disruption(DisruptionEvent {
+                event_id: Uuid::new_v4(),
+                disruption_type: DisruptionType::Delay,
+                affected_flight_id: "F1".into(),
+                description: String::new(),
+                reported_at,
+                delay_minutes: 120,
+                airport: None,
+            });
+            let gate_of = |id: &str| {
+                e.assignments
+                    .iter()
+                    .find(|a| a.flight.flight_id == id)
+                    .map(|a| a.gate.gate_id.clone())
+                    .unwrap()
+            };
+            (gate_of("F1"), gate_of("F2"), r.notes)
+        };
+
+        // Hours ahead of the new 12:00 arrival the delayed flight moves, as usual.
+        let (f1, f2, notes) = run(at(6, 0));
+        assert_eq!((f1.as_str(), f2.as_str()), ("A2", "A1"));
+        assert!(notes.is_empty());
+        // Half an hour before it, the flight keeps its gate and the next one moves instead.
+        let (f1, f2, _) = run(at(11, 30));
+        assert_eq!((f1.as_str(), f2.as_str()), ("A1", "A2"));
+
+        // A lost gate has to be left whatever the notice; the move is flagged.
+        let mut e = AllocationEngine::with_config(EngineConfig {
+            gate_change_notice_minutes: Some(60),
+            ..EngineConfig::default()
+        });
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("A2", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &["A1".into()]);
+        let r = e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::GateUnavailable,
+            affected_flight_id: String::new(),
+            description: "A1".into(),
+            reported_at: at(9, 40),
+            delay_minutes: 0,
+            airport: None,
+        });
+        assert_eq!(
+            r.notes,
+            [DisruptionNote::ShortNoticeGateChange {
+                flight_id: "F1".into(),
+                from_gate: "A1".into(),
+                to_gate: "A2".into(),
+                minutes_to_arrival: 20,
+            }]
+        );
+    }
+
+    #[test]
+    fn terminal_balance_spreads_flights_to_quieter_terminals() {
+        let run = |weight: Option<f64>| {
+            let mut e = AllocationEngine::with_config(EngineConfig {
+                scoring: ScoringConfig {
+                    terminal_balance_weight: weight,
+                    ..ScoringConfig::default()
+                },
+                ..EngineConfig::default()
+            });
+            for (id, terminal) in [
+                ("T1-A", "T1"),
+                ("T1-B", "T1"),
+                ("T2-A", "T2"),
+                ("T2-B", "T2"),
+            ] {
+                e.add_gate(gate(id, terminal, AircraftSize::Medium));
+            }
+            let busy = Flight {
+                airline: "Other".into(),
+                ..flight("F0", "A320", 10, 12)
+            };
+            e.allocate_gate(&busy, "LHR", &["T1-A".into()]);
+            let f = flight("F1", "A320", 10, 12);
+            let balance = e.explain_score("T1-B", &f, &[]).unwrap().balance;
+            let gate = e
+                .allocate_gate(&f, "LHR", &[])
+                .assignment
+                .unwrap()
+                .gate
+                .gate_id;
+            (gate, balance)
+        };
+
+        assert_eq!(run(None), ("T1-B".to_string(), 0.0));
+        // T1 is half busy against a quarter airport-wide.
+        assert_eq!(run(Some(8.0)), ("T2-A".to_string(), 2.0));
+    }
+
+    #[test]
+    fn disruption_log_filters_by_type_flight_and_time() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &[]);
+        let report = |e: &mut AllocationEngine, kind, flight: &str, minute| {
+            e.handle_disruption(DisruptionEvent {
+                event_id: Uuid::new_v4(),
+                disruption_type: kind,
+                affected_flight_id: flight.into(),
+                description: String::new(),
+                reported_at: at(8, minute),
+                delay_minutes: 10,
+                airport: None,
+            });
+        };
+        report(&mut e, DisruptionType::Delay, "F1", 0);
+        report(&mut e, DisruptionType::Weather, "F1", 10);
+        report(&mut e, DisruptionType::Delay, "F2", 20);
+        report(&mut e, DisruptionType::Cancellation, "F1", 30);
+        assert_eq!(e.disruption_log().len(), 4);
+
+        let flights = |q: DisruptionQuery| -> Vec<(DisruptionType, String)> {
+            e.disruptions_matching(&q)
+                .iter()
+                .map(|d| (d.disruption_type, d.affected_flight_id.clone()))
+                .collect()
+        };
+        assert_eq!(
+            flights(DisruptionQuery {
+                types: vec![DisruptionType::Delay],
+                ..Default::default()
+            }),
+            [
+                (DisruptionType::Delay, "F1".to_string()),
+                (DisruptionType::Delay, "F2".to_string())
+            ]
+        );
+        assert_eq!(
+            flights(DisruptionQuery {
+                types: vec![DisruptionType::Delay, DisruptionType::Cancellation],
+                flight_id: Some("F1".into()),
+                ..Default::default()
+            }),
+            [
+                (DisruptionType::Delay, "F1".to_string()),
+                (DisruptionType::Cancellation, "F1".to_string())
+            ]
+        );
+        assert_eq!(
+            flights(DisruptionQuery {
+                from: Some(at(8, 10)),
+                until: Some(at(8, 30)),
+                ..Default::default()
+            }),
+            [
+                (DisruptionType::Weather, "F1".to_string()),
+                (DisruptionType::Delay, "F2".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn recurring_flights_stick_to_yesterdays_gate() {
+        let run = |reward: Option<f64>, closed: bool| {
+            let mut e = AllocationEngine::with_config(EngineConfig {
+                scoring: ScoringConfig {
+                    gate_stickiness_reward: reward,
+                    ..ScoringConfig::default()
+                },
+                ..EngineConfig::default()
+            });
+            for id in ["A1", "A2", "A3"] {
+                e.add_gate(gate(id, "T5", AircraftSize::Medium));
+            }
+            let yesterday = flight("F1", "A320", 8, 10);
+            e.allocate_gate(&yesterday, "LHR", &["A2".into()]);
+            e.advance_to(at(23, 0));
+            assert_eq!(e.history_for("F1").len(), 1);
+
+            let day = Duration::days(1);
+            let today = Flight {
+                scheduled_arrival: yesterday.scheduled_arrival + day,
+                scheduled_departure: yesterday.scheduled_departure + day,
+                ..yesterday
+            };
+            if closed {
+                e.reserve_gate("A2", at(0, 0) + day, at(23, 0) + day, "Works")
+                    .unwrap();
+            }
+            e.allocate_gate(&today, "LHR", &[])
+                .assignment
+                .unwrap()
+                .gate
+                .gate_id
+        };
+
+        assert_eq!(run(None, false), "A1");
+        assert_eq!(run(Some(2.0), false), "A2");
+        // Yesterday's gate is taken: any free gate will do.
+        assert_eq!(run(Some(2.0), true), "A1");
+    }
+
+    #[test]
+    fn unstaffed_gates_are_refused_outside_their_shifts() {
+        let mut e = AllocationEngine::new();
+        let hm = |h| chrono::NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+        e.add_gate(Gate {
+            staffed_shifts: vec![
+                StaffingShift {
+                    start: hm(6),
+                    end: hm(10),
+                },
+                StaffingShift {
+                    start: hm(10),
+                    end: hm(14),
+                },
+            ],
+            ..gate("A1", "T5", AircraftSize::Medium)
+        });
+        e.add_gate(Gate {
+            staffed_shifts: vec![StaffingShift {
+                start: hm(14),
+                end: hm(6),
+            }],
+            ..gate("A2", "T5", AircraftSize::Medium)
+        });
+
+        // Across the 10:00 shift change on A1 (turnaround runs to 12:15).
+        let morning = flight("F1", "A320", 9, 12);
+        assert_eq!(
+            e.check_gate_at(&morning, "A2", at(9, 0), at(12, 15)),
+            Err(GateRefusal::Unstaffed)
+        );
+        let placed = e.allocate_gate(&morning, "LHR", &["A2".into()]);
+        assert_eq!(placed.assignment.unwrap().gate.gate_id, "A1");
+
+        // Past 14:00 only the overnight crew on A2 is on duty.
+        let evening = flight("F2", "A320", 13, 15);
+        assert_eq!(
+            e.check_gate_at(&evening, "A1", at(13, 0), at(15, 15)),
+            Err(GateRefusal::Unstaffed)
+        );
+        assert!(e.allocate_gate(&evening, "LHR", &[]).assignment.is_none());
+        assert!(!e.has_capacity(AircraftSize::Medium, at(13, 0), at(15, 15), None));
+        let late = flight("F3", "A320", 16, 18);
+        let placed = e.allocate_gate(&late, "LHR", &["A1".into()]);
+        assert_eq!(placed.assignment.unwrap().gate.gate_id, "A2");
+    }
+
+    #[test]
+    fn delays_report_the_assignment_before_and_after() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 8, 10), "LHR", &[]);
+        e.allocate_gate(&flight("F2", "A320", 14, 16), "LHR", &[]);
+
+        let r = e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::Delay,
+            affected_flight_id: "F1".into(),
+            description: String::new(),
+            reported_at: at(7, 0),
+            delay_minutes: 30,
+            airport: None,
+        });
+        let window = |a: &GateAssignment| {
+            (
+                a.flight.flight_id.clone(),
+                a.assigned_from,
+                a.assigned_until,
+            )
+        };
+        assert_eq!(
+            r.previous.iter().map(window).collect::<Vec<_>>(),
+            [("F1".to_string(), at(8, 0), at(10, 15))]
+        );
+        assert_eq!(
+            r.reassignments.iter().map(window).collect::<Vec<_>>(),
+            [("F1".to_string(), at(8, 30), at(10, 45))]
+        );
+        assert_eq!(
+            r.previous[0].assignment_id,
+            r.reassignments[0].assignment_id
+        );
+
+        // Booked out of order, reported in schedule order.
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("A2", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F2", "A320", 11, 13), "LHR", &["A1".into()]);
+        e.allocate_gate(&flight("F1", "A320", 8, 10), "LHR", &["A1".into()]);
+        let r = e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::GateUnavailable,
+            affected_flight_id: String::new(),
+            description: "A1".into(),
+            reported_at: at(7, 0),
+            delay_minutes: 0,
+            airport: None,
+        });
+        let flights: Vec<_> = r
+            .previous
+            .iter()
+            .map(|a| a.flight.flight_id.as_str())
+            .collect();
+        assert_eq!(flights, ["F1", "F2"]);
+    }
+
+    #[test]
+    fn delays_overrunning_the_next_leg_follow_tail_policy() {
+        let run = |policy: TailDelayPolicy| {
+            let mut e = AllocationEngine::with_config(EngineConfig {
+                tail_delay_policy: policy,
+                ..EngineConfig::default()
+            });
+            for id in ["A1", "A2"] {
+                e.add_gate(gate(id, "T5", AircraftSize::Medium));
+            }
+            let leg = |id: &str, arr: u32, dep: u32| Flight {
+                registration: Some("G-EUPT".into()),
+                ..flight(id, "A320", arr, dep)
+            };
+            e.allocate_gate(&leg("F1", 8, 10), "LHR", &["A1".into()]);
+            e.allocate_gate(&leg("F2", 11, 13), "LHR", &["A2".into()]);
+            let r = e.handle_disruption(DisruptionEvent {
+                event_id: Uuid::new_v4(),
+                disruption_type: DisruptionType::Delay,
+                affected_flight_id: "F1".into(),
+                description: String::new(),
+                reported_at: at(7, 0),
+                delay_minutes: 90,
+                airport: None,
+            });
+            let f2 = e
+                .assignments
+                .iter()
+                .find(|a| a.flight.flight_id == "F2")
+                .map(|a| a.assigned_from)
+                .unwrap();
+            // A cascaded leg is not logged as a disruption of its own.
+            assert_eq!(e.disruptions.len(), 1);
+            (f2, r.notes)
+        };
+
+        assert_eq!(run(TailDelayPolicy::Ignore), (at(11, 0), Vec::new()));
+        assert_eq!(run(TailDelayPolicy::Cascade), (at(12, 30), Vec::new()));
+        // F1 now leaves at 11:30, half an hour after F2 is due.
+        assert_eq!(
+            run(TailDelayPolicy::Flag),
+            (
+                at(11, 0),
+                vec![DisruptionNote::TailRescheduleRequired {
+                    registration: "G-EUPT".into(),
+                    flight_id: "F1".into(),
+                    next_flight_id: "F2".into(),
+                    overrun_minutes: 30,
+                }]
+            )
+        );
+    }
+
+    #[test]
+    fn unknown_airports_follow_config() {
+        let mut f = flight("F1", "A320", 10, 12);
+        f.origin = "XXX".into();
+        f.destination = "LHR".into();
+        assert!(AllocationEngine::new().is_international(&f));
+        let lenient = AllocationEngine::with_config(EngineConfig {
+            unknown_airport_is_international: false,
+            ..EngineConfig::default()
+        });
+        assert!(!lenient.is_international(&f));
+    }
+
+    #[test]
+    fn domestic_flight_skips_international_only_gate() {
+        let mut e = AllocationEngine::new();
+        let mut intl = gate("A1", "T5", AircraftSize::Medium);
+        intl.gate_class = GateClass::International;
+        e.add_gate(intl);
+        let mut dom = gate("B1", "T5", AircraftSize::Medium);
+        dom.gate_class = GateClass::Domestic;
+        e.add_gate(dom);
+        let mut f = flight("F1", "A320", 10, 12);
+        f.origin = "LHR".into();
+        f.destination = "EDI".into();
+        let r = e.allocate_gate(&f, "LHR", &["A1".into()]);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "B1");
+    }
+
+    #[test]
+    fn refuses_allocation_above_score_threshold() {
+        let mut strict = AllocationEngine::with_config(EngineConfig {
+            max_acceptable_score: Some(5.0),
+            ..EngineConfig::default()
+        });
+        strict.add_gate(gate("A1", "T5", AircraftSize::Large));
+        let r = strict.allocate_gate(&flight("F1", "E190", 10, 12), "LHR", &[]);
+        assert!(!r.success);
+        assert!(strict.assignments.is_empty());
+        assert!(matches!(
+            r.error,
+            Some(AllocationError::ScoreTooHigh { ref gate_id, .. }) if gate_id == "A1"
+        ));
+
+        let mut lenient = AllocationEngine::new();
+        lenient.add_gate(gate("A1", "T5", AircraftSize::Large));
+        assert!(
+            lenient
+                .allocate_gate(&flight("F1", "E190", 10, 12), "LHR", &[])
+                .success
+        );
+    }
+
+    #[test]
+    fn advancing_clock_emits_gate_freed() {
+        use std::sync::{Arc, Mutex};
+
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("B1", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &["B1".into()]);
+        e.allocate_gate(&flight("F2", "A320", 10, 16), "LHR", &["A1".into()]);
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        e.on_gate_freed(Box::new(move |ev| sink.lock().unwrap().push(ev.clone())));
+
+        // F1 departs at 12:00 and holds the gate for the 15-minute turnaround buffer.
+        assert!(e
+            .advance_to(Utc.with_ymd_and_hms(2026, 3, 1, 12, 10, 0).unwrap())
+            .is_empty());
+        e.advance_to(Utc.with_ymd_and_hms(2026, 3, 1, 12, 30, 0).unwrap());
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].gate_id, "B1");
+        assert_eq!(seen[0].flight_id, "F1");
+        assert_eq!(e.assignments.len(), 1);
+    }
+
+    #[test]
+    fn has_capacity_tracks_allocate_gate() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("C1", "T2", AircraftSize::Small));
+        let f = flight("F1", "A320", 10, 12);
+        let (from, until) = f.occupancy_window(Duration::minutes(TURNAROUND_BUFFER_MINUTES));
+
+        assert!(e.has_capacity(AircraftSize::Medium, from, until, None));
+        assert!(!e.has_capacity(AircraftSize::Medium, from, until, Some("T2")));
+        assert!(e.allocate_gate(&f, "LHR", &[]).success);
+
+        // The only Medium-capable gate is now booked: both paths must agree it is exhausted.
+        let g = flight("F2", "A320", 11, 13);
+        let (from, until) = g.occupancy_window(Duration::minutes(TURNAROUND_BUFFER_MINUTES));
+        assert!(!e.has_capacity(AircraftSize::Medium, from, until, None));
+        assert!(!e.allocate_gate(&g, "LHR", &[]).success);
+        assert!(e.has_capacity(AircraftSize::Small, from, until, None));
+
+        // A terminal in curfew has no capacity, just as allocation refuses it.
+        e.set_terminal_curfew(
+            "T2",
+            Curfew {
+                start: chrono::NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                end: chrono::NaiveTime::from_hms_opt(14, 0, 0).unwrap(),
+            },
+        );
+        assert!(!e.has_capacity(AircraftSize::Small, from, until, None));
+        let h = flight("F3", "E190", 11, 13);
+        assert!(matches!(
+            e.allocate_gate(&h, "LHR", &[]).error,
+            Some(AllocationError::TerminalCurfew { .. })
+        ));
+    }
+
+    #[test]
+    fn preferred_reward_decays_with_repeated_use() {
+        let run = |decay: Option<f64>| {
+            let mut e = AllocationEngine::with_config(EngineConfig {
+                preferred_reward_decay: decay,
+                ..EngineConfig::default()
+            });
+            e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+            e.add_gate(gate("B1", "T5", AircraftSize::Medium));
+            let preferred = vec!["A1".to_string(), "B1".to_string()];
+            for (i, (arr, dep)) in [(6, 7), (8, 9), (10, 11)].into_iter().enumerate() {
+                let f = flight(&format!("BA{}", i), "A320", arr, dep);
+                e.allocate_gate(&f, "LHR", &["A1".to_string()]);
+            }
+            e.allocate_gate(&flight("BA9", "A320", 13, 14), "LHR", &preferred)
+                .assignment
+                .unwrap()
+                .gate
+                .gate_id
+        };
+        // Without decay the tie between equally preferred gates goes to the first registered.
+        assert_eq!(run(None), "A1");
+        assert_eq!(run(Some(0.5)), "B1");
+    }
+
+    #[test]
+    fn reservation_blocks_gate_until_released() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        let d = |h| Utc.with_ymd_and_hms(2026, 3, 1, h, 0, 0).unwrap();
+        let id = e
+            .reserve_gate("A1", d(9), d(13), "Possible diversion")
+            .unwrap();
+
+        assert!(
+            !e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &[])
+                .success
+        );
+        assert!(e.reserve_gate("A1", d(12), d(14), "Overlap").is_err());
+
+        assert!(e.release_reservation(id).is_some());
+        assert!(
+            e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &[])
+                .success
+        );
+    }
+
+    #[test]
+    fn negative_delay_shifts_window_earlier() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &[]);
+        let mut late = flight("F2", "A320", 12, 13);
+        late.scheduled_arrival = at(11, 45);
+        assert!(!e.allocate_gate(&late, "LHR", &[]).success);
+
+        let r = e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::Delay,
+            affected_flight_id: "F1".into(),
+            description: "Expedited turnaround".into(),
+            reported_at: Utc::now(),
+            delay_minutes: -30,
+            airport: None,
+        });
+        assert_eq!(
+            r.summary,
+            "F1 brought forward 30min - 1 assignment(s) adjusted"
+        );
+        let a = &r.reassignments[0];
+        assert_eq!((a.assigned_from, a.assigned_until), (at(9, 30), at(11, 45)));
+        assert_eq!(a.flight.status, FlightStatus::Scheduled);
+        assert!(e.allocate_gate(&late, "LHR", &[]).success);
+    }
+
+    #[test]
+    fn gate_loss_without_replacement_is_queued_for_manual_handling() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &[]);
+        let outage = DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::GateUnavailable,
+            affected_flight_id: String::new(),
+            description: "A1".into(),
+            reported_at: Utc::now(),
+            delay_minutes: 0,
+            airport: None,
+        };
+
+        e.handle_disruption(outage.clone());
+        assert!(e.assignments.is_empty());
+        assert_eq!(e.pending_manual.len(), 1);
+        assert_eq!(e.pending_manual[0].0.event_id, outage.event_id);
+        let pending: Vec<&str> = e
+            .pending_manual_flights()
+            .map(|f| f.flight_id.as_str())
+            .collect();
+        assert_eq!(pending, ["F1"]);
+
+        assert_eq!(e.clear_pending_manual().len(), 1);
+        assert!(e.pending_manual.is_empty());
+    }
+
+    #[test]
+    fn disruptions_apply_in_reported_order() {
+        let setup = || {
+            let mut e = AllocationEngine::with_config(EngineConfig {
+                strict_disruption_order: true,
+                ..EngineConfig::default()
+            });
+            e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+            e.add_gate(gate("A2", "T5", AircraftSize::Medium));
+            e.allocate_gate(&flight("F1", "A320", 8, 10), "LHR", &["A1".into()]);
+            e.allocate_gate(&flight("F2", "A320", 11, 13), "LHR", &["A2".into()]);
+            e
+        };
+        let event = |kind, flight: &str, description: &str, minute, delay| DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: kind,
+            affected_flight_id: flight.into(),
+            description: description.into(),
+            reported_at: at(7, minute),
+            delay_minutes: delay,
+            airport: None,
+        };
+        let outage = event(DisruptionType::GateUnavailable, "", "A1", 0, 0);
+        let delay = event(DisruptionType::Delay, "F1", "", 10, 120);
+        let cancel = event(DisruptionType::Cancellation, "F2", "", 20, 0);
+        let state = |e: &AllocationEngine| {
+            let mut s: Vec<_> = e
+                .assignments
+                .iter()
+                .map(|a| {
+                    (
+                        a.flight.flight_id.clone(),
+                        a.gate.gate_id.clone(),
+                        a.assigned_from,
+                    )
+                })
+                .collect();
+            s.sort();
+            s
+        };
+
+        let mut in_order = setup();
+        for ev in [outage.clone(), delay.clone(), cancel.clone()] {
+            assert!(in_order.handle_disruption(ev).acknowledged);
+        }
+        let mut replayed = setup();
+        let results = replayed.handle_disruptions(vec![cancel.clone(), delay, outage.clone()]);
+        assert!(results.iter().all(|r| r.acknowledged));
+        assert_eq!(state(&replayed), state(&in_order));
+        assert_eq!(state(&replayed), [("F1".into(), "A2".into(), at(8, 0))]);
+
+        // Applied one by one in arrival order, the stale events are refused.
+        let mut strict = setup();
+        assert!(strict.handle_disruption(cancel).acknowledged);
+        assert!(!strict.handle_disruption(outage).acknowledged);
+        assert_eq!(strict.disruptions.len(), 1);
+    }
+
+    #[test]
+    fn locked_assignment_is_never_auto_moved() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("B1", "T5", AircraftSize::Medium));
+        let locked = e
+            .allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &["A1".into()])
+            .assignment
+            .unwrap();
+        e.allocate_gate(&flight("F2", "A320", 10, 12), "LHR", &["B1".into()]);
+        e.confirm_assignment(locked.assignment_id).unwrap();
+        e.lock_assignment(locked.assignment_id).unwrap();
+        assert!(e.confirm_assignment(locked.assignment_id).is_err());
+
+        let outage = |gate_id: &str| DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::GateUnavailable,
+            affected_flight_id: String::new(),
+            description: gate_id.into(),
+            reported_at: Utc::now(),
+            delay_minutes: 0,
+            airport: None,
+        };
+        e.handle_disruption(outage("B1"));
+        let f1 = e
+            .assignments
+            .iter()
+            .find(|a| a.flight.flight_id == "F1")
+            .unwrap();
+        assert_eq!(f1.gate.gate_id, "A1");
+        assert_eq!(f1.assignment_id, locked.assignment_id);
+        assert_eq!(f1.status, AssignmentStatus::Locked);
+
+        // Even losing its own gate does not move a locked assignment.
+        let r = e.handle_disruption(outage("A1"));
+        assert!(r.reassignments.is_empty());
+        assert!(r.summary.contains("1 locked left in place"));
+        assert!(e
+            .assignments
+            .iter()
+            .any(|a| a.assignment_id == locked.assignment_id));
+    }
+
+    #[test]
+    fn terminal_curfew_rejects_late_night_window() {
+        use chrono::NaiveTime;
+
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.set_terminal_curfew(
+            "T5",
+            Curfew {
+                start: NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+            },
+        );
+
+        let mut late = flight("F1", "A320", 23, 23);
+        late.scheduled_arrival = at(23, 30);
+        late.scheduled_departure = at(23, 50);
+        let r = e.allocate_gate(&late, "LHR", &[]);
+        assert_eq!(
+            r.error,
+            Some(AllocationError::TerminalCurfew {
+                terminals: vec!["T5".into()]
+            })
+        );
+
+        let mut evening = flight("F2", "A320", 22, 22);
+        evening.scheduled_departure = at(22, 30);
+        assert!(e.allocate_gate(&evening, "LHR", &[]).success);
+    }
+
+    #[test]
+    fn stale_plan_is_revalidated_on_commit() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("B1", "T5", AircraftSize::Medium));
+
+        // Two planners work from the same copy and both pick A1.
+        let copy = e.planning_copy();
+        let p1 = copy
+            .plan_allocation(&flight("F1", "A320", 10, 12), "LHR", &[])
+            .unwrap();
+        let p2 = copy
+            .plan_allocation(&flight("F2", "A320", 10, 12), "LHR", &[])
+            .unwrap();
+        assert_eq!((p1.gate_id.as_str(), p2.gate_id.as_str()), ("A1", "A1"));
+
+        assert_eq!(e.commit_plan(p1).assignment.unwrap().gate.gate_id, "A1");
+        assert_eq!(e.commit_plan(p2).assignment.unwrap().gate.gate_id, "B1");
+
+        let p3 = copy
+            .plan_allocation(&flight("F3", "A320", 10, 12), "LHR", &[])
+            .unwrap();
+        let r = e.commit_plan(p3);
+        assert_eq!(r.error, Some(AllocationError::NoCompatibleGate));
+        assert_eq!(e.assignments.len(), 2);
+    }
+
+    #[test]
+    fn commit_refuses_plans_the_live_rules_forbid() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("B1", "T5", AircraftSize::Medium));
+        e.cap_airline("Test", 1);
+
+        // Each plan is fine on its own copy; together they break the cap.
+        let copy = e.planning_copy();
+        let p1 = copy
+            .plan_allocation(&flight("F1", "A320", 10, 12), "LHR", &[])
+            .unwrap();
+        let p2 = copy
+            .plan_allocation(&flight("F2", "A320", 10, 12), "LHR", &[])
+            .unwrap();
+        assert!(e.commit_plan(p1).success);
+        let r = e.commit_plan(p2);
+        assert!(matches!(
+            r.error,
+            Some(AllocationError::AirlineCapReached { .. })
+        ));
+
+        // The clock passed the window while the plan was being scored.
+        let p3 = copy
+            .plan_allocation(&flight("F3", "A320", 6, 7), "LHR", &[])
+            .unwrap();
+        e.advance_to(at(8, 0));
+        let r = e.commit_plan(p3);
+        assert!(matches!(
+            r.error,
+            Some(AllocationError::WindowInPast { .. })
+        ));
+        assert_eq!(e.assignments.len(), 1);
+    }
+
+    #[test]
+    fn update_gate_replaces_all_but_closures_and_checks_assignments() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.schedule_closure("A1", at(14, 0), at(16, 0)).unwrap();
+        e.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &[]);
+
+        let refused = e.update_gate(Gate {
+            excluded_types: vec!["A320".into()],
+            ..gate("A1", "T3", AircraftSize::Medium)
+        });
+        assert_eq!(
+            refused.unwrap_err(),
+            "Gate A1 would refuse 1 active assignment(s): F1 (aircraft type excluded)"
+        );
+
+        let mut remote = gate("A1", "T3", AircraftSize::Large);
+        remote.remote = true;
+        let stored = e.update_gate(remote).unwrap();
+        assert!(stored.remote);
+        assert_eq!(stored.unavailable_windows, [(at(14, 0), at(16, 0))]);
+        assert_eq!(e.assignments[0].gate, stored);
+    }
+
+    #[test]
+    fn codeshare_uses_operating_carrier_lease() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T3-A1", "T3", AircraftSize::Large));
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Large));
+        e.add_lease(GateLease {
+            airline: "British Airways".into(),
+            terminal: "T5".into(),
+            hours: None,
+        });
+        e.add_lease(GateLease {
+            airline: "American Airlines".into(),
+            terminal: "T3".into(),
+            hours: None,
+        });
+        let mut f = flight("BA-117", "B777", 10, 12);
+        f.airline = "British Airways".into();
+        f.codeshare_airlines = vec!["American Airlines".into()];
+        let r = e.allocate_gate(&f, "LHR", &[]);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "T5-A1");
+    }
+
+    #[test]
+    fn full_home_terminal_overflows_along_chain() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T2-A1", "T2", AircraftSize::Medium));
+        e.add_gate(gate("T3-A1", "T3", AircraftSize::Medium));
+        e.add_gate(gate("T1-A1", "T1", AircraftSize::Medium));
+        e.set_overflow_chain("T5", &["T3", "T1"]);
+        let preferred = ["T5-A1".to_string()];
+        assert!(e
+            .allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &preferred)
+            .overflow_terminal
+            .is_none());
+
+        let r = e.allocate_gate(&flight("F2", "A320", 10, 12), "LHR", &preferred);
+        assert_eq!(r.overflow_terminal.as_deref(), Some("T3"));
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "T3-A1");
+        assert!(r.message.contains("overflow terminal T3"));
+    }
+
+    #[test]
+    fn cancellation_is_scoped_to_its_airport() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("LHR-A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("LGW-N1", "North", AircraftSize::Medium));
+        e.allocate_gate(&flight("BA-42", "A320", 8, 9), "LHR", &["LHR-A1".into()]);
+        e.allocate_gate(&flight("BA-42", "A320", 15, 16), "LGW", &["LGW-N1".into()]);
+
+        let r = e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::Cancellation,
+            affected_flight_id: "BA-42".into(),
+            description: "Crew shortage".into(),
+            reported_at: Utc::now(),
+            delay_minutes: 0,
+            airport: Some("LHR".into()),
+        });
+        assert_eq!(r.summary, "BA-42 cancelled - 1 gate(s) freed");
+        assert_eq!(e.assignments.len(), 1);
+        assert_eq!(e.assignments[0].airport, "LGW");
+        assert_eq!(e.assignments[0].gate.gate_id, "LGW-N1");
+
+        // So is a diversion: BA-42 landing elsewhere instead of LHR keeps its LGW gate.
+        e.allocate_gate(&flight("BA-42", "A320", 8, 9), "LHR", &["LHR-A1".into()]);
+        e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::Diversion,
+            affected_flight_id: "BA-42".into(),
+            description: "Diverted to STN".into(),
+            reported_at: Utc::now(),
+            delay_minutes: 0,
+            airport: Some("LHR".into()),
+        });
+        assert_eq!(e.assignments.len(), 1);
+        assert_eq!(e.assignments[0].airport, "LGW");
+    }
+
+    #[test]
+    fn delay_reallocation_stays_in_original_terminal() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T3-A1", "T3", AircraftSize::Medium));
+        e.add_gate(gate("T5-B1", "T5", AircraftSize::Large));
+        e.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &["T5-A1".into()]);
+        e.allocate_gate(&flight("F2", "A320", 10, 12), "LHR", &["T5-A1".into()]);
+
+        let r = e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::Delay,
+            affected_flight_id: "F1".into(),
+            description: "Late inbound".into(),
+            reported_at: Utc::now(),
+            delay_minutes: 120,
+            airport: None,
+        });
+        // T3-A1 fits exactly, but the oversized T5-B1 keeps passengers in T5.
+        assert_eq!(r.reassignments[0].gate.gate_id, "T5-B1");
+        let f1: Vec<_> = e
+            .assignments
+            .iter()
+            .filter(|a| a.flight.flight_id == "F1")
+            .collect();
+        assert_eq!(f1.len(), 1);
+        assert_eq!(f1[0].gate.gate_id, "T5-B1");
+    }
+
+    #[test]
+    fn adjacent_gates_cannot_push_back_together() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("A2", "T5", AircraftSize::Medium));
+        e.add_pushback_conflict("A1", "A2", 10).unwrap();
+        assert!(e.add_pushback_conflict("A1", "Z9", 10).is_err());
+
+        e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &["A1".into()]);
+        // A2 is free, but departing alongside F1 would push back at the same moment.
+        let mut same_time = flight("F2", "A320", 8, 12);
+        same_time.scheduled_departure = at(12, 5);
+        assert!(!e.allocate_gate(&same_time, "LHR", &[]).success);
+        // Standing side by side is fine as long as the departures are spread out.
+        let staggered = e.allocate_gate(&flight("F3", "A320", 8, 13), "LHR", &[]);
+        assert_eq!(staggered.assignment.unwrap().gate.gate_id, "A2");
+    }
+
+    #[test]
+    fn reap_orphans_returns_assignments_of_missing_gates() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("A2", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &["A1".into()]);
+        e.allocate_gate(&flight("F2", "A320", 10, 11), "LHR", &["A1".into()]);
+        e.allocate_gate(&flight("F3", "A320", 8, 9), "LHR", &["A2".into()]);
+        assert!(e.reap_orphans().is_empty());
+
+        e.gates.retain(|g| g.gate_id != "A1");
+        let reaped: Vec<String> = e
+            .reap_orphans()
+            .into_iter()
+            .map(|a| a.flight.flight_id)
+            .collect();
+        assert_eq!(reaped, ["F1", "F2"]);
+        assert_eq!(e.assignments.len(), 1);
+        assert_eq!(e.assignments[0].gate.gate_id, "A2");
+    }
+
+    #[test]
+    fn stats_report_on_time_performance() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &[]);
+        e.allocate_gate(&flight("F2", "A320", 12, 13), "LHR", &[]);
+        e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::Delay,
+            affected_flight_id: "F1".into(),
+            description: "Late crew".into(),
+            reported_at: Utc::now(),
+            delay_minutes: 45,
+            airport: None,
+        });
 
-    fn gate(id: &str, term: &str, size: AircraftSize) -> Gate {
-        Gate {
-            gate_id: id.into(),
-            terminal: term.into(),
-            size,
-            is_available: true,
-        }
+        let f1 = e
+            .assignments
+            .iter()
+            .find(|a| a.flight.flight_id == "F1")
+            .unwrap();
+        assert_eq!(f1.scheduled.unwrap().0, at(8, 0));
+        assert_eq!(f1.assigned_from, at(8, 45));
+        let stats = e.stats();
+        assert_eq!(stats.delayed_assignments, 1);
+        assert_eq!(stats.average_delay_minutes, 45.0);
     }
-    fn flight(id: &str, ac: &str, arr_h: u32, dep_h: u32) -> Flight {
-        Flight {
-            flight_id: id.into(),
-            airline: "Test".into(),
-            origin: "LHR".into(),
-            destination: "JFK".into(),
-            aircraft_type: ac.into(),
-            scheduled_arrival: Utc.with_ymd_and_hms(2026, 3, 1, arr_h, 0, 0).unwrap(),
-            scheduled_departure: Utc.with_ymd_and_hms(2026, 3, 1, dep_h, 0, 0).unwrap(),
-            status: FlightStatus::Scheduled,
-        }
+
+    #[test]
+    fn delay_cascade_keeps_the_minimum_gap() {
+        let mut e = AllocationEngine::with_config(EngineConfig {
+            max_cascade_depth: 1,
+            min_gap_minutes: 10,
+            ..EngineConfig::default()
+        });
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 7, 8), "LHR", &[]);
+        e.allocate_gate(&flight("F2", "A320", 9, 10), "LHR", &[]);
+
+        // F1 now ends at 08:55: no overlap with F2, but inside the 10-minute gap.
+        let r = e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::Delay,
+            affected_flight_id: "F1".into(),
+            description: "Late inbound".into(),
+            reported_at: Utc::now(),
+            delay_minutes: 40,
+            airport: None,
+        });
+        assert!(r.notes.is_empty());
+        let windows: Vec<_> = e
+            .assignments
+            .iter()
+            .map(|a| (a.assigned_from, a.assigned_until))
+            .collect();
+        assert_eq!(windows, [(at(7, 40), at(8, 55)), (at(9, 5), at(10, 20))]);
     }
 
     #[test]
-    fn allocates_right_sized_gate() {
+    fn delay_cascade_stops_at_configured_depth() {
+        let run = |max_cascade_depth: usize| {
+            let mut e = AllocationEngine::with_config(EngineConfig {
+                max_cascade_depth,
+                ..EngineConfig::default()
+            });
+            e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+            for (id, arr) in [("F1", 8), ("F2", 9), ("F3", 10)] {
+                let mut f = flight(id, "A320", arr, arr + 1);
+                f.scheduled_arrival += Duration::minutes(if arr == 8 { 0 } else { 30 });
+                e.allocate_gate(&f, "LHR", &[]);
+            }
+            let r = e.handle_disruption(DisruptionEvent {
+                event_id: Uuid::new_v4(),
+                disruption_type: DisruptionType::Delay,
+                affected_flight_id: "F1".into(),
+                description: "Late inbound".into(),
+                reported_at: Utc::now(),
+                delay_minutes: 60,
+                airport: None,
+            });
+            let starts: Vec<_> = e.assignments.iter().map(|a| a.assigned_from).collect();
+            (r, starts)
+        };
+
+        // F1 pushes F2, which would have to push F3: one step too many.
+        let (r, starts) = run(1);
+        assert_eq!(
+            r.notes,
+            [DisruptionNote::CascadeLimitReached {
+                unresolved: vec!["F1".into(), "F2".into(), "F3".into()]
+            }]
+        );
+        assert!(r
+            .summary
+            .ends_with("cascade limit reached, unresolved: F1, F2, F3"));
+        assert_eq!(starts, [at(8, 0), at(9, 30), at(10, 30)]);
+
+        let (r, starts) = run(2);
+        assert!(r.notes.is_empty());
+        assert_eq!(starts, [at(9, 0), at(10, 15), at(11, 0)]);
+    }
+
+    #[test]
+    fn a380_is_confined_to_its_designated_gates() {
         let mut e = AllocationEngine::new();
-        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
-        e.add_gate(gate("A2", "T5", AircraftSize::Large));
+        e.add_gate(gate("T5-L1", "T5", AircraftSize::Large));
+        e.add_gate(gate("T5-L2", "T5", AircraftSize::Large));
+        let mut designated = gate("T5-L9", "T5", AircraftSize::Large);
+        designated.permitted_types = vec!["A380".into()];
+        e.add_gate(designated);
+        let mut no_b747 = gate("T5-L3", "T5", AircraftSize::Large);
+        no_b747.excluded_types = vec!["B747".into()];
+        e.add_gate(no_b747);
+
+        let r = e.allocate_gate(&flight("F1", "a380", 8, 10), "LHR", &["T5-L1".into()]);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "T5-L9");
+        // The designated gate is taken, and the other Large gates do not count.
+        let r = e.allocate_gate(&flight("F2", "A380", 9, 11), "LHR", &[]);
+        assert!(r.assignment.is_none());
+        // Other wide-bodies may not use the A380 gate, and exclusions are honoured.
+        let r = e.allocate_gate(&flight("F3", "B747", 12, 13), "LHR", &["T5-L3".into()]);
+        let gate_id = r.assignment.unwrap().gate.gate_id;
+        assert!(gate_id == "T5-L1" || gate_id == "T5-L2");
+    }
+
+    #[test]
+    fn late_inbound_delays_linked_outbound() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-A2", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("IN1", "A320", 8, 9), "LHR", &["T5-A1".into()]);
+        e.allocate_gate(&flight("OUT1", "A320", 10, 11), "LHR", &["T5-A2".into()]);
+        e.link_turnaround("IN1", "OUT1").unwrap();
+        assert!(e.link_turnaround("OUT1", "IN1").is_err());
+
+        let r = e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::Delay,
+            affected_flight_id: "IN1".into(),
+            description: "Late inbound".into(),
+            reported_at: Utc::now(),
+            delay_minutes: 45,
+            airport: None,
+        });
+        assert!(r.summary.contains("OUT1 delayed 45min"), "{}", r.summary);
+        let out = e
+            .assignments
+            .iter()
+            .find(|a| a.flight.flight_id == "OUT1")
+            .unwrap();
+        assert_eq!(out.assigned_from, at(10, 45));
+        assert_eq!(out.assigned_until, at(11, 15) + Duration::minutes(45));
+        assert_eq!(out.flight.status, FlightStatus::Delayed);
+        // The knock-on is part of the reported delay, not a disruption of its own.
+        assert_eq!(e.disruptions.len(), 1);
+    }
+
+    #[test]
+    fn score_breakdown_sums_to_total() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-L1", "T5", AircraftSize::Large));
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        let f = flight("F1", "E190", 8, 9);
+        let preferred = ["T5-A1".to_string()];
+
+        let b = e.explain_score("T5-L1", &f, &preferred).unwrap();
+        assert_eq!(b.oversize, 2.0 * PENALTY_OVERSIZED_GATE);
+        assert_eq!(b.preference, PENALTY_PREFERRED_MISS);
+        assert_eq!(b.total(), b.oversize + b.preference);
+        assert_eq!(b.total(), e.score_gate(&e.gates[0], &f, &preferred));
+        assert!(e.explain_score("Z9", &f, &preferred).is_none());
+    }
+
+    #[test]
+    fn mars_gate_takes_two_narrow_bodies_or_one_wide_body() {
+        let mut e = AllocationEngine::new();
+        let mut mars = gate("T5-M1", "T5", AircraftSize::Large);
+        mars.mars = true;
+        e.add_gate(mars);
+        let preferred = ["T5-M1".to_string()];
+
+        let a = e.allocate_gate(&flight("F1", "A320", 8, 10), "LHR", &preferred);
+        let b = e.allocate_gate(&flight("F2", "A320", 9, 11), "LHR", &preferred);
+        assert_eq!(a.assignment.unwrap().sub_stand, Some(0));
+        assert_eq!(b.assignment.unwrap().sub_stand, Some(1));
+        // Both sub-stands are held.
+        let c = e.allocate_gate(&flight("F3", "A320", 9, 10), "LHR", &preferred);
+        assert!(c.assignment.is_none());
+
+        // A wide-body needs the whole stand, and then blocks narrow-bodies too.
+        let w = e.allocate_gate(&flight("W1", "B777", 10, 12), "LHR", &preferred);
+        assert!(w.assignment.is_none());
+        let w = e.allocate_gate(&flight("W2", "B777", 13, 15), "LHR", &preferred);
+        assert_eq!(w.assignment.unwrap().sub_stand, None);
+        let n = e.allocate_gate(&flight("F4", "A320", 14, 15), "LHR", &preferred);
+        assert!(n.assignment.is_none());
+    }
+
+    #[test]
+    fn directional_scoring_matches_gate_flow_to_flight_role() {
+        let mut e = AllocationEngine::with_config(EngineConfig {
+            directional_penalty: Some(8.0),
+            ..EngineConfig::default()
+        });
+        let mut near_immigration = gate("T5-A1", "T5", AircraftSize::Medium);
+        near_immigration.favours = Some(FlightRole::Arrival);
+        let mut near_security = gate("T5-A2", "T5", AircraftSize::Medium);
+        near_security.favours = Some(FlightRole::Departure);
+        e.add_gate(near_immigration);
+        e.add_gate(near_security);
+
+        let long_stay = flight("F1", "A320", 8, 11);
+        let quick_turn = flight("F2", "A320", 8, 9);
+        assert_eq!(long_stay.dominant_role(), FlightRole::Arrival);
+        assert_eq!(quick_turn.dominant_role(), FlightRole::Departure);
+        let b = e.explain_score("T5-A2", &long_stay, &[]).unwrap();
+        assert_eq!(b.direction, 8.0);
+
+        let r = e.allocate_gate(&quick_turn, "LHR", &[]);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "T5-A2");
+        let r = e.allocate_gate(&long_stay, "LHR", &[]);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "T5-A1");
+    }
+
+    #[test]
+    fn try_allocate_at_commits_exact_gate_and_window() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-A2", "T5", AircraftSize::Medium));
+        let r = e.try_allocate_at(
+            &flight("F1", "A320", 8, 9),
+            "LGW",
+            "T5-A2",
+            at(7, 50),
+            at(9, 30),
+        );
+        assert!(r.success, "{}", r.message);
+        let a = r.assignment.unwrap();
+        assert_eq!(a.gate.gate_id, "T5-A2");
+        assert_eq!((a.assigned_from, a.assigned_until), (at(7, 50), at(9, 30)));
+        assert_eq!(a.airport, "LGW");
+    }
+
+    #[test]
+    fn try_allocate_at_refuses_too_small_gate() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        let r = e.try_allocate_at(
+            &flight("F1", "B777", 8, 9),
+            "LHR",
+            "T5-A1",
+            at(8, 0),
+            at(9, 15),
+        );
+        assert!(!r.success);
         assert_eq!(
-            e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &[])
+            r.error,
+            Some(AllocationError::GateRefused {
+                gate_id: "T5-A1".into(),
+                reason: GateRefusal::TooSmall,
+            })
+        );
+        assert!(e.assignments.is_empty());
+    }
+
+    #[test]
+    fn try_allocate_at_refuses_occupied_window() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &[]);
+        let r = e.try_allocate_at(
+            &flight("F2", "A320", 9, 10),
+            "LHR",
+            "T5-A1",
+            at(9, 0),
+            at(10, 15),
+        );
+        assert!(matches!(
+            r.error,
+            Some(AllocationError::GateRefused {
+                reason: GateRefusal::Occupied,
+                ..
+            })
+        ));
+        assert!(
+            r.message.contains("gate occupied during window"),
+            "{}",
+            r.message
+        );
+        // Once the earlier flight's buffer has passed the gate is free.
+        let r = e.try_allocate_at(
+            &flight("F2", "A320", 9, 10),
+            "LHR",
+            "T5-A1",
+            at(9, 15),
+            at(10, 15),
+        );
+        assert!(r.success);
+    }
+
+    #[test]
+    fn double_stand_needs_an_adjacent_free_pair() {
+        let mut e = AllocationEngine::new();
+        for id in ["T5-L1", "T5-L2", "T5-L3", "T5-L4"] {
+            e.add_gate(gate(id, "T5", AircraftSize::Large));
+        }
+        e.add_adjacent_gates("T5-L1", "T5-L2").unwrap();
+        e.add_adjacent_gates("T5-L3", "T5-L4").unwrap();
+        let mut oversized = flight("F1", "A380", 8, 10);
+        oversized.requires_double_stand = true;
+
+        let r = e.allocate_gate(&oversized, "LHR", &[]);
+        assert!(r.success, "{}", r.message);
+        let held: Vec<&str> = e
+            .gate_timeline("T5-L1")
+            .iter()
+            .chain(e.gate_timeline("T5-L2").iter())
+            .map(|a| a.flight.flight_id.as_str())
+            .collect();
+        assert_eq!(held, ["F1", "F1"]);
+
+        // Break up the remaining pair: L3 is taken, L4 and nothing next to it are free.
+        e.allocate_gate(&flight("F2", "B777", 8, 10), "LHR", &["T5-L3".into()]);
+        let mut second = flight("F3", "A380", 9, 11);
+        second.requires_double_stand = true;
+        let r = e.allocate_gate(&second, "LHR", &[]);
+        assert!(!r.success);
+        assert_eq!(e.assignments.len(), 3);
+    }
+
+    #[test]
+    fn double_stand_moves_as_a_pair_on_delay_and_gate_loss() {
+        // F1 holds L1 + L2 from 08:00; F2 follows on L2 from 11:00.
+        let setup = |max_cascade_depth: usize, spare_pair: bool| {
+            let mut e = AllocationEngine::with_config(EngineConfig {
+                max_cascade_depth,
+                ..EngineConfig::default()
+            });
+            for id in ["T5-L1", "T5-L2"] {
+                e.add_gate(gate(id, "T5", AircraftSize::Large));
+            }
+            e.add_adjacent_gates("T5-L1", "T5-L2").unwrap();
+            if spare_pair {
+                for id in ["T5-L3", "T5-L4"] {
+                    e.add_gate(gate(id, "T5", AircraftSize::Large));
+                }
+                e.add_adjacent_gates("T5-L3", "T5-L4").unwrap();
+            }
+            let mut oversized = flight("F1", "A380", 8, 10);
+            oversized.requires_double_stand = true;
+            assert!(
+                e.allocate_gate(&oversized, "LHR", &["T5-L1".into()])
+                    .success
+            );
+            e.allocate_gate(&flight("F2", "B777", 11, 12), "LHR", &["T5-L2".into()]);
+            e
+        };
+        let event = |kind: DisruptionType, description: &str, delay_minutes: i32| DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: kind,
+            affected_flight_id: "F1".into(),
+            description: description.into(),
+            reported_at: Utc::now(),
+            delay_minutes,
+            airport: None,
+        };
+        let held = |e: &AllocationEngine| {
+            let mut held: Vec<(String, String, DateTime<Utc>)> = e
+                .assignments
+                .iter()
+                .map(|a| {
+                    (
+                        a.flight.flight_id.clone(),
+                        a.gate.gate_id.clone(),
+                        a.assigned_from,
+                    )
+                })
+                .collect();
+            held.sort();
+            held
+        };
+        let entry = |f: &str, g: &str, from| (f.to_string(), g.to_string(), from);
+
+        // Delayed into F2 on L2: the whole pair moves to L3 + L4.
+        let mut e = setup(0, true);
+        let r = e.handle_disruption(event(DisruptionType::Delay, "Late inbound", 60));
+        assert!(r.notes.is_empty(), "{}", r.summary);
+        assert_eq!(r.reassignments.len(), 2);
+        assert_eq!(
+            held(&e),
+            [
+                entry("F1", "T5-L3", at(9, 0)),
+                entry("F1", "T5-L4", at(9, 0)),
+                entry("F2", "T5-L2", at(11, 0)),
+            ]
+        );
+
+        // No other pair: the pair keeps its gates and F2 is pushed back.
+        let mut e = setup(1, false);
+        let r = e.handle_disruption(event(DisruptionType::Delay, "Late inbound", 60));
+        assert!(r.notes.is_empty(), "{}", r.summary);
+        assert_eq!(
+            held(&e),
+            [
+                entry("F1", "T5-L1", at(9, 0)),
+                entry("F1", "T5-L2", at(9, 0)),
+                entry("F2", "T5-L2", at(11, 15)),
+            ]
+        );
+
+        // Nor a cascade: nothing moves, and each flight is queued once.
+        let mut e = setup(0, false);
+        let before = held(&e);
+        let r = e.handle_disruption(event(DisruptionType::Delay, "Late inbound", 60));
+        assert_eq!(
+            r.notes,
+            [DisruptionNote::CascadeLimitReached {
+                unresolved: vec!["F1".into(), "F2".into()]
+            }]
+        );
+        assert_eq!(held(&e), before);
+        let queued: Vec<&str> = e
+            .pending_manual_flights()
+            .map(|f| f.flight_id.as_str())
+            .collect();
+        assert_eq!(queued, ["F1", "F2"]);
+
+        // Losing the partner gate gives up L1 too.
+        let mut e = setup(0, true);
+        let r = e.handle_disruption(event(DisruptionType::GateUnavailable, "T5-L2", 0));
+        assert_eq!(r.reassignments.len(), 3);
+        assert_eq!(
+            held(&e),
+            [
+                entry("F1", "T5-L3", at(8, 0)),
+                entry("F1", "T5-L4", at(8, 0)),
+                entry("F2", "T5-L1", at(11, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn terminal_change_costs_more_than_same_terminal_move() {
+        let run = |alternate_terminal: &str| {
+            let mut e = AllocationEngine::new();
+            e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+            e.add_gate(gate("X-A2", alternate_terminal, AircraftSize::Medium));
+            e.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &["T5-A1".into()]);
+            e.handle_disruption(DisruptionEvent {
+                event_id: Uuid::new_v4(),
+                disruption_type: DisruptionType::GateUnavailable,
+                affected_flight_id: "F1".into(),
+                description: "T5-A1".into(),
+                reported_at: Utc::now(),
+                delay_minutes: 0,
+                airport: None,
+            })
+        };
+        let same = run("T5");
+        let moved = run("T3");
+        assert_eq!(same.reassignments.len(), 1);
+        assert_eq!(moved.reassignments.len(), 1);
+        assert_eq!(same.estimated_reallocation_cost, 0.0);
+        assert_eq!(moved.estimated_reallocation_cost, PENALTY_TERMINAL_CHANGE);
+    }
+
+    #[test]
+    fn flights_cluster_in_their_baggage_zone() {
+        let mut e = AllocationEngine::new();
+        for (id, zone) in [
+            ("T5-A1", "North"),
+            ("T5-A2", "North"),
+            ("T5-B1", "South"),
+            ("T5-B2", "South"),
+        ] {
+            let mut g = gate(id, "T5", AircraftSize::Medium);
+            g.baggage_zone = Some(zone.into());
+            e.add_gate(g);
+        }
+        for id in ["F1", "F2"] {
+            let mut f = flight(id, "A320", 8, 9);
+            f.baggage_zone = Some("South".into());
+            let gate_id = e
+                .allocate_gate(&f, "LHR", &[])
                 .assignment
                 .unwrap()
                 .gate
-                .gate_id,
-            "A1"
+                .gate_id;
+            assert!(gate_id.starts_with("T5-B"), "{} went to {}", id, gate_id);
+        }
+        // Untagged flights score every zone alike.
+        let r = e.allocate_gate(&flight("F3", "A320", 8, 9), "LHR", &[]);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "T5-A1");
+    }
+
+    #[test]
+    fn sequential_ids_make_runs_reproducible() {
+        let run = || {
+            let mut e = AllocationEngine::new();
+            e.set_id_generator(sequential_ids());
+            e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+            e.add_gate(gate("T5-A2", "T5", AircraftSize::Medium));
+            e.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &[]);
+            e.allocate_gate(&flight("F2", "A320", 8, 9), "LHR", &[]);
+            e.assignments
+                .iter()
+                .map(|a| a.assignment_id)
+                .collect::<Vec<Uuid>>()
+        };
+        let first = run();
+        assert_eq!(first, run());
+        assert_eq!(first, [Uuid::from_u128(1), Uuid::from_u128(2)]);
+    }
+
+    #[test]
+    fn wide_body_departure_holds_gate_through_cooldown() {
+        let mut e = AllocationEngine::with_config(EngineConfig {
+            post_departure_cooldown: BTreeMap::from([(AircraftSize::Large, 20)]),
+            ..EngineConfig::default()
+        });
+        e.add_gate(gate("T5-L1", "T5", AircraftSize::Large));
+        let wide = e.allocate_gate(&flight("F1", "B777", 8, 10), "LHR", &[]);
+        let narrow = e.allocate_gate(&flight("F2", "E190", 12, 13), "LHR", &[]);
+        let hold = |r: AllocationResult| {
+            let a = r.assignment.unwrap();
+            a.assigned_until - a.flight.scheduled_departure
+        };
+        assert_eq!(hold(wide), Duration::minutes(35));
+        assert_eq!(hold(narrow), Duration::minutes(15));
+
+        // 10:15 would clear the buffer alone, but the cool-down runs to 10:35.
+        let mut next = flight("F3", "A320", 10, 11);
+        next.scheduled_arrival = at(10, 20);
+        assert!(!e.allocate_gate(&next, "LHR", &[]).success);
+        next.scheduled_arrival = at(10, 35);
+        assert!(e.allocate_gate(&next, "LHR", &[]).success);
+    }
+
+    #[test]
+    fn empty_engine_reports_no_gates_registered() {
+        let mut e = AllocationEngine::new();
+        let r = e.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &[]);
+        assert_eq!(
+            r.error,
+            Some(AllocationError::NoGatesRegistered {
+                airport: "LHR".into()
+            })
         );
+        assert!(r.message.contains("No gates registered for LHR"));
+
+        // With gates present, a misfit is still the ordinary capacity failure.
+        e.add_gate(gate("T2-C1", "T2", AircraftSize::Small));
+        let r = e.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &[]);
+        assert_eq!(r.error, Some(AllocationError::NoCompatibleGate));
     }
 
     #[test]
-    fn rejects_undersized_gate() {
+    fn long_turnaround_skips_time_limited_remote_stand() {
         let mut e = AllocationEngine::new();
-        e.add_gate(gate("A1", "T5", AircraftSize::Small));
+        let mut remote = gate("R1", "T5", AircraftSize::Medium);
+        remote.max_occupancy_minutes = Some(120);
+        e.add_gate(remote);
+        let preferred = ["R1".to_string()];
+
+        // 08:00 - 11:15 is over the two-hour limit; no other gate exists yet.
+        let long = flight("F1", "A320", 8, 11);
+        assert!(!e.allocate_gate(&long, "LHR", &preferred).success);
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        let r = e.allocate_gate(&long, "LHR", &preferred);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "T5-A1");
+
+        let short = flight("F2", "A320", 12, 13);
+        let r = e.allocate_gate(&short, "LHR", &preferred);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "R1");
+    }
+
+    #[test]
+    fn minimum_billing_block_refuses_short_window() {
+        let mut e = AllocationEngine::new();
+        let mut contact = gate("T5-A1", "T5", AircraftSize::Medium);
+        contact.min_occupancy_minutes = Some(90);
+        e.add_gate(contact);
+        let r = e.try_allocate_at(
+            &flight("F1", "A320", 8, 9),
+            "LHR",
+            "T5-A1",
+            at(8, 0),
+            at(9, 15),
+        );
+        assert!(matches!(
+            r.error,
+            Some(AllocationError::GateRefused {
+                reason: GateRefusal::OccupancyLimit,
+                ..
+            })
+        ));
         assert!(
-            !e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &[])
+            e.allocate_gate(&flight("F1", "A320", 8, 10), "LHR", &[])
                 .success
         );
     }
 
     #[test]
-    fn detects_time_conflicts() {
+    fn cancelled_flight_is_refused_a_gate() {
         let mut e = AllocationEngine::new();
-        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
-        assert!(
-            e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &[])
-                .success
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        let mut cancelled = flight("F1", "A320", 8, 9);
+        cancelled.status = FlightStatus::Cancelled;
+        let r = e.allocate_gate(&cancelled, "LHR", &[]);
+        assert_eq!(
+            r.error,
+            Some(AllocationError::InactiveFlight {
+                status: FlightStatus::Cancelled
+            })
+        );
+        assert!(r.message.contains("F1 is Cancelled"));
+        cancelled.status = FlightStatus::Diverted;
+        let r = e.try_allocate_at(&cancelled, "LHR", "T5-A1", at(8, 0), at(9, 15));
+        assert!(matches!(
+            r.error,
+            Some(AllocationError::InactiveFlight { .. })
+        ));
+        assert!(e.assignments.is_empty());
+
+        let r = e.allocate_gate(&flight("F2", "A320", 8, 9), "LHR", &[]);
+        assert!(r.success);
+    }
+
+    #[test]
+    fn past_window_is_refused_unless_replaying() {
+        let past = flight("F1", "A320", 8, 9);
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.advance_to(at(12, 0));
+        let r = e.allocate_gate(&past, "LHR", &[]);
+        assert_eq!(
+            r.error,
+            Some(AllocationError::WindowInPast {
+                until: at(9, 15),
+                now: at(12, 0)
+            })
         );
+        // A window still running is fine.
         assert!(
-            !e.allocate_gate(&flight("F2", "A320", 11, 13), "LHR", &[])
+            e.allocate_gate(&flight("F2", "A320", 11, 12), "LHR", &[])
                 .success
         );
+
+        let mut replay = AllocationEngine::with_config(EngineConfig {
+            reject_past_windows: false,
+            ..EngineConfig::default()
+        });
+        replay.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        replay.advance_to(at(12, 0));
+        assert!(replay.allocate_gate(&past, "LHR", &[]).success);
     }
 
     #[test]
-    fn cancellation_frees_gate() {
+    fn disruption_stats_group_by_type_and_hour() {
         let mut e = AllocationEngine::new();
-        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
-        e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &[]);
-        e.handle_disruption(DisruptionEvent {
+        let event = |t: DisruptionType, h: u32| DisruptionEvent {
             event_id: Uuid::new_v4(),
-            disruption_type: DisruptionType::Cancellation,
+            disruption_type: t,
             affected_flight_id: "F1".into(),
-            description: "Cancelled".into(),
-            reported_at: Utc::now(),
+            description: String::new(),
+            reported_at: at(h, 10),
             delay_minutes: 0,
+            airport: None,
+        };
+        e.disruptions = vec![
+            event(DisruptionType::Delay, 7),
+            event(DisruptionType::Delay, 7),
+            event(DisruptionType::Weather, 7),
+            event(DisruptionType::Delay, 9),
+            event(DisruptionType::Cancellation, 18),
+        ];
+
+        let stats = e.disruption_stats();
+        assert_eq!(stats.by_type[&DisruptionType::Delay], 3);
+        assert_eq!(stats.by_type[&DisruptionType::Weather], 1);
+        assert_eq!(stats.by_type[&DisruptionType::Cancellation], 1);
+        assert!(!stats.by_type.contains_key(&DisruptionType::Diversion));
+        assert_eq!(stats.by_hour, BTreeMap::from([(7, 3), (9, 1), (18, 1)]));
+    }
+
+    #[test]
+    fn right_door_aircraft_skips_left_only_gate() {
+        let mut e = AllocationEngine::new();
+        let mut left_only = gate("T5-A1", "T5", AircraftSize::Small);
+        left_only.jetbridge_config = JetbridgeConfig::LeftOnly;
+        e.add_gate(left_only);
+        e.add_gate(gate("T5-A2", "T5", AircraftSize::Small));
+
+        let atr = flight("F1", "ATR72", 8, 9);
+        assert_eq!(door_config(&atr.aircraft_type), DoorSide::Right);
+        let r = e.allocate_gate(&atr, "LHR", &["T5-A1".into()]);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "T5-A2");
+
+        // Left-door types still use the left-only bridge.
+        let r = e.allocate_gate(&flight("F2", "E190", 8, 9), "LHR", &["T5-A1".into()]);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "T5-A1");
+    }
+
+    #[test]
+    fn expired_assignments_are_archived_and_queryable() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-A2", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 7, 8), "LHR", &[]);
+        e.allocate_gate(&flight("F2", "A320", 9, 10), "LHR", &[]);
+        e.allocate_gate(&flight("F3", "A320", 13, 14), "LHR", &[]);
+
+        e.advance_to(at(12, 0));
+        assert_eq!(e.history.len(), 2);
+        let f1 = e.history_for("F1");
+        assert_eq!(f1.len(), 1);
+        assert_eq!(f1[0].assigned_from, at(7, 0));
+        assert!(e.history_for("F3").is_empty());
+
+        let morning = e.history_in(at(9, 0), at(11, 0));
+        assert_eq!(morning.len(), 1);
+        assert_eq!(morning[0].flight.flight_id, "F2");
+
+        e.config.history_limit = 2;
+        e.advance_to(at(15, 0));
+        let ids: Vec<_> = e
+            .history
+            .iter()
+            .map(|a| a.flight.flight_id.as_str())
+            .collect();
+        assert_eq!(ids, ["F2", "F3"]);
+    }
+
+    #[test]
+    fn infeasible_preferred_gate_is_reported_or_refused() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-S1", "T5", AircraftSize::Small));
+        e.add_gate(gate("T5-L1", "T5", AircraftSize::Large));
+        let wide = flight("F1", "B777", 8, 10);
+
+        let r = e.allocate_gate(&wide, "LHR", &["T5-S1".into()]);
+        assert!(r.success);
+        assert_eq!(
+            r.infeasible_preferred,
+            vec![("T5-S1".to_string(), GateRefusal::TooSmall)]
+        );
+        assert!(r
+            .message
+            .contains("preferred T5-S1 infeasible (gate too small for aircraft)"));
+
+        let mut strict = AllocationEngine::with_config(EngineConfig {
+            strict_preferences: true,
+            ..EngineConfig::default()
         });
+        strict.add_gate(gate("T5-S1", "T5", AircraftSize::Small));
+        strict.add_gate(gate("T5-L1", "T5", AircraftSize::Large));
+        let r = strict.allocate_gate(&wide, "LHR", &["T5-S1".into()]);
+        assert_eq!(
+            r.error,
+            Some(AllocationError::GateRefused {
+                gate_id: "T5-S1".into(),
+                reason: GateRefusal::TooSmall
+            })
+        );
+        assert!(strict.assignments.is_empty());
+    }
+
+    #[test]
+    fn delay_ignores_the_flights_own_assignments() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-A2", "T5", AircraftSize::Medium));
+        // Two back-to-back legs of the same flight on one gate.
+        let f = flight("F1", "A320", 8, 9);
         assert!(
-            e.allocate_gate(&flight("F2", "A320", 11, 13), "LHR", &[])
+            e.try_allocate_at(&f, "LHR", "T5-A1", at(8, 0), at(9, 0))
+                .success
+        );
+        assert!(
+            e.try_allocate_at(&f, "LHR", "T5-A1", at(9, 0), at(10, 0))
                 .success
         );
+
+        let r = e.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::Delay,
+            affected_flight_id: "F1".into(),
+            description: "Late crew".into(),
+            reported_at: Utc::now(),
+            delay_minutes: 30,
+            airport: None,
+        });
+
+        assert_eq!(r.reassignments.len(), 2);
+        let mut windows: Vec<_> = e
+            .assignments
+            .iter()
+            .map(|a| (a.gate.gate_id.as_str(), a.assigned_from))
+            .collect();
+        windows.sort();
+        assert_eq!(windows, [("T5-A1", at(8, 30)), ("T5-A1", at(9, 30))]);
+        assert!(!overlaps((at(8, 0), at(9, 0)), (at(9, 0), at(10, 0))));
+        assert!(overlaps((at(8, 0), at(9, 1)), (at(9, 0), at(10, 0))));
     }
 
     #[test]
-    fn prefers_requested_gate() {
+    fn delay_does_not_shift_into_a_reservation_or_closure() {
         let mut e = AllocationEngine::new();
-        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
-        e.add_gate(gate("B1", "T5", AircraftSize::Medium));
-        let r = e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &["B1".into()]);
-        assert_eq!(r.assignment.unwrap().gate.gate_id, "B1");
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-A2", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-A3", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &["T5-A1".into()]);
+        e.allocate_gate(&flight("F2", "A320", 12, 13), "LHR", &["T5-A2".into()]);
+        e.reserve_gate("T5-A1", at(10, 0), at(11, 0), "VIP")
+            .unwrap();
+        e.schedule_closure("T5-A2", at(14, 0), at(15, 0)).unwrap();
+
+        for id in ["F1", "F2"] {
+            e.handle_disruption(DisruptionEvent {
+                event_id: Uuid::new_v4(),
+                disruption_type: DisruptionType::Delay,
+                affected_flight_id: id.into(),
+                description: "Late inbound".into(),
+                reported_at: Utc::now(),
+                delay_minutes: 90,
+                airport: None,
+            });
+        }
+        // Neither flight stays put: the held and the closed gate both force a move.
+        let gates: Vec<_> = e
+            .assignments
+            .iter()
+            .map(|a| (a.flight.flight_id.as_str(), a.gate.gate_id.as_str()))
+            .collect();
+        assert_eq!(gates.len(), 2);
+        assert!(!gates.contains(&("F1", "T5-A1")));
+        assert!(!gates.contains(&("F2", "T5-A2")));
+    }
+
+    #[test]
+    fn earlier_preference_beats_later_one() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-B2", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-B1", "T5", AircraftSize::Medium));
+        let preferred = ["T5-B1".to_string(), "T5-B2".to_string()];
+
+        let r = e.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &preferred);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "T5-B1");
+        let f = flight("F2", "A320", 10, 11);
+        let first = e.explain_score("T5-B1", &f, &preferred).unwrap();
+        let second = e.explain_score("T5-B2", &f, &preferred).unwrap();
+        assert_eq!(first.preference, REWARD_PREFERRED_GATE);
+        assert_eq!(
+            second.preference,
+            REWARD_PREFERRED_GATE + PREFERRED_RANK_STEP
+        );
+    }
+
+    #[test]
+    fn lease_binds_only_within_its_hours() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T3-A1", "T3", AircraftSize::Medium));
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        let t = |h| chrono::NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+        e.add_lease(GateLease {
+            airline: "British Airways".into(),
+            terminal: "T5".into(),
+            hours: Some((t(6), t(14))),
+        });
+        let ba = |id: &str, arr, dep| Flight {
+            airline: "British Airways".into(),
+            ..flight(id, "A320", arr, dep)
+        };
+
+        // 07:00 is inside the lease: only T5 will do, even with a T3 preference.
+        let r = e.allocate_gate(&ba("BA-1", 7, 8), "LHR", &["T3-A1".into()]);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "T5-A1");
+        // 20:00 is outside it: the preferred T3 gate is allowed.
+        let r = e.allocate_gate(&ba("BA-2", 20, 21), "LHR", &["T3-A1".into()]);
+        assert_eq!(r.assignment.unwrap().gate.gate_id, "T3-A1");
+    }
+
+    #[test]
+    fn min_gap_rejects_back_to_back_touches() {
+        let run = |gap| {
+            let mut e = AllocationEngine::with_config(EngineConfig {
+                min_gap_minutes: gap,
+                ..EngineConfig::default()
+            });
+            e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+            // 08:00-09:15 including the turnaround buffer, then 09:15 onwards.
+            e.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &[]);
+            let mut next = flight("F2", "A320", 9, 10);
+            next.scheduled_arrival = at(9, 15);
+            e.allocate_gate(&next, "LHR", &[]).success
+        };
+        assert!(run(0));
+        assert!(!run(10));
+    }
+
+    #[test]
+    fn night_restricted_type_is_refused_in_restricted_hours() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Large));
+        e.add_gate(gate("T5-A2", "T5", AircraftSize::Large));
+        e.restrict_type_at_night(
+            "b747",
+            Curfew {
+                start: chrono::NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+                end: chrono::NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+            },
+        );
+
+        let r = e.allocate_gate(&flight("F1", "B747", 1, 3), "LHR", &[]);
+        assert_eq!(
+            r.error,
+            Some(AllocationError::NightRestricted {
+                aircraft_type: "B747".into()
+            })
+        );
+        assert!(
+            e.allocate_gate(&flight("F2", "A320", 1, 3), "LHR", &[])
+                .success
+        );
+        assert!(
+            e.allocate_gate(&flight("F3", "B747", 8, 10), "LHR", &[])
+                .success
+        );
     }
 }
@@ -0,0 +1,471 @@
+//! Whole-schedule repacking operations that move existing assignments between gates.
+
+use tracing::info;
+
+use super::AllocationEngine;
+use crate::domain::*;
+
+impl AllocationEngine {
+    /// Repack the assignments in `terminal` onto as few gates as possible without introducing
+    /// conflicts, freeing the remaining gates (e.g. for overnight maintenance or power savings).
+    ///
+    /// Assignments are placed in arrival order onto the first compatible gate, trying gates that
+    /// already carry the most assignments first so that as few flights as possible move.  Returns
+    /// the assignments whose gate or sub-stand changed, in their new state.  If no conflict-free
+    /// packing is found the schedule is left untouched and nothing is returned.  Every placement
+    /// meets the same constraints as a fresh allocation.  Locked and double-stand assignments
+    /// never move; they are packed around.
+    pub fn consolidate(&mut self, terminal: &str) -> Vec<GateAssignment> {
+        self.tracked(|e| e.repack(terminal))
+    }
+
+    /// Move up to `max_moves` assignments from `from_terminal` onto free gates in `to_terminal`
+    /// to relieve a congested terminal.  Windows are kept; every move must satisfy the same
+    /// constraints as a fresh allocation.  The least disruptive moves go first: provisional
+    /// before confirmed, and the latest-starting flights (whose passengers are furthest from the
+    /// gate) before earlier ones.  Locked and double-stand assignments stay put.  Returns the
+    /// moved assignments in their new state.
+    pub fn rebalance(
+        &mut self,
+        from_terminal: &str,
+        to_terminal: &str,
+        max_moves: usize,
+    ) -> Result<Vec<GateAssignment>, String> {
+        if from_terminal == to_terminal {
+            return Err(format!("Cannot rebalance {} onto itself", from_terminal));
+        }
+        for terminal in [from_terminal, to_terminal] {
+            if !self.gates.iter().any(|g| g.terminal == terminal) {
+                return Err(format!("Unknown terminal {}", terminal));
+            }
+        }
+        Ok(self.tracked(|e| e.shift_terminal(from_terminal, to_terminal, max_moves)))
+    }
+
+    /// Exchange the gates of two assigned flights, keeping their windows.  Both new placements
+    /// must pass the same checks as a fresh allocation (size, restrictions, closures, conflicts
+    /// with everything else on the gate); otherwise neither flight moves.  Locked and
+    /// double-stand assignments cannot be swapped.
+    pub fn swap(&mut self, flight_a: &str, flight_b: &str) -> Result<(), String> {
+        self.tracked(|e| e.swap_gates(flight_a, flight_b))
+    }
+
+    fn swap_gates(&mut self, flight_a: &str, flight_b: &str) -> Result<(), String> {
+        let index_of = |e: &Self, flight_id: &str| -> Result<usize, String> {
+            let idx = e
+                .assignments
+                .iter()
+                .position(|a| a.flight.flight_id == flight_id)
+                .ok_or_else(|| format!("No assignment for flight {}", flight_id))?;
+            let a = &e.assignments[idx];
+            if a.is_locked() {
+                return Err(format!("Assignment for {} is locked", flight_id));
+            }
+            if a.flight.requires_double_stand {
+                return Err(format!("{} holds a double stand", flight_id));
+            }
+            Ok(idx)
+        };
+        let (ia, ib) = (index_of(self, flight_a)?, index_of(self, flight_b)?);
+        if self.assignments[ia].gate.gate_id == self.assignments[ib].gate.gate_id {
+            return Err(format!("{} and {} share a gate", flight_a, flight_b));
+        }
+
+        // Take both out so each is checked only against the rest of its new gate.
+        let (lo, hi) = (ia.min(ib), ia.max(ib));
+        let mut second = self.assignments.remove(hi);
+        let mut first = self.assignments.remove(lo);
+        let placed = self
+            .swap_target(&first, &second.gate.gate_id)
+            .and_then(|to_second| Ok((to_second, self.swap_target(&second, &first.gate.gate_id)?)));
+        if let Ok(((gate, stand), (other_gate, other_stand))) = &placed {
+            info!(%flight_a, %flight_b, "Gates swapped");
+            (first.gate, first.sub_stand) = (gate.clone(), *stand);
+            (second.gate, second.sub_stand) = (other_gate.clone(), *other_stand);
+        }
+        self.assignments.insert(lo, first);
+        self.assignments.insert(hi, second);
+        placed.map(|_| ())
+    }
+
+    /// The gate `a` would move to, and its sub-stand, if it may take over `gate_id`.
+    fn swap_target(&self, a: &GateAssignment, gate_id: &str) -> Result<(Gate, Option<u8>), String> {
+        let gate = self
+            .gates
+            .iter()
+            .find(|g| g.gate_id == gate_id)
+            .ok_or_else(|| format!("Unknown gate {}", gate_id))?;
+        let size = a.flight.aircraft_size();
+        let fits = self.is_usable(gate, size, a.assigned_from, a.assigned_until)
+            && self.fits_flight(gate, &a.flight);
+        if !fits {
+            return Err(format!(
+                "{} cannot take gate {}",
+                a.flight.flight_id, gate.gate_id
+            ));
+        }
+        let stand = self
+            .free_stand(gate, size, a.assigned_from, a.assigned_until)
+            .flatten();
+        Ok((gate.clone(), stand))
+    }
+
+    /// Put `gate_id` back in service and move onto it any assignments it now suits better than
+    /// the gate they hold, e.g. flights bumped to oversized or remote stands during its outage.
+    /// Best improvements go first.  Locked, double-stand and already-started assignments stay
+    /// put, as do flights in other terminals unless
+    /// `EngineConfig::reinstatement_crosses_terminals` is set.  Returns the moved assignments
+    /// in their new state.
+    pub fn reinstate_gate(&mut self, gate_id: &str) -> Result<Vec<GateAssignment>, String> {
+        let gate = self
+            .gates
+            .iter_mut()
+            .find(|g| g.gate_id == gate_id)
+            .ok_or_else(|| format!("Unknown gate {}", gate_id))?;
+        gate.is_available = true;
+        info!(gate_id, "Gate reinstated");
+        Ok(self.tracked(|e| e.migrate_to(gate_id)))
+    }
+
+    fn migrate_to(&mut self, gate_id: &str) -> Vec<GateAssignment> {
+        let Some(gate) = self.gates.iter().find(|g| g.gate_id == gate_id).cloned() else {
+            return Vec::new();
+        };
+        let mut gains: Vec<(usize, f64)> = self
+            .assignments
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| {
+                a.gate.gate_id != gate_id
+                    && !a.is_locked()
+                    && !a.flight.requires_double_stand
+                    && self.clock.is_none_or(|now| a.assigned_from > now)
+                    && (self.config.reinstatement_crosses_terminals
+                        || a.gate.terminal == gate.terminal)
+            })
+            .map(|(i, a)| {
+                let current = self.score_gate(&a.gate, &a.flight, &[]);
+                (i, current - self.score_gate(&gate, &a.flight, &[]))
+            })
+            .filter(|&(_, gain)| gain > 0.0)
+            .collect();
+        gains.sort_by(|x, y| y.1.total_cmp(&x.1));
+
+        let mut moved = Vec::new();
+        for (idx, _) in gains {
+            let a = &self.assignments[idx];
+            let size = a.flight.aircraft_size();
+            if !(self.is_usable(&gate, size, a.assigned_from, a.assigned_until)
+                && self.fits_flight(&gate, &a.flight))
+            {
+                continue;
+            }
+            let sub_stand = self
+                .free_stand(&gate, size, a.assigned_from, a.assigned_until)
+                .flatten();
+            let a = &mut self.assignments[idx];
+            info!(flight = %a.flight.flight_id, from = %a.gate.gate_id, to = %gate.gate_id, "Moved to reinstated gate");
+            a.gate = gate.clone();
+            a.sub_stand = sub_stand;
+            moved.push(a.clone());
+        }
+        moved
+    }
+
+    fn shift_terminal(&mut self, from: &str, to: &str, max_moves: usize) -> Vec<GateAssignment> {
+        let mut candidates: Vec<usize> = (0..self.assignments.len())
+            .filter(|&i| {
+                let a = &self.assignments[i];
+                a.gate.terminal == from && !a.is_locked() && !a.flight.requires_double_stand
+            })
+            .collect();
+        candidates.sort_by_key(|&i| {
+            let a = &self.assignments[i];
+            (
+                a.status == AssignmentStatus::Confirmed,
+                std::cmp::Reverse(a.assigned_from),
+            )
+        });
+
+        let mut moved = Vec::new();
+        for idx in candidates {
+            if moved.len() == max_moves {
+                break;
+            }
+            let a = &self.assignments[idx];
+            let size = a.flight.aircraft_size();
+            let target = self
+                .gates
+                .iter()
+                .filter(|g| {
+                    g.terminal == to
+                        && self.is_usable(g, size, a.assigned_from, a.assigned_until)
+                        && self.fits_flight(g, &a.flight)
+                })
+                .map(|g| (g, self.score_gate(g, &a.flight, &[])))
+                .min_by(|x, y| x.1.total_cmp(&y.1))
+                .map(|(g, _)| g);
+            let Some(gate) = target else {
+                continue;
+            };
+            let sub_stand = self
+                .free_stand(gate, size, a.assigned_from, a.assigned_until)
+                .flatten();
+            let gate = gate.clone();
+            let a = &mut self.assignments[idx];
+            info!(flight = %a.flight.flight_id, from = %a.gate.gate_id, to = %gate.gate_id, "Rebalanced");
+            a.gate = gate;
+            a.sub_stand = sub_stand;
+            moved.push(a.clone());
+        }
+        moved
+    }
+
+    fn repack(&mut self, terminal: &str) -> Vec<GateAssignment> {
+        // Locked and double-stand assignments never move; everything else is re-placed.
+        let (pinned, mut order): (Vec<usize>, Vec<usize>) = (0..self.assignments.len())
+            .filter(|&i| self.assignments[i].gate.terminal == terminal)
+            .partition(|&i| {
+                let a = &self.assignments[i];
+                a.is_locked() || a.flight.requires_double_stand
+            });
+        order.sort_by_key(|&i| self.assignments[i].assigned_from);
+
+        let mut gates: Vec<&Gate> = self
+            .gates
+            .iter()
+            .filter(|g| g.terminal == terminal)
+            .collect();
+        // Gates pinned by locked assignments can never be vacated, so fill them first; then prefer
+        // busier gates.  The stable sort keeps registration order among equals.
+        let uses = |gate_id: &str, idxs: &[usize]| {
+            idxs.iter()
+                .filter(|&&i| self.assignments[i].gate.gate_id == gate_id)
+                .count()
+        };
+        gates.sort_by_key(|g| {
+            std::cmp::Reverse((uses(&g.gate_id, &pinned), uses(&g.gate_id, &order)))
+        });
+
+        // The plan is built on a copy without the movable assignments, so each placement is
+        // checked with the same rules as a fresh allocation against everything placed so far.
+        let movable: Vec<_> = order
+            .iter()
+            .map(|&i| self.assignments[i].assignment_id)
+            .collect();
+        let mut scratch = self.planning_copy();
+        scratch
+            .assignments
+            .retain(|a| !movable.contains(&a.assignment_id));
+        let mut plan: Vec<(usize, Gate, Option<u8>)> = Vec::with_capacity(order.len());
+        for &idx in &order {
+            let a = &self.assignments[idx];
+            let size = a.flight.aircraft_size();
+            let target = gates.iter().find(|g| {
+                scratch.is_usable(g, size, a.assigned_from, a.assigned_until)
+                    && scratch.fits_flight(g, &a.flight)
+            });
+            let Some(&gate) = target else {
+                return Vec::new();
+            };
+            let sub_stand = scratch
+                .free_stand(gate, size, a.assigned_from, a.assigned_until)
+                .flatten();
+            scratch.assignments.push(GateAssignment {
+                gate: gate.clone(),
+                sub_stand,
+                ..a.clone()
+            });
+            plan.push((idx, gate.clone(), sub_stand));
+        }
+
+        let mut moved = Vec::with_capacity(plan.len());
+        for (idx, gate, sub_stand) in plan {
+            let a = &mut self.assignments[idx];
+            if a.gate.gate_id == gate.gate_id && a.sub_stand == sub_stand {
+                continue;
+            }
+            info!(flight = %a.flight.flight_id, from = %a.gate.gate_id, to = %gate.gate_id, "Consolidated");
+            a.gate = gate;
+            a.sub_stand = sub_stand;
+            moved.push(a.clone());
+        }
+        moved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::{flight, gate};
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn consolidates_sparse_schedule_onto_two_gates() {
+        let mut e = AllocationEngine::new();
+        for id in ["A1", "A2", "A3", "A4"] {
+            e.add_gate(gate(id, "T5", AircraftSize::Medium));
+        }
+        e.add_gate(gate("X1", "T2", AircraftSize::Medium));
+        // Spread four flights over four gates: two morning, two afternoon.
+        for (id, arr, dep, pref) in [
+            ("F1", 6, 7, "A1"),
+            ("F2", 6, 7, "A2"),
+            ("F3", 12, 13, "A3"),
+            ("F4", 12, 13, "A4"),
+        ] {
+            e.allocate_gate(&flight(id, "A320", arr, dep), "LHR", &[pref.into()]);
+        }
+
+        let moved = e.consolidate("T5");
+        assert_eq!(moved.len(), 2);
+        let used: HashSet<&str> = e
+            .assignments
+            .iter()
+            .map(|a| a.gate.gate_id.as_str())
+            .collect();
+        assert_eq!(used.len(), 2);
+        for a in &e.assignments {
+            assert!(!e
+                .assignments
+                .iter()
+                .any(|b| b.assignment_id != a.assignment_id
+                    && b.gate.gate_id == a.gate.gate_id
+                    && b.overlaps(a.assigned_from, a.assigned_until)));
+        }
+    }
+
+    #[test]
+    fn consolidate_leaves_locked_assignments_in_place() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("A2", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 6, 7), "LHR", &["A1".into()]);
+        let pinned = e
+            .allocate_gate(&flight("F2", "A320", 12, 13), "LHR", &["A2".into()])
+            .assignment
+            .unwrap();
+        e.lock_assignment(pinned.assignment_id).unwrap();
+
+        let moved = e.consolidate("T5");
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].flight.flight_id, "F1");
+        assert!(e.assignments.iter().all(|a| a.gate.gate_id == "A2"));
+    }
+
+    #[test]
+    fn consolidate_only_packs_onto_staffed_gates() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(Gate {
+            staffed_shifts: vec![StaffingShift {
+                start: chrono::NaiveTime::from_hms_opt(10, 0, 0).unwrap(),
+                end: chrono::NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+            }],
+            ..gate("A2", "T5", AircraftSize::Medium)
+        });
+        e.allocate_gate(&flight("F1", "A320", 6, 7), "LHR", &["A1".into()]);
+        e.allocate_gate(&flight("F2", "A320", 11, 12), "LHR", &["A2".into()]);
+        e.allocate_gate(&flight("F3", "A320", 13, 14), "LHR", &["A2".into()]);
+
+        // A2 is the busier gate, but nobody crews it for F1's early turnaround.
+        assert!(e.consolidate("T5").is_empty());
+        assert_eq!(e.assignments[0].gate.gate_id, "A1");
+    }
+
+    #[test]
+    fn rebalance_moves_at_most_max_moves_without_conflicts() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("A2", "T5", AircraftSize::Medium));
+        e.add_gate(gate("X1", "T2", AircraftSize::Medium));
+        e.add_gate(gate("X2", "T2", AircraftSize::Medium));
+        for (id, arr, dep, pref) in [
+            ("F1", 6, 7, "A1"),
+            ("F2", 6, 7, "A2"),
+            ("F3", 9, 10, "A1"),
+            ("F4", 12, 13, "A2"),
+        ] {
+            e.allocate_gate(&flight(id, "A320", arr, dep), "LHR", &[pref.into()]);
+        }
+        let locked = e.assignments[3].assignment_id;
+        e.lock_assignment(locked).unwrap();
+
+        let moved = e.rebalance("T5", "T2", 2).unwrap();
+        // Latest-starting unlocked flights go first; F4 is locked.
+        let ids: Vec<&str> = moved.iter().map(|a| a.flight.flight_id.as_str()).collect();
+        assert_eq!(ids, ["F3", "F1"]);
+        assert!(moved.iter().all(|a| a.gate.terminal == "T2"));
+        assert_eq!(
+            e.assignments
+                .iter()
+                .filter(|a| a.gate.terminal == "T5")
+                .count(),
+            2
+        );
+        for a in &e.assignments {
+            assert!(!e
+                .assignments
+                .iter()
+                .any(|b| b.assignment_id != a.assignment_id
+                    && b.gate.gate_id == a.gate.gate_id
+                    && b.overlaps(a.assigned_from, a.assigned_until)));
+        }
+        assert!(e.rebalance("T5", "T9", 1).is_err());
+    }
+
+    #[test]
+    fn swap_exchanges_gates_or_leaves_both_in_place() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-A2", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-S1", "T5", AircraftSize::Small));
+        e.allocate_gate(&flight("F1", "A320", 8, 10), "LHR", &["T5-A1".into()]);
+        e.allocate_gate(&flight("F2", "A320", 9, 11), "LHR", &["T5-A2".into()]);
+        e.allocate_gate(&flight("F3", "E190", 8, 10), "LHR", &["T5-S1".into()]);
+        let gate_of = |e: &AllocationEngine, id: &str| {
+            e.get_assignments(None)
+                .into_iter()
+                .find(|a| a.flight.flight_id == id)
+                .map(|a| a.gate.gate_id.clone())
+                .unwrap()
+        };
+
+        e.swap("F1", "F2").unwrap();
+        assert_eq!(gate_of(&e, "F1"), "T5-A2");
+        assert_eq!(gate_of(&e, "F2"), "T5-A1");
+
+        // An A320 does not fit the small stand, so the E190 stays put too.
+        assert!(e.swap("F2", "F3").is_err());
+        assert_eq!(gate_of(&e, "F2"), "T5-A1");
+        assert_eq!(gate_of(&e, "F3"), "T5-S1");
+        assert!(e.swap("F1", "NOPE").is_err());
+    }
+
+    #[test]
+    fn bumped_flight_returns_to_reinstated_gate() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("T5-L1", "T5", AircraftSize::Large));
+        e.add_gate(gate("T3-A1", "T3", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 10, 12), "LHR", &[]);
+        e.handle_disruption(DisruptionEvent {
+            event_id: uuid::Uuid::new_v4(),
+            disruption_type: DisruptionType::GateUnavailable,
+            affected_flight_id: String::new(),
+            description: "T5-A1".into(),
+            reported_at: chrono::Utc::now(),
+            delay_minutes: 0,
+            airport: None,
+        });
+        assert_eq!(e.assignments[0].gate.gate_id, "T5-L1");
+
+        let moved = e.reinstate_gate("T5-A1").unwrap();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(e.assignments[0].gate.gate_id, "T5-A1");
+        assert!(e.gates[0].is_available);
+        // Flights in other terminals stay put by default.
+        assert!(e.reinstate_gate("T3-A1").unwrap().is_empty());
+        assert!(e.reinstate_gate("NOPE").is_err());
+    }
+}
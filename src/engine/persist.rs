@@ -0,0 +1,59 @@
+//! Saving and restoring the assignment set, e.g. across a restart.
+
+use super::AllocationEngine;
+use crate::domain::*;
+
+impl AllocationEngine {
+    /// The current assignments, notes included, as JSON.
+    pub fn save_assignments(&self) -> Result<String, String> {
+        serde_json::to_string(&self.assignments)
+            .map_err(|e| format!("Cannot serialise assignments: {}", e))
+    }
+
+    /// Replace the current assignments with ones saved by `save_assignments`.  Every
+    /// assignment's gate must be registered; nothing is loaded otherwise.  Returns how many
+    /// assignments were loaded.
+    pub fn load_assignments(&mut self, json: &str) -> Result<usize, String> {
+        let loaded: Vec<GateAssignment> =
+            serde_json::from_str(json).map_err(|e| format!("Invalid assignments JSON: {}", e))?;
+        if let Some(a) = loaded
+            .iter()
+            .find(|a| !self.gates.iter().any(|g| g.gate_id == a.gate.gate_id))
+        {
+            return Err(format!(
+                "Assignment for {} names unknown gate {}",
+                a.flight.flight_id, a.gate.gate_id
+            ));
+        }
+        let count = loaded.len();
+        self.tracked(|e| e.assignments = loaded);
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::{flight, gate};
+    use super::*;
+
+    #[test]
+    fn notes_survive_save_and_load() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        let res = e.allocate_gate(&flight("F1", "A320", 8, 10), "LHR", &[]);
+        let id = res.assignment.unwrap().assignment_id;
+        e.annotate_assignment(id, "VIP handling").unwrap();
+        e.annotate_assignment(id, "towing at 09:30").unwrap();
+        assert!(e.annotate_assignment(id, "  ").is_err());
+
+        let saved = e.save_assignments().unwrap();
+        let mut restored = AllocationEngine::new();
+        assert!(restored.load_assignments(&saved).is_err());
+        restored.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        assert_eq!(restored.load_assignments(&saved), Ok(1));
+        assert_eq!(
+            restored.assignments[0].notes,
+            ["VIP handling", "towing at 09:30"]
+        );
+    }
+}
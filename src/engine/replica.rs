@@ -0,0 +1,287 @@
+//! Live mirroring of an engine onto a hot standby: the primary emits state deltas, the replica
+//! applies them.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{AllocationEngine, Charge};
+use crate::domain::*;
+
+/// Callback registered via `AllocationEngine::on_state_delta`.
+pub type StateDeltaListener = Box<dyn Fn(StateDelta) + Send>;
+
+/// One change to the mirrored state (gates, assignments, reservations, the disruption log, the
+/// clock, the history archive and completed charges).  `index` is the position in the primary's
+/// list after the change, so a replica applying every delta in order keeps the same ordering.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StateDelta {
+    /// A gate was registered or changed.
+    GatePut {
+        index: usize,
+        gate: Gate,
+    },
+    GateRemoved {
+        gate_id: String,
+    },
+    /// An assignment was made or changed (window, gate, status, notes...).
+    AssignmentPut {
+        index: usize,
+        assignment: Box<GateAssignment>,
+    },
+    AssignmentRemoved {
+        assignment_id: Uuid,
+    },
+    ReservationPut {
+        index: usize,
+        reservation: GateReservation,
+    },
+    ReservationRemoved {
+        reservation_id: Uuid,
+    },
+    DisruptionRecorded(DisruptionEvent),
+    /// The operational clock moved to this time.
+    ClockAdvanced(DateTime<Utc>),
+    /// A completed assignment entered the history archive.  The replica trims the archive to its
+    /// own `EngineConfig::history_limit`.
+    Archived(Box<GateAssignment>),
+    ChargeRecorded(Charge),
+}
+
+/// The mirrored state as it was before a tracked operation.
+pub(super) struct Mirrored {
+    gates: Vec<Gate>,
+    assignments: Vec<GateAssignment>,
+    reservations: Vec<GateReservation>,
+    disruptions: usize,
+    clock: Option<DateTime<Utc>>,
+    /// The newest archived assignment; the archive is trimmed from the front, so its length
+    /// alone cannot tell what was added.
+    last_archived: Option<Uuid>,
+    charges: usize,
+}
+
+impl Mirrored {
+    pub(super) fn of(engine: &AllocationEngine) -> Self {
+        Self {
+            gates: engine.gates.clone(),
+            assignments: engine.assignments.clone(),
+            reservations: engine.reservations.clone(),
+            disruptions: engine.disruptions.len(),
+            clock: engine.clock,
+            last_archived: engine.history.back().map(|a| a.assignment_id),
+            charges: engine.completed_charges.len(),
+        }
+    }
+
+    /// What changed between this state and `engine`'s current one: removals first, then puts
+    /// in list order, then newly recorded disruptions, the clock, archived assignments and
+    /// charges.
+    pub(super) fn deltas_to(&self, engine: &AllocationEngine) -> Vec<StateDelta> {
+        let mut deltas = Vec::new();
+        let (removed, put) = changes(&self.gates, &engine.gates, |g| g.gate_id.clone());
+        deltas.extend(
+            removed
+                .into_iter()
+                .map(|gate_id| StateDelta::GateRemoved { gate_id }),
+        );
+        deltas.extend(
+            put.into_iter()
+                .map(|(index, gate)| StateDelta::GatePut { index, gate }),
+        );
+        let (removed, put) = changes(&self.assignments, &engine.assignments, |a| a.assignment_id);
+        deltas.extend(
+            removed
+                .into_iter()
+                .map(|assignment_id| StateDelta::AssignmentRemoved { assignment_id }),
+        );
+        deltas.extend(
+            put.into_iter()
+                .map(|(index, assignment)| StateDelta::AssignmentPut {
+                    index,
+                    assignment: Box::new(assignment),
+                }),
+        );
+        let (removed, put) = changes(&self.reservations, &engine.reservations, |r| {
+            r.reservation_id
+        });
+        deltas.extend(
+            removed
+                .into_iter()
+                .map(|reservation_id| StateDelta::ReservationRemoved { reservation_id }),
+        );
+        deltas.extend(
+            put.into_iter()
+                .map(|(index, reservation)| StateDelta::ReservationPut { index, reservation }),
+        );
+        deltas.extend(
+            engine
+                .disruptions
+                .iter()
+                .skip(self.disruptions)
+                .cloned()
+                .map(StateDelta::DisruptionRecorded),
+        );
+        if engine.clock != self.clock {
+            deltas.extend(engine.clock.map(StateDelta::ClockAdvanced));
+        }
+        let archived = self
+            .last_archived
+            .and_then(|id| engine.history.iter().position(|a| a.assignment_id == id))
+            .map_or(0, |at| at + 1);
+        deltas.extend(
+            engine
+                .history
+                .iter()
+                .skip(archived)
+                .map(|a| StateDelta::Archived(Box::new(a.clone()))),
+        );
+        deltas.extend(
+            engine
+                .completed_charges
+                .iter()
+                .skip(self.charges)
+                .cloned()
+                .map(StateDelta::ChargeRecorded),
+        );
+        deltas
+    }
+}
+
+/// Keys of items gone from `after`, and the new or changed items of `after` with their index.
+fn changes<T: Clone + PartialEq, K: PartialEq>(
+    before: &[T],
+    after: &[T],
+    key: impl Fn(&T) -> K,
+) -> (Vec<K>, Vec<(usize, T)>) {
+    let removed = before
+        .iter()
+        .filter(|b| !after.iter().any(|a| key(a) == key(b)))
+        .map(&key)
+        .collect();
+    let put = after
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| !before.contains(a))
+        .map(|(i, a)| (i, a.clone()))
+        .collect();
+    (removed, put)
+}
+
+/// Place `item` at `index`, replacing (and moving) any item with the same key.
+fn put<T, K: PartialEq>(list: &mut Vec<T>, index: usize, item: T, key: impl Fn(&T) -> K) {
+    if let Some(at) = list.iter().position(|x| key(x) == key(&item)) {
+        list.remove(at);
+    }
+    list.insert(index.min(list.len()), item);
+}
+
+impl AllocationEngine {
+    /// Register a callback invoked with every change to gates, assignments, reservations, the
+    /// disruption log, the clock, the history archive and completed charges, for mirroring onto
+    /// a standby with `apply_delta`.  Configuration (leases, curfews, caps, links, airports,
+    /// history limit) is not mirrored; set the standby up the same way.
+    pub fn on_state_delta(&mut self, listener: StateDeltaListener) {
+        self.delta_listeners.push(listener);
+    }
+
+    /// Apply a delta emitted by a primary's `on_state_delta` listener.  Deltas must be applied
+    /// in the order they were emitted.  A replica's own listeners see the delta too, so standbys
+    /// can be chained.
+    pub fn apply_delta(&mut self, delta: StateDelta) {
+        self.tracked(|e| match delta {
+            StateDelta::GatePut { index, gate } => {
+                put(&mut e.gates, index, gate, |g| g.gate_id.clone())
+            }
+            StateDelta::GateRemoved { gate_id } => e.gates.retain(|g| g.gate_id != gate_id),
+            StateDelta::AssignmentPut { index, assignment } => {
+                put(&mut e.assignments, index, *assignment, |a| a.assignment_id)
+            }
+            StateDelta::AssignmentRemoved { assignment_id } => {
+                e.assignments.retain(|a| a.assignment_id != assignment_id)
+            }
+            StateDelta::ReservationPut { index, reservation } => {
+                put(&mut e.reservations, index, reservation, |r| {
+                    r.reservation_id
+                })
+            }
+            StateDelta::ReservationRemoved { reservation_id } => e
+                .reservations
+                .retain(|r| r.reservation_id != reservation_id),
+            StateDelta::DisruptionRecorded(event) => e.disruptions.push(event),
+            StateDelta::ClockAdvanced(now) => e.clock = Some(now),
+            StateDelta::Archived(assignment) => {
+                e.history.push_back(*assignment);
+                let overflow = e.history.len().saturating_sub(e.config.history_limit);
+                e.history.drain(..overflow);
+            }
+            StateDelta::ChargeRecorded(charge) => e.completed_charges.push(charge),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::super::test_support::{at, flight, gate};
+    use super::*;
+
+    #[test]
+    fn replica_applying_deltas_matches_primary() {
+        let mut primary = AllocationEngine::new();
+        let deltas = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&deltas);
+        primary.on_state_delta(Box::new(move |d| sink.lock().unwrap().push(d)));
+
+        for id in ["A1", "A2", "A3"] {
+            primary.add_gate(gate(id, "T5", AircraftSize::Medium));
+        }
+        let f1 = primary
+            .allocate_gate(&flight("F1", "A320", 8, 10), "LHR", &["A1".into()])
+            .assignment
+            .unwrap();
+        primary.allocate_gate(&flight("F2", "A320", 9, 11), "LHR", &["A2".into()]);
+        primary.allocate_gate(&flight("F3", "A320", 12, 14), "LHR", &["A1".into()]);
+        primary.confirm_assignment(f1.assignment_id).unwrap();
+        primary
+            .annotate_assignment(f1.assignment_id, "VIP handling")
+            .unwrap();
+        let held = primary
+            .reserve_gate("A3", at(15, 0), at(16, 0), "Towing")
+            .unwrap();
+        primary
+            .schedule_closure("A2", at(18, 0), at(20, 0))
+            .unwrap();
+        primary.handle_disruption(DisruptionEvent {
+            event_id: Uuid::new_v4(),
+            disruption_type: DisruptionType::GateUnavailable,
+            affected_flight_id: String::new(),
+            description: "A1".into(),
+            reported_at: at(7, 0),
+            delay_minutes: 0,
+            airport: None,
+        });
+        primary.swap("F1", "F2").unwrap();
+        primary.release_reservation(held).unwrap();
+        primary.advance_to(at(13, 0));
+
+        let mut replica = AllocationEngine::new();
+        let shipped = deltas.lock().unwrap().clone();
+        for delta in shipped {
+            // Deltas survive the wire.
+            let json = serde_json::to_string(&delta).unwrap();
+            replica.apply_delta(serde_json::from_str(&json).unwrap());
+        }
+
+        assert_eq!(replica.gates, primary.gates);
+        assert_eq!(replica.assignments, primary.assignments);
+        assert_eq!(replica.reservations, primary.reservations);
+        assert_eq!(replica.disruptions, primary.disruptions);
+        assert_eq!(replica.clock, Some(at(13, 0)));
+        assert_eq!(replica.history, primary.history);
+        assert_eq!(replica.history.len(), 2);
+        assert_eq!(replica.completed_charges, primary.completed_charges);
+        assert!(!replica.gates[0].is_available);
+    }
+}
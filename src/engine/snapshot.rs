@@ -0,0 +1,194 @@
+//! Immutable point-in-time copies of the engine for read-only queries.
+
+use chrono::{DateTime, Utc};
+
+use super::{AllocationEngine, EngineStats, GateInventory};
+use crate::domain::*;
+
+/// Gates and assignments cloned out of an `AllocationEngine`.  Readers (dashboards, list RPCs)
+/// query a snapshot instead of taking the engine lock; it does not change once taken.
+#[derive(Debug, Clone, Default)]
+pub struct EngineSnapshot {
+    pub gates: Vec<Gate>,
+    pub assignments: Vec<GateAssignment>,
+    pub total_disruptions: usize,
+    /// When the snapshot was taken (wall-clock time).
+    pub taken_at: Option<DateTime<Utc>>,
+}
+
+impl AllocationEngine {
+    /// Clone the state served by the read queries into an `EngineSnapshot`.
+    pub fn snapshot(&self) -> EngineSnapshot {
+        EngineSnapshot {
+            gates: self.gates.clone(),
+            assignments: self.assignments.clone(),
+            total_disruptions: self.disruptions.len(),
+            taken_at: Some(Utc::now()),
+        }
+    }
+}
+
+impl EngineSnapshot {
+    /// Combine snapshots of separate engines (typically one per airport) into one read-only
+    /// view; gates, assignments and disruption counts are concatenated and `taken_at` is the
+    /// oldest part's.  Nothing is written back.  Gate ids are assumed unique across the parts.
+    pub fn federate(parts: impl IntoIterator<Item = EngineSnapshot>) -> EngineSnapshot {
+        let mut merged = EngineSnapshot::default();
+        for part in parts {
+            merged.gates.extend(part.gates);
+            merged.assignments.extend(part.assignments);
+            merged.total_disruptions += part.total_disruptions;
+            merged.taken_at = match (merged.taken_at, part.taken_at) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        }
+        merged
+    }
+
+    pub fn get_assignments(&self, terminal: Option<&str>) -> Vec<&GateAssignment> {
+        assignments_in(&self.assignments, terminal)
+    }
+
+    pub fn gate_timeline(&self, gate_id: &str) -> Vec<&GateAssignment> {
+        timeline(&self.assignments, gate_id)
+    }
+
+    pub fn inventory(&self) -> GateInventory {
+        inventory_of(&self.gates)
+    }
+
+    pub fn stats(&self) -> EngineStats {
+        stats_of(&self.gates, &self.assignments, self.total_disruptions)
+    }
+}
+
+// The queries below back both the live engine and snapshots so the two never drift apart.
+
+pub(super) fn assignments_in<'a>(
+    assignments: &'a [GateAssignment],
+    terminal: Option<&str>,
+) -> Vec<&'a GateAssignment> {
+    assignments
+        .iter()
+        .filter(|a| terminal.map(|t| a.gate.terminal == t).unwrap_or(true))
+        .collect()
+}
+
+/// Assignments on `gate_id` ordered by start time.
+pub(super) fn timeline<'a>(
+    assignments: &'a [GateAssignment],
+    gate_id: &str,
+) -> Vec<&'a GateAssignment> {
+    let mut on_gate: Vec<&GateAssignment> = assignments
+        .iter()
+        .filter(|a| a.gate.gate_id == gate_id)
+        .collect();
+    on_gate.sort_by_key(|a| a.assigned_from);
+    on_gate
+}
+
+pub(super) fn inventory_of(gates: &[Gate]) -> GateInventory {
+    let mut inv = GateInventory::default();
+    for g in gates {
+        for count in [
+            &mut inv.overall,
+            inv.by_terminal.entry(g.terminal.clone()).or_default(),
+            inv.by_size.entry(g.size).or_default(),
+            inv.by_terminal_size
+                .entry((g.terminal.clone(), g.size))
+                .or_default(),
+        ] {
+            count.total += 1;
+            if g.is_available {
+                count.available += 1;
+            }
+        }
+    }
+    inv
+}
+
+pub(super) fn stats_of(
+    gates: &[Gate],
+    assignments: &[GateAssignment],
+    total_disruptions: usize,
+) -> EngineStats {
+    let delays: Vec<i64> = assignments
+        .iter()
+        .map(|a| a.delay().num_minutes())
+        .filter(|&m| m > 0)
+        .collect();
+    EngineStats {
+        total_gates: gates.len(),
+        available_gates: gates.iter().filter(|g| g.is_available).count(),
+        occupied_gates: assignments.len(),
+        total_disruptions,
+        delayed_assignments: delays.len(),
+        average_delay_minutes: if delays.is_empty() {
+            0.0
+        } else {
+            delays.iter().sum::<i64>() as f64 / delays.len() as f64
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::{flight, gate};
+    use super::*;
+
+    #[test]
+    fn snapshot_keeps_state_from_before_later_mutations() {
+        let mut e = AllocationEngine::new();
+        e.add_gate(gate("A1", "T5", AircraftSize::Medium));
+        e.add_gate(gate("A2", "T5", AircraftSize::Medium));
+        e.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &["A1".into()]);
+        let before = e.snapshot();
+
+        e.allocate_gate(&flight("F2", "A320", 10, 11), "LHR", &["A1".into()]);
+
+        assert_eq!(before.get_assignments(None).len(), 1);
+        assert_eq!(before.gate_timeline("A1").len(), 1);
+        assert_eq!(before.stats().occupied_gates, 1);
+        assert_eq!(e.get_assignments(None).len(), 2);
+        let live: Vec<&str> = e
+            .gate_timeline("A1")
+            .iter()
+            .map(|a| a.flight.flight_id.as_str())
+            .collect();
+        assert_eq!(live, ["F1", "F2"]);
+        assert_eq!(e.stats().occupied_gates, 2);
+    }
+
+    #[test]
+    fn federated_stats_are_the_sum_of_airport_engines() {
+        let mut lhr = AllocationEngine::new();
+        lhr.add_gate(gate("T5-A1", "T5", AircraftSize::Medium));
+        lhr.add_gate(gate("T5-A2", "T5", AircraftSize::Medium));
+        lhr.allocate_gate(&flight("F1", "A320", 8, 9), "LHR", &[]);
+        let mut jfk = AllocationEngine::new();
+        jfk.add_gate(gate("T4-B1", "T4", AircraftSize::Large));
+        jfk.allocate_gate(&flight("F2", "B777", 8, 10), "JFK", &[]);
+        jfk.allocate_gate(&flight("F3", "A320", 11, 12), "JFK", &[]);
+
+        let parts = [lhr.snapshot(), jfk.snapshot()];
+        let federated = EngineSnapshot::federate(parts.clone());
+        let stats = federated.stats();
+        assert_eq!(
+            stats.total_gates,
+            parts.iter().map(|p| p.stats().total_gates).sum::<usize>()
+        );
+        assert_eq!(
+            stats.occupied_gates,
+            parts
+                .iter()
+                .map(|p| p.stats().occupied_gates)
+                .sum::<usize>()
+        );
+        assert_eq!((stats.total_gates, stats.occupied_gates), (3, 3));
+        assert_eq!(federated.get_assignments(Some("T4")).len(), 2);
+        assert_eq!(federated.inventory().overall.total, 3);
+        // The source engines are untouched.
+        assert_eq!(lhr.get_assignments(None).len(), 1);
+    }
+}
@@ -0,0 +1,51 @@
+//! Fixtures shared by the engine's unit tests.
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::domain::*;
+
+pub(crate) fn gate(id: &str, term: &str, size: AircraftSize) -> Gate {
+    Gate {
+        gate_id: id.into(),
+        terminal: term.into(),
+        size,
+        is_available: true,
+        gate_class: GateClass::Swing,
+        unavailable_windows: Vec::new(),
+        permitted_types: Vec::new(),
+        excluded_types: Vec::new(),
+        mars: false,
+        favours: None,
+        baggage_zone: None,
+        min_occupancy_minutes: None,
+        max_occupancy_minutes: None,
+        remote: false,
+        jetbridge_config: JetbridgeConfig::Both,
+        staffed_shifts: Vec::new(),
+    }
+}
+
+pub(crate) fn flight(id: &str, ac: &str, arr_h: u32, dep_h: u32) -> Flight {
+    Flight {
+        flight_id: id.into(),
+        airline: "Test".into(),
+        codeshare_airlines: Vec::new(),
+        origin: "LHR".into(),
+        destination: "JFK".into(),
+        aircraft_type: ac.into(),
+        scheduled_arrival: at(arr_h, 0),
+        scheduled_departure: at(dep_h, 0),
+        status: FlightStatus::Scheduled,
+        requires_double_stand: false,
+        baggage_zone: None,
+        registration: None,
+        passenger_count: None,
+        is_ferry: false,
+        wingspan_m: None,
+    }
+}
+
+/// 2026-03-01 at `h:m` UTC, the test day used throughout.
+pub(crate) fn at(h: u32, m: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 3, 1, h, m, 0).unwrap()
+}
@@ -0,0 +1,91 @@
+//! Schedule checks run before allocation, for imported or seeded flights.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::domain::*;
+
+/// Two flights flown by the same aircraft whose ground windows overlap, which no real schedule
+/// can do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TailConflict {
+    pub registration: String,
+    pub first_flight: String,
+    pub second_flight: String,
+}
+
+impl fmt::Display for TailConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is on the ground for both {} and {} at once",
+            self.registration, self.first_flight, self.second_flight
+        )
+    }
+}
+
+/// Check that no registration is on two flights whose arrival-to-departure windows overlap.
+/// Flights without a registration, and cancelled, diverted or departed flights, are skipped.
+pub fn validate_registrations(flights: &[Flight]) -> Result<(), Vec<TailConflict>> {
+    let mut by_tail: BTreeMap<&str, Vec<&Flight>> = BTreeMap::new();
+    for f in flights.iter().filter(|f| !f.status.is_terminal()) {
+        if let Some(reg) = &f.registration {
+            by_tail.entry(reg).or_default().push(f);
+        }
+    }
+
+    let mut conflicts = Vec::new();
+    for (reg, mut legs) in by_tail {
+        legs.sort_by_key(|f| f.scheduled_arrival);
+        for (i, first) in legs.iter().enumerate() {
+            for second in &legs[i + 1..] {
+                if second.scheduled_arrival >= first.scheduled_departure {
+                    break;
+                }
+                conflicts.push(TailConflict {
+                    registration: reg.to_string(),
+                    first_flight: first.flight_id.clone(),
+                    second_flight: second.flight_id.clone(),
+                });
+            }
+        }
+    }
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(conflicts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::flight;
+    use super::*;
+
+    #[test]
+    fn overlapping_flights_on_one_tail_are_rejected() {
+        let tail = |id: &str, arr, dep, reg: &str| Flight {
+            registration: Some(reg.into()),
+            ..flight(id, "A320", arr, dep)
+        };
+        let flights = [
+            tail("F1", 8, 10, "G-EUUA"),
+            tail("F2", 9, 11, "G-EUUA"),
+            // Back-to-back on the same tail, and overlapping on different tails, are fine.
+            tail("F3", 11, 12, "G-EUUA"),
+            tail("F4", 8, 10, "G-EUUB"),
+            flight("F5", "A320", 8, 10),
+        ];
+
+        let conflicts = validate_registrations(&flights).unwrap_err();
+        assert_eq!(
+            conflicts,
+            vec![TailConflict {
+                registration: "G-EUUA".into(),
+                first_flight: "F1".into(),
+                second_flight: "F2".into(),
+            }]
+        );
+        assert!(validate_registrations(&flights[2..]).is_ok());
+    }
+}
@@ -1,12 +1,19 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveTime, Timelike, Utc};
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, mpsc, Mutex, Semaphore};
 use tonic::{Request, Response, Status};
-use tracing::info;
+use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::domain;
-use crate::engine::AllocationEngine;
+use crate::engine::{
+    AllocationEngine, AllocationError, AllocationResult, AssignmentChange, DisruptionQuery,
+    EngineSnapshot,
+};
+use crate::policy::AllocationPolicy;
 
 pub mod proto {
     tonic::include_proto!("allocation");
@@ -15,13 +22,177 @@ pub mod proto {
 use proto::allocation_service_server::AllocationService;
 use proto::*;
 
+/// Number of recent disruptions retained for late-joining `StreamDisruptions` subscribers.
+const DISRUPTION_REPLAY_CAPACITY: usize = 64;
+/// Assignment changes buffered per `WatchAssignments` subscriber before it starts missing some.
+const ASSIGNMENT_WATCH_CAPACITY: usize = 256;
+/// Per-subscriber channel depth; a slow client exerts back-pressure on its forwarding task only.
+const STREAM_CHANNEL_CAPACITY: usize = 16;
+/// How often the read snapshot is re-taken to pick up changes made outside the RPC handlers.
+const SNAPSHOT_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+/// Default bound on `AllocateGate` calls in flight at once; see `with_allocation_limit`.
+const DEFAULT_MAX_CONCURRENT_ALLOCATIONS: usize = 32;
+
 pub struct AllocationGrpcService {
     engine: Arc<Mutex<AllocationEngine>>,
+    disruption_tx: broadcast::Sender<DisruptionEvent>,
+    /// Fed by the engine's assignment-change listener; one receiver per `WatchAssignments` call.
+    assignment_tx: broadcast::Sender<AssignmentChange>,
+    /// Bounded history of published disruptions.  Publishing and subscribing both happen under
+    /// this lock so a new subscriber sees each event exactly once (replayed or live, never both).
+    recent_disruptions: Arc<std::sync::Mutex<VecDeque<DisruptionEvent>>>,
+    /// Read-only copy served by the list/inventory RPCs so they never wait on the engine lock.
+    /// Re-taken after every mutating RPC and periodically by `spawn_snapshot_refresh`.
+    snapshot: Arc<std::sync::RwLock<Arc<EngineSnapshot>>>,
+    /// Admission control for `AllocateGate`: calls beyond the limit are refused, not queued.
+    allocation_permits: Arc<Semaphore>,
+    /// Supplies preferred gates for `AllocateGate` requests that name none.
+    policy: AllocationPolicy,
+    /// Per-airport engines added with `with_airport`, keyed by IATA code.
+    airports: HashMap<String, Arc<Mutex<AllocationEngine>>>,
+    /// Where `with_ndjson_output` sends one JSON line per allocation or disruption outcome.
+    outcome_sink: Option<Arc<std::sync::Mutex<Box<dyn Write + Send>>>>,
 }
 
 impl AllocationGrpcService {
     pub fn new(engine: Arc<Mutex<AllocationEngine>>) -> Self {
-        Self { engine }
+        let (disruption_tx, _) = broadcast::channel(DISRUPTION_REPLAY_CAPACITY);
+        let (assignment_tx, _) = broadcast::channel(ASSIGNMENT_WATCH_CAPACITY);
+        // Nothing else holds a freshly shared engine yet; if something does, the first refresh
+        // fills the snapshot in, but assignment changes cannot be watched.
+        let snapshot = match engine.try_lock() {
+            Ok(mut e) => {
+                let tx = assignment_tx.clone();
+                // An error only means nobody is watching right now.
+                e.on_assignment_change(Box::new(move |change| {
+                    let _ = tx.send(change);
+                }));
+                e.snapshot()
+            }
+            Err(_) => {
+                warn!("Engine busy at service start - WatchAssignments will see no changes");
+                EngineSnapshot::default()
+            }
+        };
+        Self {
+            engine,
+            snapshot: Arc::new(std::sync::RwLock::new(Arc::new(snapshot))),
+            disruption_tx,
+            assignment_tx,
+            recent_disruptions: Arc::new(std::sync::Mutex::new(VecDeque::with_capacity(
+                DISRUPTION_REPLAY_CAPACITY,
+            ))),
+            allocation_permits: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_ALLOCATIONS)),
+            policy: AllocationPolicy::default(),
+            airports: HashMap::new(),
+            outcome_sink: None,
+        }
+    }
+
+    /// Route requests for `airport_iata` to `engine`.  Once any airport is registered, requests
+    /// naming an airport without an engine are refused with `NOT_FOUND`, and
+    /// `AnnotateAssignment`, which names none, with `UNIMPLEMENTED`; until then every request
+    /// goes to the service's own engine.  The read snapshot and `spawn_snapshot_refresh` cover
+    /// the service's own engine only, so routed reads take the airport engine's lock.
+    pub fn with_airport(
+        mut self,
+        airport_iata: impl Into<String>,
+        engine: Arc<Mutex<AllocationEngine>>,
+    ) -> Self {
+        let watched = Arc::ptr_eq(&engine, &self.engine)
+            || self.airports.values().any(|e| Arc::ptr_eq(e, &engine));
+        if !watched {
+            match engine.try_lock() {
+                Ok(mut e) => {
+                    let tx = self.assignment_tx.clone();
+                    e.on_assignment_change(Box::new(move |change| {
+                        let _ = tx.send(change);
+                    }));
+                }
+                Err(_) => {
+                    warn!("Airport engine busy when routed - WatchAssignments will see no changes")
+                }
+            }
+        }
+        self.airports.insert(airport_iata.into(), engine);
+        self
+    }
+
+    /// The engine managing `airport_iata`, or `None` if the airport is not routed here.
+    fn engine_for(&self, airport_iata: &str) -> Option<Arc<Mutex<AllocationEngine>>> {
+        if self.airports.is_empty() {
+            return Some(self.engine.clone());
+        }
+        self.airports.get(airport_iata).cloned()
+    }
+
+    /// Fill in preferred gates from `policy` when a request does not list any.
+    pub fn with_policy(mut self, policy: AllocationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Allow at most `limit` simultaneous `AllocateGate` calls; further calls fail fast with
+    /// `RESOURCE_EXHAUSTED` so a burst cannot pile up behind the engine lock.
+    pub fn with_allocation_limit(mut self, limit: usize) -> Self {
+        self.allocation_permits = Arc::new(Semaphore::new(limit));
+        self
+    }
+
+    /// Write every `AllocateGate` and `ReportDisruption` outcome to `out` as a single-line JSON
+    /// record, for log-shipping pipelines.  Separate from the tracing logs.
+    pub fn with_ndjson_output(mut self, out: impl Write + Send + 'static) -> Self {
+        self.outcome_sink = Some(Arc::new(std::sync::Mutex::new(Box::new(out))));
+        self
+    }
+
+    fn emit_outcome(&self, record: serde_json::Value) {
+        let Some(sink) = &self.outcome_sink else {
+            return;
+        };
+        let mut out = sink.lock().unwrap();
+        if let Err(e) = writeln!(out, "{}", record).and_then(|_| out.flush()) {
+            warn!(error = %e, "Could not write NDJSON outcome");
+        }
+    }
+
+    fn publish_disruption(&self, event: DisruptionEvent) {
+        let mut recent = self.recent_disruptions.lock().unwrap();
+        if recent.len() == DISRUPTION_REPLAY_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(event.clone());
+        // An error only means there are no live subscribers right now.
+        let _ = self.disruption_tx.send(event);
+    }
+
+    fn refresh_snapshot(&self, engine: &AllocationEngine) {
+        *self.snapshot.write().unwrap() = Arc::new(engine.snapshot());
+    }
+
+    /// `refresh_snapshot` after a change to `engine`, if it is the one the snapshot mirrors.
+    fn refresh_if_own(&self, engine: &Arc<Mutex<AllocationEngine>>, eng: &AllocationEngine) {
+        if Arc::ptr_eq(engine, &self.engine) {
+            self.refresh_snapshot(eng);
+        }
+    }
+
+    fn read_snapshot(&self) -> Arc<EngineSnapshot> {
+        self.snapshot.read().unwrap().clone()
+    }
+
+    /// Re-take the read snapshot every `SNAPSHOT_REFRESH_INTERVAL` for as long as the process
+    /// runs, so engine changes made outside this service (e.g. the clock) become visible.
+    pub fn spawn_snapshot_refresh(&self) {
+        let (engine, snapshot) = (self.engine.clone(), self.snapshot.clone());
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SNAPSHOT_REFRESH_INTERVAL);
+            loop {
+                interval.tick().await;
+                let fresh = Arc::new(engine.lock().await.snapshot());
+                *snapshot.write().unwrap() = fresh;
+            }
+        });
     }
 }
 
@@ -36,6 +207,7 @@ fn proto_flight(pf: &Flight) -> domain::Flight {
     domain::Flight {
         flight_id: pf.flight_id.clone(),
         airline: pf.airline.clone(),
+        codeshare_airlines: pf.codeshare_airlines.clone(),
         origin: pf.origin.clone(),
         destination: pf.destination.clone(),
         aircraft_type: pf.aircraft_type.clone(),
@@ -46,15 +218,187 @@ fn proto_flight(pf: &Flight) -> domain::Flight {
             Ok(FlightStatus::Cancelled) => domain::FlightStatus::Cancelled,
             _ => domain::FlightStatus::Scheduled,
         },
+        requires_double_stand: pf.requires_double_stand,
+        baggage_zone: Some(pf.baggage_zone.clone()).filter(|z| !z.is_empty()),
+        registration: Some(pf.registration.clone()).filter(|r| !r.is_empty()),
+        passenger_count: Some(pf.passenger_count).filter(|&n| n > 0),
+        is_ferry: pf.is_ferry,
+        wingspan_m: Some(pf.wingspan_m).filter(|&w| w > 0.0),
     }
 }
 
+fn to_proto_disruption(e: &domain::DisruptionEvent) -> DisruptionEvent {
+    DisruptionEvent {
+        event_id: e.event_id.to_string(),
+        r#type: match e.disruption_type {
+            domain::DisruptionType::Delay => DisruptionType::Delay as i32,
+            domain::DisruptionType::Cancellation => DisruptionType::Cancellation as i32,
+            domain::DisruptionType::Diversion => DisruptionType::Diversion as i32,
+            domain::DisruptionType::GateUnavailable => DisruptionType::GateUnavailable as i32,
+            domain::DisruptionType::Weather => DisruptionType::Weather as i32,
+            domain::DisruptionType::Mechanical => DisruptionType::Mechanical as i32,
+        },
+        affected_flight: Some(Flight {
+            flight_id: e.affected_flight_id.clone(),
+            ..Default::default()
+        }),
+        description: e.description.clone(),
+        reported_at_utc: e.reported_at.timestamp(),
+        delay_minutes: e.delay_minutes,
+        airport_iata: e.airport.clone().unwrap_or_default(),
+    }
+}
+
+/// `None` for `DISRUPTION_TYPE_UNKNOWN` and unrecognised codes.
+fn from_proto_disruption_type(code: i32) -> Option<domain::DisruptionType> {
+    match DisruptionType::try_from(code) {
+        Ok(DisruptionType::Delay) => Some(domain::DisruptionType::Delay),
+        Ok(DisruptionType::Cancellation) => Some(domain::DisruptionType::Cancellation),
+        Ok(DisruptionType::Diversion) => Some(domain::DisruptionType::Diversion),
+        Ok(DisruptionType::GateUnavailable) => Some(domain::DisruptionType::GateUnavailable),
+        Ok(DisruptionType::Weather) => Some(domain::DisruptionType::Weather),
+        Ok(DisruptionType::Mechanical) => Some(domain::DisruptionType::Mechanical),
+        _ => None,
+    }
+}
+
+fn to_proto_size(size: domain::AircraftSize) -> i32 {
+    match size {
+        domain::AircraftSize::Small => GateSize::Small as i32,
+        domain::AircraftSize::Medium => GateSize::Medium as i32,
+        domain::AircraftSize::Large => GateSize::Large as i32,
+    }
+}
+
+fn from_proto_size(size: i32) -> Option<domain::AircraftSize> {
+    match GateSize::try_from(size) {
+        Ok(GateSize::Small) => Some(domain::AircraftSize::Small),
+        Ok(GateSize::Medium) => Some(domain::AircraftSize::Medium),
+        Ok(GateSize::Large) => Some(domain::AircraftSize::Large),
+        _ => None,
+    }
+}
+
+fn to_proto_gate(g: &domain::Gate) -> Gate {
+    Gate {
+        gate_id: g.gate_id.clone(),
+        terminal: g.terminal.clone(),
+        size: to_proto_size(g.size),
+        is_available: g.is_available,
+        gate_class: match g.gate_class {
+            domain::GateClass::Domestic => GateClass::Domestic as i32,
+            domain::GateClass::International => GateClass::International as i32,
+            domain::GateClass::Swing => GateClass::Swing as i32,
+        },
+        unavailable_windows: g
+            .unavailable_windows
+            .iter()
+            .map(|(from, until)| TimeWindow {
+                from_utc: from.timestamp(),
+                until_utc: until.timestamp(),
+            })
+            .collect(),
+        permitted_types: g.permitted_types.clone(),
+        excluded_types: g.excluded_types.clone(),
+        mars: g.mars,
+        favours: match g.favours {
+            None => FlightRole::Unknown as i32,
+            Some(domain::FlightRole::Arrival) => FlightRole::Arrival as i32,
+            Some(domain::FlightRole::Departure) => FlightRole::Departure as i32,
+        },
+        baggage_zone: g.baggage_zone.clone().unwrap_or_default(),
+        min_occupancy_minutes: g.min_occupancy_minutes.unwrap_or_default(),
+        max_occupancy_minutes: g.max_occupancy_minutes.unwrap_or_default(),
+        remote: g.remote,
+        jetbridge_config: match g.jetbridge_config {
+            domain::JetbridgeConfig::LeftOnly => JetbridgeConfig::LeftOnly as i32,
+            domain::JetbridgeConfig::RightOnly => JetbridgeConfig::RightOnly as i32,
+            domain::JetbridgeConfig::Both => JetbridgeConfig::Both as i32,
+        },
+        staffed_shifts: g
+            .staffed_shifts
+            .iter()
+            .map(|s| DailyWindow {
+                start_minute: s.start.num_seconds_from_midnight() / 60,
+                end_minute: s.end.num_seconds_from_midnight() / 60,
+            })
+            .collect(),
+    }
+}
+
+/// Validate the gate carried by a `GateRequest`.  Unset enums take the domain defaults (swing
+/// class, no favoured flow, jet bridges on both sides) and zero occupancy limits mean none.
+fn proto_gate(pg: Option<&Gate>) -> Result<domain::Gate, String> {
+    let pg = pg.ok_or("Gate required")?;
+    if pg.gate_id.is_empty() {
+        return Err("gate_id required".into());
+    }
+    let size = from_proto_size(pg.size).ok_or(format!("Gate {} needs a size", pg.gate_id))?;
+    let mut unavailable_windows = Vec::with_capacity(pg.unavailable_windows.len());
+    for w in &pg.unavailable_windows {
+        if w.until_utc <= w.from_utc {
+            return Err(format!(
+                "Gate {} closure must end after it starts",
+                pg.gate_id
+            ));
+        }
+        unavailable_windows.push((ts_to_dt(w.from_utc), ts_to_dt(w.until_utc)));
+    }
+    let time_of_day = |minute: u32| {
+        minute
+            .checked_mul(60)
+            .and_then(|secs| NaiveTime::from_num_seconds_from_midnight_opt(secs, 0))
+            .ok_or(format!(
+                "Gate {} shift minute {} is past midnight",
+                pg.gate_id, minute
+            ))
+    };
+    let mut staffed_shifts = Vec::with_capacity(pg.staffed_shifts.len());
+    for s in &pg.staffed_shifts {
+        staffed_shifts.push(domain::StaffingShift {
+            start: time_of_day(s.start_minute)?,
+            end: time_of_day(s.end_minute)?,
+        });
+    }
+    Ok(domain::Gate {
+        gate_id: pg.gate_id.clone(),
+        terminal: pg.terminal.clone(),
+        size,
+        is_available: pg.is_available,
+        gate_class: match GateClass::try_from(pg.gate_class) {
+            Ok(GateClass::Domestic) => domain::GateClass::Domestic,
+            Ok(GateClass::International) => domain::GateClass::International,
+            _ => domain::GateClass::Swing,
+        },
+        unavailable_windows,
+        permitted_types: pg.permitted_types.clone(),
+        excluded_types: pg.excluded_types.clone(),
+        mars: pg.mars,
+        favours: match FlightRole::try_from(pg.favours) {
+            Ok(FlightRole::Arrival) => Some(domain::FlightRole::Arrival),
+            Ok(FlightRole::Departure) => Some(domain::FlightRole::Departure),
+            _ => None,
+        },
+        baggage_zone: Some(pg.baggage_zone.clone()).filter(|z| !z.is_empty()),
+        min_occupancy_minutes: Some(pg.min_occupancy_minutes).filter(|&m| m > 0),
+        max_occupancy_minutes: Some(pg.max_occupancy_minutes).filter(|&m| m > 0),
+        remote: pg.remote,
+        jetbridge_config: match JetbridgeConfig::try_from(pg.jetbridge_config) {
+            Ok(JetbridgeConfig::LeftOnly) => domain::JetbridgeConfig::LeftOnly,
+            Ok(JetbridgeConfig::RightOnly) => domain::JetbridgeConfig::RightOnly,
+            _ => domain::JetbridgeConfig::Both,
+        },
+        staffed_shifts,
+    })
+}
+
 fn to_proto_assignment(a: &domain::GateAssignment) -> GateAssignment {
     GateAssignment {
         assignment_id: a.assignment_id.to_string(),
         flight: Some(Flight {
             flight_id: a.flight.flight_id.clone(),
             airline: a.flight.airline.clone(),
+            codeshare_airlines: a.flight.codeshare_airlines.clone(),
             origin: a.flight.origin.clone(),
             destination: a.flight.destination.clone(),
             aircraft_type: a.flight.aircraft_type.clone(),
@@ -65,19 +409,50 @@ fn to_proto_assignment(a: &domain::GateAssignment) -> GateAssignment {
                 domain::FlightStatus::Cancelled => FlightStatus::Cancelled as i32,
                 _ => FlightStatus::Scheduled as i32,
             },
+            requires_double_stand: a.flight.requires_double_stand,
+            baggage_zone: a.flight.baggage_zone.clone().unwrap_or_default(),
+            registration: a.flight.registration.clone().unwrap_or_default(),
+            passenger_count: a.flight.passenger_count.unwrap_or_default(),
+            is_ferry: a.flight.is_ferry,
+            wingspan_m: a.flight.wingspan_m.unwrap_or_default(),
         }),
-        gate: Some(Gate {
-            gate_id: a.gate.gate_id.clone(),
-            terminal: a.gate.terminal.clone(),
-            size: match a.gate.size {
-                domain::AircraftSize::Small => GateSize::Small as i32,
-                domain::AircraftSize::Medium => GateSize::Medium as i32,
-                domain::AircraftSize::Large => GateSize::Large as i32,
-            },
-            is_available: a.gate.is_available,
-        }),
+        gate: Some(to_proto_gate(&a.gate)),
         assigned_from_utc: a.assigned_from.timestamp(),
         assigned_until_utc: a.assigned_until.timestamp(),
+        status: match a.status {
+            domain::AssignmentStatus::Provisional => AssignmentStatus::Provisional as i32,
+            domain::AssignmentStatus::Confirmed => AssignmentStatus::Confirmed as i32,
+            domain::AssignmentStatus::Locked => AssignmentStatus::Locked as i32,
+        },
+        notes: a.notes.clone(),
+    }
+}
+
+/// One engine change as a `WatchAssignments` update.
+fn to_proto_update(change: &AssignmentChange) -> AssignmentUpdate {
+    let (kind, previous, current) = match change {
+        AssignmentChange::Added(a) => (AssignmentChangeKind::Added, None, Some(a)),
+        AssignmentChange::Removed(a) => (AssignmentChangeKind::Removed, Some(a), None),
+        AssignmentChange::Moved { from, to } => (AssignmentChangeKind::Moved, Some(from), Some(to)),
+        AssignmentChange::Shifted { from, to } => {
+            (AssignmentChangeKind::Shifted, Some(from), Some(to))
+        }
+    };
+    AssignmentUpdate {
+        kind: kind as i32,
+        assignments: Vec::new(),
+        previous: previous.map(to_proto_assignment),
+        current: current.map(to_proto_assignment),
+    }
+}
+
+/// Assignments a change touches, before and after.
+fn change_assignments(change: &AssignmentChange) -> [Option<&domain::GateAssignment>; 2] {
+    match change {
+        AssignmentChange::Added(a) | AssignmentChange::Removed(a) => [Some(a), None],
+        AssignmentChange::Moved { from, to } | AssignmentChange::Shifted { from, to } => {
+            [Some(from), Some(to)]
+        }
     }
 }
 
@@ -87,17 +462,84 @@ impl AllocationService for AllocationGrpcService {
         &self,
         req: Request<AllocateGateRequest>,
     ) -> Result<Response<AllocateGateResponse>, Status> {
-        let r = req.into_inner();
+        let _permit = self.allocation_permits.try_acquire().map_err(|_| {
+            warn!("Allocation request refused - concurrency limit reached");
+            Status::resource_exhausted("Too many concurrent allocation requests")
+        })?;
+        let mut r = req.into_inner();
         let f = r
             .flight
             .as_ref()
             .ok_or_else(|| Status::invalid_argument("Flight required"))?;
-        let mut eng = self.engine.lock().await;
-        let res = eng.allocate_gate(&proto_flight(f), &r.airport_iata, &r.preferred_gates);
+        if r.preferred_gates.is_empty() {
+            r.preferred_gates = self.policy.preferred_for(&f.airline);
+        }
+        let engine = self
+            .engine_for(&r.airport_iata)
+            .ok_or_else(|| unmanaged(&r.airport_iata))?;
+        // Score against a detached copy so the engine lock is only held to copy and to commit.
+        let (unknown, planner) = {
+            let eng = engine.lock().await;
+            (eng.unknown_gates(&r.preferred_gates), eng.planning_copy())
+        };
+        if !unknown.is_empty() && r.strict_preferred_gates {
+            return Err(Status::invalid_argument(format!(
+                "Unknown preferred gate(s): {}",
+                unknown.join(", ")
+            )));
+        }
+        let flight = proto_flight(f);
+        let planned = {
+            let (flight, airport, preferred) = (
+                flight.clone(),
+                r.airport_iata.clone(),
+                r.preferred_gates.clone(),
+            );
+            tokio::task::spawn_blocking(move || {
+                planner.plan_allocation(&flight, &airport, &preferred)
+            })
+            .await
+            .map_err(|e| Status::internal(format!("Allocation task failed: {}", e)))?
+        };
+        let res = match planned {
+            // Only a full pool can look different on the live engine; any other refusal stands
+            // without taking the lock again.
+            Err((error, message)) if error != AllocationError::NoCompatibleGate => {
+                AllocationResult::failed(error, message)
+            }
+            planned => {
+                let mut eng = engine.lock().await;
+                let res = match planned {
+                    Ok(plan) => eng.commit_plan(plan),
+                    // The copy may be stale in the other direction too (a gate freed meanwhile).
+                    Err(_) => eng.allocate_gate(&flight, &r.airport_iata, &r.preferred_gates),
+                };
+                self.refresh_if_own(&engine, &eng);
+                res
+            }
+        };
+        let mut message = res.message;
+        if !unknown.is_empty() {
+            message.push_str(&format!(
+                " (ignored unknown preferred gate(s): {})",
+                unknown.join(", ")
+            ));
+        }
+        self.emit_outcome(json!({
+            "kind": "allocation",
+            "at": Utc::now().to_rfc3339(),
+            "airport": r.airport_iata,
+            "flight_id": flight.flight_id,
+            "success": res.success,
+            "gate_id": res.assignment.as_ref().map(|a| &a.gate.gate_id),
+            "assignment_id": res.assignment.as_ref().map(|a| a.assignment_id),
+            "message": message,
+        }));
         Ok(Response::new(AllocateGateResponse {
             success: res.success,
             assignment: res.assignment.map(|a| to_proto_assignment(&a)),
-            message: res.message,
+            message,
+            unknown_preferred_gates: unknown,
         }))
     }
 
@@ -110,28 +552,55 @@ impl AllocationService for AllocationGrpcService {
             .affected_flight
             .as_ref()
             .ok_or_else(|| Status::invalid_argument("Flight required"))?;
+        let engine = self
+            .engine_for(&r.airport_iata)
+            .ok_or_else(|| unmanaged(&r.airport_iata))?;
         let event = domain::DisruptionEvent {
             event_id: Uuid::new_v4(),
-            disruption_type: match DisruptionType::try_from(r.r#type) {
-                Ok(DisruptionType::Cancellation) => domain::DisruptionType::Cancellation,
-                Ok(DisruptionType::Diversion) => domain::DisruptionType::Diversion,
-                Ok(DisruptionType::GateUnavailable) => domain::DisruptionType::GateUnavailable,
-                Ok(DisruptionType::Weather) => domain::DisruptionType::Weather,
-                Ok(DisruptionType::Mechanical) => domain::DisruptionType::Mechanical,
-                // Unknown / unrecognised codes are treated as a Delay (conservative default).
-                _ => domain::DisruptionType::Delay,
-            },
+            // Unknown / unrecognised codes are treated as a Delay (conservative default).
+            disruption_type: from_proto_disruption_type(r.r#type)
+                .unwrap_or(domain::DisruptionType::Delay),
             affected_flight_id: af.flight_id.clone(),
             description: r.description,
             reported_at: Utc::now(),
             delay_minutes: r.delay_minutes,
+            airport: Some(r.airport_iata).filter(|a| !a.is_empty()),
         };
-        let mut eng = self.engine.lock().await;
+        let published = to_proto_disruption(&event);
+        let reported = (
+            event.event_id,
+            event.disruption_type,
+            event.affected_flight_id.clone(),
+            event.reported_at,
+        );
+        let mut eng = engine.lock().await;
         let res = eng.handle_disruption(event);
+        self.refresh_if_own(&engine, &eng);
+        drop(eng);
+        self.publish_disruption(published);
+        let reassignments: Vec<_> = res
+            .reassignments
+            .iter()
+            .map(|a| json!({"flight_id": a.flight.flight_id, "gate_id": a.gate.gate_id}))
+            .collect();
+        let (event_id, disruption_type, flight_id, reported_at) = reported;
+        self.emit_outcome(json!({
+            "kind": "disruption",
+            "at": reported_at.to_rfc3339(),
+            "event_id": event_id,
+            "disruption_type": disruption_type,
+            "flight_id": flight_id,
+            "acknowledged": res.acknowledged,
+            "reassignments": reassignments,
+            "summary": res.summary,
+            "estimated_reallocation_cost": res.estimated_reallocation_cost,
+        }));
         Ok(Response::new(DisruptionResponse {
             acknowledged: res.acknowledged,
             reassignments: res.reassignments.iter().map(to_proto_assignment).collect(),
+            previous: res.previous.iter().map(to_proto_assignment).collect(),
             summary: res.summary,
+            estimated_reallocation_cost: res.estimated_reallocation_cost,
         }))
     }
 
@@ -145,12 +614,180 @@ impl AllocationService for AllocationGrpcService {
         } else {
             Some(r.terminal.as_str())
         };
-        let eng = self.engine.lock().await;
-        Ok(Response::new(GateAssignmentsResponse {
-            assignments: eng
+        let assignments = if self.airports.is_empty() {
+            self.read_snapshot()
                 .get_assignments(tf)
                 .iter()
                 .map(|a| to_proto_assignment(a))
+                .collect()
+        } else {
+            let engine = self
+                .engine_for(&r.airport_iata)
+                .ok_or_else(|| unmanaged(&r.airport_iata))?;
+            let eng = engine.lock().await;
+            eng.get_assignments(tf)
+                .iter()
+                .map(|a| to_proto_assignment(a))
+                .collect()
+        };
+        Ok(Response::new(GateAssignmentsResponse { assignments }))
+    }
+
+    async fn get_inventory(
+        &self,
+        req: Request<InventoryRequest>,
+    ) -> Result<Response<InventoryResponse>, Status> {
+        let airport_iata = req.into_inner().airport_iata;
+        let inv = if self.airports.is_empty() {
+            self.read_snapshot().inventory()
+        } else {
+            let engine = self
+                .engine_for(&airport_iata)
+                .ok_or_else(|| unmanaged(&airport_iata))?;
+            let eng = engine.lock().await;
+            eng.inventory()
+        };
+        Ok(Response::new(InventoryResponse {
+            total: inv.overall.total as u32,
+            available: inv.overall.available as u32,
+            entries: inv
+                .by_terminal_size
+                .iter()
+                .map(|((terminal, size), count)| InventoryEntry {
+                    terminal: terminal.clone(),
+                    size: to_proto_size(*size),
+                    total: count.total as u32,
+                    available: count.available as u32,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn register_gate(
+        &self,
+        req: Request<GateRequest>,
+    ) -> Result<Response<GateResponse>, Status> {
+        let r = req.into_inner();
+        let gate = proto_gate(r.gate.as_ref()).map_err(Status::invalid_argument)?;
+        let engine = self
+            .engine_for(&r.airport_iata)
+            .ok_or_else(|| unmanaged(&r.airport_iata))?;
+        let mut eng = engine.lock().await;
+        if eng.gates.iter().any(|g| g.gate_id == gate.gate_id) {
+            return Err(Status::already_exists(format!(
+                "Gate {} already registered",
+                gate.gate_id
+            )));
+        }
+        let reply = GateResponse {
+            gate: Some(to_proto_gate(&gate)),
+            message: format!("Registered {}", gate),
+        };
+        eng.add_gate(gate);
+        self.refresh_if_own(&engine, &eng);
+        Ok(Response::new(reply))
+    }
+
+    async fn update_gate(
+        &self,
+        req: Request<GateRequest>,
+    ) -> Result<Response<GateResponse>, Status> {
+        let r = req.into_inner();
+        let gate = proto_gate(r.gate.as_ref()).map_err(Status::invalid_argument)?;
+        let engine = self
+            .engine_for(&r.airport_iata)
+            .ok_or_else(|| unmanaged(&r.airport_iata))?;
+        let mut eng = engine.lock().await;
+        if !eng.gates.iter().any(|g| g.gate_id == gate.gate_id) {
+            return Err(Status::not_found(format!("Unknown gate {}", gate.gate_id)));
+        }
+        let stored = eng.update_gate(gate).map_err(Status::failed_precondition)?;
+        self.refresh_if_own(&engine, &eng);
+        Ok(Response::new(GateResponse {
+            gate: Some(to_proto_gate(&stored)),
+            message: format!("Updated {}", stored),
+        }))
+    }
+
+    async fn remove_gate(
+        &self,
+        req: Request<RemoveGateRequest>,
+    ) -> Result<Response<GateResponse>, Status> {
+        let r = req.into_inner();
+        let gate_id = r.gate_id;
+        let engine = self
+            .engine_for(&r.airport_iata)
+            .ok_or_else(|| unmanaged(&r.airport_iata))?;
+        let mut eng = engine.lock().await;
+        if !eng.gates.iter().any(|g| g.gate_id == gate_id) {
+            return Err(Status::not_found(format!("Unknown gate {}", gate_id)));
+        }
+        let removed = eng
+            .remove_gate(&gate_id)
+            .map_err(Status::failed_precondition)?;
+        self.refresh_if_own(&engine, &eng);
+        Ok(Response::new(GateResponse {
+            gate: Some(to_proto_gate(&removed)),
+            message: format!("Removed {}", removed.gate_id),
+        }))
+    }
+
+    /// Not available once airports are routed with `with_airport`: the request names no airport
+    /// to route by.
+    async fn annotate_assignment(
+        &self,
+        req: Request<AnnotateAssignmentRequest>,
+    ) -> Result<Response<GateAssignment>, Status> {
+        if !self.airports.is_empty() {
+            return Err(Status::unimplemented(
+                "AnnotateAssignment names no airport and cannot be routed",
+            ));
+        }
+        let r = req.into_inner();
+        let id = Uuid::parse_str(&r.assignment_id)
+            .map_err(|e| Status::invalid_argument(format!("Invalid assignment id: {}", e)))?;
+        let mut eng = self.engine.lock().await;
+        let Some(pos) = eng.assignments.iter().position(|a| a.assignment_id == id) else {
+            return Err(Status::not_found(format!("Unknown assignment {}", id)));
+        };
+        eng.annotate_assignment(id, &r.note)
+            .map_err(Status::invalid_argument)?;
+        self.refresh_snapshot(&eng);
+        Ok(Response::new(to_proto_assignment(&eng.assignments[pos])))
+    }
+
+    async fn get_disruptions(
+        &self,
+        req: Request<GetDisruptionsRequest>,
+    ) -> Result<Response<GetDisruptionsResponse>, Status> {
+        let r = req.into_inner();
+        let mut types = Vec::with_capacity(r.types.len());
+        for &code in &r.types {
+            types.push(from_proto_disruption_type(code).ok_or_else(|| {
+                Status::invalid_argument(format!("Unknown disruption type {}", code))
+            })?);
+        }
+        let time = |secs: i64| {
+            (secs != 0)
+                .then(|| DateTime::from_timestamp(secs, 0))
+                .flatten()
+        };
+        let query = DisruptionQuery {
+            types,
+            flight_id: Some(r.flight_id).filter(|id| !id.is_empty()),
+            from: time(r.from_utc),
+            until: time(r.until_utc),
+        };
+        let engine = self
+            .engine_for(&r.airport_iata)
+            .ok_or_else(|| unmanaged(&r.airport_iata))?;
+        let eng = engine.lock().await;
+        Ok(Response::new(GetDisruptionsResponse {
+            disruptions: eng
+                .disruptions_matching(&query)
+                .into_iter()
+                .filter(|e| r.airport_iata.is_empty() || e.covers_airport(&r.airport_iata))
+                .map(to_proto_disruption)
                 .collect(),
         }))
     }
@@ -158,32 +795,847 @@ impl AllocationService for AllocationGrpcService {
     type StreamDisruptionsStream =
         tokio_stream::wrappers::ReceiverStream<Result<proto::DisruptionEvent, Status>>;
 
-    /// Stub implementation — returns an immediately-closed stream.
-    /// A full implementation would store the `tx` sender on `AllocationGrpcService` (wrapped in a
-    /// `broadcast::Sender`) and push `DisruptionEvent` messages whenever `handle_disruption` is called.
+    /// Stream disruptions as they are reported.  With `replay_last > 0` the subscriber first
+    /// receives up to that many recent events.  Each subscriber is fed through its own bounded
+    /// channel; if it falls so far behind that the broadcast buffer wraps, the skipped events are
+    /// dropped (and logged) rather than stalling publishers.
     async fn stream_disruptions(
         &self,
-        _req: Request<StreamDisruptionsRequest>,
+        req: Request<StreamDisruptionsRequest>,
     ) -> Result<Response<Self::StreamDisruptionsStream>, Status> {
-        let (_tx, rx) = tokio::sync::mpsc::channel(16);
+        let replay_last = req.into_inner().replay_last as usize;
+        let (replay, mut live) = {
+            let recent = self.recent_disruptions.lock().unwrap();
+            let skip = recent.len().saturating_sub(replay_last);
+            let replay: Vec<DisruptionEvent> = recent.iter().skip(skip).cloned().collect();
+            (replay, self.disruption_tx.subscribe())
+        };
+
+        let (tx, rx) = mpsc::channel(STREAM_CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            for event in replay {
+                if tx.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+            loop {
+                match live.recv().await {
+                    Ok(event) => {
+                        if tx.send(Ok(event)).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "Disruption subscriber lagging - events dropped");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+            }
+        });
+        Ok(Response::new(tokio_stream::wrappers::ReceiverStream::new(
+            rx,
+        )))
+    }
+
+    type WatchAssignmentsStream =
+        tokio_stream::wrappers::ReceiverStream<Result<AssignmentUpdate, Status>>;
+
+    /// Send the current assignments as one `SNAPSHOT` update, then an update per change.  The
+    /// snapshot is taken under the engine lock, so no change is missed or sent twice.  Once
+    /// airports are routed, covers the engine of the requested airport.  A subscriber that falls
+    /// too far behind loses the skipped changes (logged).
+    async fn watch_assignments(
+        &self,
+        req: Request<WatchAssignmentsRequest>,
+    ) -> Result<Response<Self::WatchAssignmentsStream>, Status> {
+        let airport = req.into_inner().airport_iata;
+        let engine = self
+            .engine_for(&airport)
+            .ok_or_else(|| unmanaged(&airport))?;
+        let covers = move |a: &domain::GateAssignment| airport.is_empty() || a.airport == airport;
+        let (current, mut live) = {
+            let eng = engine.lock().await;
+            let current: Vec<GateAssignment> = eng
+                .assignments
+                .iter()
+                .filter(|a| covers(a))
+                .map(to_proto_assignment)
+                .collect();
+            (current, self.assignment_tx.subscribe())
+        };
+
+        let (tx, rx) = mpsc::channel(STREAM_CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            let snapshot = AssignmentUpdate {
+                kind: AssignmentChangeKind::Snapshot as i32,
+                assignments: current,
+                previous: None,
+                current: None,
+            };
+            if tx.send(Ok(snapshot)).await.is_err() {
+                return;
+            }
+            loop {
+                match live.recv().await {
+                    Ok(change) => {
+                        if !change_assignments(&change)
+                            .into_iter()
+                            .flatten()
+                            .any(&covers)
+                        {
+                            continue;
+                        }
+                        if tx.send(Ok(to_proto_update(&change))).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "Assignment watcher lagging - changes dropped");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+            }
+        });
         Ok(Response::new(tokio_stream::wrappers::ReceiverStream::new(
             rx,
         )))
     }
 }
 
+/// The status airport-scoped RPCs return for an airport `with_airport` did not route.
+fn unmanaged(airport_iata: &str) -> Status {
+    Status::not_found(format!("Airport {} is not managed here", airport_iata))
+}
+
+/// Serve on `addr`; with `ndjson`, outcomes are also written to stdout as NDJSON records.
 pub async fn start_grpc_server(
     engine: Arc<Mutex<AllocationEngine>>,
+    policy: AllocationPolicy,
     addr: std::net::SocketAddr,
+    ndjson: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    info!(%addr, "Starting gRPC server");
+    info!(%addr, ndjson, "Starting gRPC server");
+    let mut service = AllocationGrpcService::new(engine).with_policy(policy);
+    if ndjson {
+        service = service.with_ndjson_output(std::io::stdout());
+    }
+    service.spawn_snapshot_refresh();
     tonic::transport::Server::builder()
-        .add_service(
-            proto::allocation_service_server::AllocationServiceServer::new(
-                AllocationGrpcService::new(engine),
-            ),
-        )
+        .add_service(proto::allocation_service_server::AllocationServiceServer::new(service))
         .serve(addr)
         .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+
+    fn service() -> AllocationGrpcService {
+        AllocationGrpcService::new(Arc::new(Mutex::new(AllocationEngine::new())))
+    }
+
+    fn service_with_gate(gate_id: &str) -> AllocationGrpcService {
+        AllocationGrpcService::new(Arc::new(Mutex::new(engine_with_gate(gate_id))))
+    }
+
+    fn engine_with_gate(gate_id: &str) -> AllocationEngine {
+        engine_with_gates(&[gate_id])
+    }
+
+    /// An engine with a Large T5 gate for each id.
+    fn engine_with_gates<S: AsRef<str>>(gate_ids: &[S]) -> AllocationEngine {
+        let mut engine = AllocationEngine::new();
+        for id in gate_ids {
+            engine.add_gate(
+                domain::Gate::builder()
+                    .gate_id(id.as_ref())
+                    .terminal("T5")
+                    .size(domain::AircraftSize::Large)
+                    .build()
+                    .unwrap(),
+            );
+        }
+        engine
+    }
+
+    fn allocate_request(flight_id: &str, preferred: &[&str]) -> AllocateGateRequest {
+        AllocateGateRequest {
+            flight: Some(Flight {
+                flight_id: flight_id.into(),
+                airline: "British Airways".into(),
+                origin: "JFK".into(),
+                destination: "LHR".into(),
+                aircraft_type: "B777".into(),
+                scheduled_arrival_utc: 1_773_556_200,
+                scheduled_departure_utc: 1_773_566_100,
+                status: FlightStatus::Scheduled as i32,
+                ..Default::default()
+            }),
+            airport_iata: "LHR".into(),
+            preferred_gates: preferred.iter().map(|s| s.to_string()).collect(),
+            strict_preferred_gates: false,
+        }
+    }
+
+    fn weather(flight_id: &str) -> proto::DisruptionEvent {
+        proto::DisruptionEvent {
+            r#type: DisruptionType::Weather as i32,
+            affected_flight: Some(Flight {
+                flight_id: flight_id.into(),
+                ..Default::default()
+            }),
+            description: "Thunderstorms".into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn late_subscriber_replays_recent_then_live() {
+        let svc = service();
+        svc.report_disruption(Request::new(weather("F1")))
+            .await
+            .unwrap();
+        svc.report_disruption(Request::new(weather("F2")))
+            .await
+            .unwrap();
+
+        let mut stream = svc
+            .stream_disruptions(Request::new(StreamDisruptionsRequest {
+                airport_iata: "LHR".into(),
+                replay_last: 2,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        svc.report_disruption(Request::new(weather("F3")))
+            .await
+            .unwrap();
+
+        let mut seen = Vec::new();
+        for _ in 0..3 {
+            let event = stream.next().await.unwrap().unwrap();
+            seen.push(event.affected_flight.unwrap().flight_id);
+        }
+        assert_eq!(seen, vec!["F1", "F2", "F3"]);
+    }
+
+    #[tokio::test]
+    async fn subscriber_without_replay_sees_only_live_events() {
+        let svc = service();
+        svc.report_disruption(Request::new(weather("F1")))
+            .await
+            .unwrap();
+        let mut stream = svc
+            .stream_disruptions(Request::new(StreamDisruptionsRequest::default()))
+            .await
+            .unwrap()
+            .into_inner();
+        svc.report_disruption(Request::new(weather("F2")))
+            .await
+            .unwrap();
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.affected_flight.unwrap().flight_id, "F2");
+    }
+
+    #[tokio::test]
+    async fn unknown_preferred_gate_is_reported() {
+        let svc = service_with_gate("T5-A1");
+        let res = svc
+            .allocate_gate(Request::new(allocate_request(
+                "BA-001",
+                &["T5-A1", "T5-Z9"],
+            )))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(res.success);
+        assert_eq!(res.unknown_preferred_gates, vec!["T5-Z9"]);
+        assert!(res.message.contains("T5-Z9"));
+    }
+
+    #[tokio::test]
+    async fn strict_mode_rejects_unknown_preferred_gate() {
+        let svc = service_with_gate("T5-A1");
+        let mut req = allocate_request("BA-001", &["T5-Z9"]);
+        req.strict_preferred_gates = true;
+        let err = svc.allocate_gate(Request::new(req)).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        assert!(svc.engine.lock().await.assignments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn get_inventory_reports_terminal_size_counts() {
+        let svc = service_with_gate("T5-A1");
+        let inv = svc
+            .get_inventory(Request::new(InventoryRequest::default()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!((inv.total, inv.available), (1, 1));
+        assert_eq!(inv.entries.len(), 1);
+        assert_eq!(inv.entries[0].terminal, "T5");
+        assert_eq!(inv.entries[0].size, GateSize::Large as i32);
+    }
+
+    fn gate_request(gate_id: &str, size: GateSize) -> GateRequest {
+        GateRequest {
+            airport_iata: "LHR".into(),
+            gate: Some(Gate {
+                gate_id: gate_id.into(),
+                terminal: "T3".into(),
+                size: size as i32,
+                is_available: true,
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn register_gate_adds_to_pool_once() {
+        let svc = service();
+        let reply = svc
+            .register_gate(Request::new(gate_request("T3-B1", GateSize::Large)))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(reply.gate.unwrap().gate_id, "T3-B1");
+        let res = svc
+            .allocate_gate(Request::new(allocate_request("BA-001", &[])))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(res.assignment.unwrap().gate.unwrap().gate_id, "T3-B1");
+
+        let err = svc
+            .register_gate(Request::new(gate_request("T3-B1", GateSize::Large)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::AlreadyExists);
+    }
+
+    #[tokio::test]
+    async fn update_gate_replaces_attributes() {
+        let svc = service_with_gate("T5-A1");
+        let reply = svc
+            .update_gate(Request::new(gate_request("T5-A1", GateSize::Medium)))
+            .await
+            .unwrap()
+            .into_inner();
+        let gate = reply.gate.unwrap();
+        assert_eq!(
+            (gate.terminal.as_str(), gate.size),
+            ("T3", GateSize::Medium as i32)
+        );
+        let res = svc
+            .allocate_gate(Request::new(allocate_request("BA-001", &[])))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!res.success, "a B777 no longer fits the shrunk gate");
+
+        let err = svc
+            .update_gate(Request::new(gate_request("T9-Z1", GateSize::Medium)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn remove_gate_is_refused_while_assigned() {
+        let svc = service_with_gate("T5-A1");
+        svc.allocate_gate(Request::new(allocate_request("BA-001", &[])))
+            .await
+            .unwrap();
+        let remove = || RemoveGateRequest {
+            airport_iata: "LHR".into(),
+            gate_id: "T5-A1".into(),
+        };
+
+        let err = svc.remove_gate(Request::new(remove())).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+
+        let mut cancel = weather("BA-001");
+        cancel.r#type = DisruptionType::Cancellation as i32;
+        svc.report_disruption(Request::new(cancel)).await.unwrap();
+        svc.remove_gate(Request::new(remove())).await.unwrap();
+        let inv = svc
+            .get_inventory(Request::new(InventoryRequest::default()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(inv.total, 0);
+    }
+
+    #[tokio::test]
+    async fn concurrent_allocations_never_double_book() {
+        let engine = engine_with_gates(&["T5-A1", "T5-A2", "T5-A3"]);
+        let svc = Arc::new(AllocationGrpcService::new(Arc::new(Mutex::new(engine))));
+        let tasks: Vec<_> = (0..6)
+            .map(|i| {
+                let svc = Arc::clone(&svc);
+                tokio::spawn(async move {
+                    svc.allocate_gate(Request::new(allocate_request(&format!("BA-{}", i), &[])))
+                        .await
+                        .unwrap()
+                        .into_inner()
+                        .success
+                })
+            })
+            .collect();
+        let mut placed = 0;
+        for t in tasks {
+            if t.await.unwrap() {
+                placed += 1;
+            }
+        }
+        assert_eq!(placed, 3);
+        let eng = svc.engine.lock().await;
+        let mut gates: Vec<&str> = eng
+            .assignments
+            .iter()
+            .map(|a| a.gate.gate_id.as_str())
+            .collect();
+        gates.sort();
+        assert_eq!(gates, vec!["T5-A1", "T5-A2", "T5-A3"]);
+    }
+
+    #[tokio::test]
+    async fn refused_plans_do_not_wait_for_the_engine_again() {
+        let svc = Arc::new(service_with_gate("T5-A1"));
+        let mut req = allocate_request("BA-1", &[]);
+        req.flight.as_mut().unwrap().status = FlightStatus::Cancelled as i32;
+
+        // Queue the call behind us, then take the engine back as soon as it has its copy.
+        let guard = svc.engine.lock().await;
+        let call = {
+            let svc = Arc::clone(&svc);
+            tokio::spawn(async move { svc.allocate_gate(Request::new(req)).await })
+        };
+        for _ in 0..5 {
+            tokio::task::yield_now().await;
+        }
+        drop(guard);
+        let _guard = svc.engine.lock().await;
+
+        let res = tokio::time::timeout(std::time::Duration::from_secs(5), call)
+            .await
+            .expect("refusal waited on the engine lock")
+            .unwrap()
+            .unwrap()
+            .into_inner();
+        assert!(!res.success);
+        assert!(
+            res.message.contains("cannot be allocated"),
+            "{}",
+            res.message
+        );
+    }
+
+    #[tokio::test]
+    async fn allocations_over_the_limit_are_refused() {
+        let ids: Vec<String> = (0..6).map(|i| format!("T5-A{}", i)).collect();
+        let engine = engine_with_gates(&ids);
+        let svc = Arc::new(
+            AllocationGrpcService::new(Arc::new(Mutex::new(engine))).with_allocation_limit(2),
+        );
+        // Hold the engine so admitted requests stay in flight while the rest arrive.
+        let guard = svc.engine.lock().await;
+        let tasks: Vec<_> = (0..5)
+            .map(|i| {
+                let svc = Arc::clone(&svc);
+                tokio::spawn(async move {
+                    svc.allocate_gate(Request::new(allocate_request(&format!("BA-{}", i), &[])))
+                        .await
+                })
+            })
+            .collect();
+        for _ in 0..5 {
+            tokio::task::yield_now().await;
+        }
+        drop(guard);
+
+        let (mut placed, mut refused) = (0, 0);
+        for t in tasks {
+            match t.await.unwrap() {
+                Ok(res) => {
+                    assert!(res.into_inner().success);
+                    placed += 1;
+                }
+                Err(status) => {
+                    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+                    refused += 1;
+                }
+            }
+        }
+        assert_eq!((placed, refused), (2, 3));
+        assert_eq!(svc.engine.lock().await.assignments.len(), 2);
+        // Permits are returned once the admitted calls finish.
+        let res = svc
+            .allocate_gate(Request::new(allocate_request("BA-9", &[])))
+            .await
+            .unwrap();
+        assert!(res.into_inner().success);
+    }
+
+    #[tokio::test]
+    async fn recurring_flight_sticks_to_its_gate_over_grpc() {
+        let mut engine = engine_with_gates(&["T5-A1", "T5-A2"]);
+        engine.config.scoring.gate_stickiness_reward = Some(2.0);
+        let today = proto_flight(allocate_request("BA-1", &[]).flight.as_ref().unwrap());
+        let day = chrono::Duration::days(1);
+        let yesterday = domain::Flight {
+            scheduled_arrival: today.scheduled_arrival - day,
+            scheduled_departure: today.scheduled_departure - day,
+            ..today.clone()
+        };
+        engine.allocate_gate(&yesterday, "LHR", &["T5-A2".into()]);
+        engine.advance_to(today.scheduled_arrival - chrono::Duration::hours(1));
+        let svc = AllocationGrpcService::new(Arc::new(Mutex::new(engine)));
+
+        let res = svc
+            .allocate_gate(Request::new(allocate_request("BA-1", &[])))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(res.assignment.unwrap().gate.unwrap().gate_id, "T5-A2");
+    }
+
+    #[tokio::test]
+    async fn allocations_are_routed_by_airport() {
+        let lhr = Arc::new(Mutex::new(engine_with_gate("T5-A1")));
+        let jfk = Arc::new(Mutex::new(engine_with_gates(&["T4-A1", "T4-A2"])));
+        let svc = service()
+            .with_airport("LHR", lhr.clone())
+            .with_airport("JFK", jfk.clone());
+
+        let to_lhr = svc
+            .allocate_gate(Request::new(allocate_request("BA-1", &[])))
+            .await
+            .unwrap()
+            .into_inner();
+        let mut req = allocate_request("BA-2", &[]);
+        req.airport_iata = "JFK".into();
+        let to_jfk = svc
+            .allocate_gate(Request::new(req))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(to_lhr.assignment.unwrap().gate.unwrap().gate_id, "T5-A1");
+        assert_eq!(to_jfk.assignment.unwrap().gate.unwrap().gate_id, "T4-A1");
+        assert_eq!(lhr.lock().await.assignments.len(), 1);
+        assert_eq!(jfk.lock().await.assignments.len(), 1);
+
+        let mut req = allocate_request("BA-3", &[]);
+        req.airport_iata = "CDG".into();
+        let err = svc.allocate_gate(Request::new(req)).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+
+        let inventory = |airport: &str| {
+            svc.get_inventory(Request::new(InventoryRequest {
+                airport_iata: airport.into(),
+            }))
+        };
+        let inv = inventory("JFK").await.unwrap().into_inner();
+        assert_eq!((inv.total, inv.available), (2, 2));
+        let err = inventory("CDG").await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn airport_scoped_rpcs_are_routed() {
+        let lhr = Arc::new(Mutex::new(engine_with_gate("T5-A1")));
+        let jfk = Arc::new(Mutex::new(engine_with_gate("T4-A1")));
+        let svc = service()
+            .with_airport("LHR", lhr.clone())
+            .with_airport("JFK", jfk.clone());
+        let mut req = allocate_request("BA-1", &[]);
+        req.airport_iata = "JFK".into();
+        svc.allocate_gate(Request::new(req)).await.unwrap();
+
+        let mut watch = svc
+            .watch_assignments(Request::new(WatchAssignmentsRequest {
+                airport_iata: "JFK".into(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let first = watch.next().await.unwrap().unwrap();
+        assert_eq!(first.assignments.len(), 1);
+
+        let cancel = |airport: &str| proto::DisruptionEvent {
+            r#type: DisruptionType::Cancellation as i32,
+            airport_iata: airport.into(),
+            ..weather("BA-1")
+        };
+        let err = svc
+            .report_disruption(Request::new(cancel("CDG")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+        svc.report_disruption(Request::new(cancel("JFK")))
+            .await
+            .unwrap();
+        assert!(jfk.lock().await.assignments.is_empty());
+        let update = watch.next().await.unwrap().unwrap();
+        assert_eq!(update.kind, AssignmentChangeKind::Removed as i32);
+
+        let listed = |airport: &str| {
+            svc.get_gate_assignments(Request::new(GateAssignmentsRequest {
+                airport_iata: airport.into(),
+                terminal: String::new(),
+            }))
+        };
+        assert!(listed("JFK")
+            .await
+            .unwrap()
+            .into_inner()
+            .assignments
+            .is_empty());
+        assert_eq!(
+            listed("CDG").await.unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+        let logged = svc
+            .get_disruptions(Request::new(GetDisruptionsRequest {
+                airport_iata: "JFK".into(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(logged.disruptions.len(), 1);
+        assert!(lhr.lock().await.disruption_log().is_empty());
+
+        let mut add = gate_request("T4-B1", GateSize::Medium);
+        add.airport_iata = "JFK".into();
+        svc.register_gate(Request::new(add)).await.unwrap();
+        assert_eq!(jfk.lock().await.gates.len(), 2);
+        assert_eq!(lhr.lock().await.gates.len(), 1);
+        let err = svc
+            .remove_gate(Request::new(RemoveGateRequest {
+                airport_iata: "CDG".into(),
+                gate_id: "T4-B1".into(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+
+        let err = svc
+            .annotate_assignment(Request::new(AnnotateAssignmentRequest {
+                assignment_id: Uuid::new_v4().to_string(),
+                note: "VIP handling".into(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unimplemented);
+    }
+
+    #[tokio::test]
+    async fn watcher_sees_snapshot_then_new_assignment() {
+        let svc = service_with_gate("T5-A1");
+        svc.allocate_gate(Request::new(allocate_request("BA-1", &[])))
+            .await
+            .unwrap();
+
+        let mut stream = svc
+            .watch_assignments(Request::new(WatchAssignmentsRequest {
+                airport_iata: "LHR".into(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let first = stream.next().await.unwrap().unwrap();
+        assert_eq!(first.kind, AssignmentChangeKind::Snapshot as i32);
+        assert_eq!(first.assignments.len(), 1);
+
+        let mut later = allocate_request("BA-2", &[]);
+        let f = later.flight.as_mut().unwrap();
+        f.scheduled_arrival_utc += 6 * 3600;
+        f.scheduled_departure_utc += 6 * 3600;
+        svc.allocate_gate(Request::new(later)).await.unwrap();
+
+        let update = stream.next().await.unwrap().unwrap();
+        assert_eq!(update.kind, AssignmentChangeKind::Added as i32);
+        let added = update.current.unwrap();
+        assert_eq!(added.flight.unwrap().flight_id, "BA-2");
+        assert_eq!(added.gate.unwrap().gate_id, "T5-A1");
+    }
+
+    #[tokio::test]
+    async fn annotations_appear_in_assignment_listing() {
+        let svc = service_with_gate("T5-A1");
+        let placed = svc
+            .allocate_gate(Request::new(allocate_request("BA-001", &[])))
+            .await
+            .unwrap()
+            .into_inner()
+            .assignment
+            .unwrap();
+        let annotate = |note: &str| AnnotateAssignmentRequest {
+            assignment_id: placed.assignment_id.clone(),
+            note: note.into(),
+        };
+
+        let annotated = svc
+            .annotate_assignment(Request::new(annotate("VIP handling")))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(annotated.notes, ["VIP handling"]);
+        let err = svc
+            .annotate_assignment(Request::new(annotate("")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        let listed = svc
+            .get_gate_assignments(Request::new(GateAssignmentsRequest::default()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(listed.assignments[0].notes, ["VIP handling"]);
+    }
+
+    #[tokio::test]
+    async fn ndjson_output_records_each_allocation() {
+        #[derive(Clone, Default)]
+        struct Captured(Arc<std::sync::Mutex<Vec<u8>>>);
+        impl Write for Captured {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let out = Captured::default();
+        let svc = service_with_gate("T5-A1").with_ndjson_output(out.clone());
+
+        let res = svc
+            .allocate_gate(Request::new(allocate_request("BA-001", &["T5-A1"])))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let text = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let record: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(record["kind"], "allocation");
+        assert_eq!(record["flight_id"], "BA-001");
+        assert_eq!(record["airport"], "LHR");
+        assert_eq!(record["success"], true);
+        assert_eq!(record["gate_id"], "T5-A1");
+        assert_eq!(
+            record["assignment_id"],
+            res.assignment.unwrap().assignment_id.as_str()
+        );
+    }
+
+    #[tokio::test]
+    async fn get_disruptions_filters_the_log_by_type() {
+        let svc = service();
+        svc.report_disruption(Request::new(weather("F1")))
+            .await
+            .unwrap();
+        svc.report_disruption(Request::new(proto::DisruptionEvent {
+            r#type: DisruptionType::Cancellation as i32,
+            ..weather("F2")
+        }))
+        .await
+        .unwrap();
+
+        let query = |types: Vec<DisruptionType>| GetDisruptionsRequest {
+            types: types.into_iter().map(|t| t as i32).collect(),
+            ..Default::default()
+        };
+        let all = svc
+            .get_disruptions(Request::new(query(Vec::new())))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(all.disruptions.len(), 2);
+        let cancelled = svc
+            .get_disruptions(Request::new(query(vec![DisruptionType::Cancellation])))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(cancelled.disruptions.len(), 1);
+        assert_eq!(
+            cancelled.disruptions[0]
+                .affected_flight
+                .as_ref()
+                .unwrap()
+                .flight_id,
+            "F2"
+        );
+        let err = svc
+            .get_disruptions(Request::new(query(vec![DisruptionType::Unknown])))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn feature_rich_gates_round_trip_through_the_proto() {
+        let hour = |h: i64| DateTime::from_timestamp(1_772_323_200 + h * 3600, 0).unwrap();
+        let gate = domain::Gate {
+            gate_id: "T5-B7".into(),
+            terminal: "T5".into(),
+            size: domain::AircraftSize::Large,
+            is_available: false,
+            gate_class: domain::GateClass::International,
+            unavailable_windows: vec![(hour(2), hour(4))],
+            permitted_types: vec!["A380".into()],
+            excluded_types: vec!["B747".into()],
+            mars: true,
+            favours: Some(domain::FlightRole::Departure),
+            baggage_zone: Some("South".into()),
+            min_occupancy_minutes: Some(30),
+            max_occupancy_minutes: Some(360),
+            remote: true,
+            jetbridge_config: domain::JetbridgeConfig::LeftOnly,
+            staffed_shifts: vec![domain::StaffingShift {
+                start: NaiveTime::from_hms_opt(22, 30, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+            }],
+        };
+
+        let wire = to_proto_gate(&gate);
+        assert_eq!(wire.gate_class, GateClass::International as i32);
+        assert_eq!(wire.unavailable_windows.len(), 1);
+        assert_eq!(proto_gate(Some(&wire)), Ok(gate));
+
+        // Unset fields fall back to the domain defaults.
+        let bare = proto_gate(Some(&Gate {
+            gate_id: "T5-B8".into(),
+            size: GateSize::Small as i32,
+            ..Default::default()
+        }))
+        .unwrap();
+        assert_eq!(bare.gate_class, domain::GateClass::Swing);
+        assert_eq!(bare.favours, None);
+        assert_eq!(bare.jetbridge_config, domain::JetbridgeConfig::Both);
+        assert_eq!(bare.max_occupancy_minutes, None);
+
+        // Shift minutes past midnight are refused, however large.
+        for minute in [24 * 60, u32::MAX] {
+            let shifted = Gate {
+                staffed_shifts: vec![DailyWindow {
+                    start_minute: minute,
+                    end_minute: 60,
+                }],
+                ..wire.clone()
+            };
+            assert_eq!(
+                proto_gate(Some(&shifted)),
+                Err(format!(
+                    "Gate T5-B7 shift minute {} is past midnight",
+                    minute
+                ))
+            );
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Flight schedules imported from CSV or JSON, with errors that point at the offending record
+//! and field.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use crate::domain::{Flight, FlightStatus};
+
+/// One problem with an imported record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportError {
+    /// CSV line number (the header is row 1), or 1-based position in a JSON array.
+    pub record: usize,
+    /// Column or key at fault; empty when the record as a whole is malformed.
+    pub field: String,
+    pub reason: String,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "row {}: {}", self.record, self.reason)
+        } else {
+            write!(f, "row {}: {}: {}", self.record, self.field, self.reason)
+        }
+    }
+}
+
+/// How an import treats bad records.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Stop at the first error.
+    #[default]
+    Strict,
+    /// Skip bad records, collecting every error.
+    Lenient,
+}
+
+/// What an import produced: the flights that parsed, and under `ImportMode::Lenient` the errors
+/// for those that did not.
+#[derive(Debug, Clone, Default)]
+pub struct ImportedFlights {
+    pub flights: Vec<Flight>,
+    pub errors: Vec<ImportError>,
+}
+
+/// Import flights from CSV with a header row naming the `Flight` fields (any order).  Required
+/// columns: `flight_id`, `airline`, `origin`, `destination`, `aircraft_type`,
+/// `scheduled_arrival` and `scheduled_departure` (RFC 3339).  Optional: `status`,
+/// `baggage_zone`, `registration`, `passenger_count`, `wingspan_m`, `is_ferry`,
+/// `requires_double_stand` and `codeshare_airlines` (separated by `|`).  Fields are split on
+/// commas; quoting is not supported.  Blank lines are skipped.
+pub fn flights_from_csv(text: &str, mode: ImportMode) -> Result<ImportedFlights, ImportError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty());
+    let Some((_, header)) = lines.next() else {
+        return Ok(ImportedFlights::default());
+    };
+    let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+
+    let mut imported = ImportedFlights::default();
+    for (line, row) in lines {
+        let record = line + 1;
+        let values: Vec<&str> = row.split(',').map(str::trim).collect();
+        let parsed = if values.len() != columns.len() {
+            Err(vec![ImportError {
+                record,
+                field: String::new(),
+                reason: format!("expected {} fields, found {}", columns.len(), values.len()),
+            }])
+        } else {
+            let fields: HashMap<&str, String> = columns
+                .iter()
+                .zip(&values)
+                .filter(|(_, v)| !v.is_empty())
+                .map(|(&c, &v)| (c, v.to_string()))
+                .collect();
+            parse_flight(record, &fields)
+        };
+        collect(&mut imported, parsed, mode)?;
+    }
+    Ok(imported)
+}
+
+/// Import flights from a JSON array of objects keyed by the same fields as
+/// `flights_from_csv`; `codeshare_airlines` may also be an array.
+pub fn flights_from_json(text: &str, mode: ImportMode) -> Result<ImportedFlights, ImportError> {
+    let records: Vec<Value> = serde_json::from_str(text).map_err(|e| ImportError {
+        record: 0,
+        field: String::new(),
+        reason: format!("not a JSON array: {}", e),
+    })?;
+
+    let mut imported = ImportedFlights::default();
+    for (i, value) in records.iter().enumerate() {
+        let record = i + 1;
+        let parsed = match value.as_object() {
+            Some(object) => {
+                let fields: HashMap<&str, String> = object
+                    .iter()
+                    .filter_map(|(k, v)| Some((k.as_str(), json_text(v)?)))
+                    .collect();
+                parse_flight(record, &fields)
+            }
+            None => Err(vec![ImportError {
+                record,
+                field: String::new(),
+                reason: "not a JSON object".to_string(),
+            }]),
+        };
+        collect(&mut imported, parsed, mode)?;
+    }
+    Ok(imported)
+}
+
+fn collect(
+    imported: &mut ImportedFlights,
+    parsed: Result<Flight, Vec<ImportError>>,
+    mode: ImportMode,
+) -> Result<(), ImportError> {
+    match parsed {
+        Ok(flight) => imported.flights.push(flight),
+        Err(mut errors) if mode == ImportMode::Strict => return Err(errors.remove(0)),
+        Err(errors) => imported.errors.extend(errors),
+    }
+    Ok(())
+}
+
+/// A JSON value as the text a CSV cell would hold; `None` for null.
+fn json_text(v: &Value) -> Option<String> {
+    match v {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Array(items) => Some(
+            items
+                .iter()
+                .filter_map(json_text)
+                .collect::<Vec<_>>()
+                .join("|"),
+        ),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Build a flight from one record's fields, reporting every bad field.
+fn parse_flight(record: usize, fields: &HashMap<&str, String>) -> Result<Flight, Vec<ImportError>> {
+    let mut errors = Vec::new();
+    let mut fail = |field: &str, reason: String| {
+        errors.push(ImportError {
+            record,
+            field: field.to_string(),
+            reason,
+        })
+    };
+    let mut text = |field: &str| match fields.get(field) {
+        Some(v) => v.clone(),
+        None => {
+            fail(field, "missing".to_string());
+            String::new()
+        }
+    };
+    let flight_id = text("flight_id");
+    let airline = text("airline");
+    let origin = text("origin");
+    let destination = text("destination");
+    let aircraft_type = text("aircraft_type");
+    let arrival = text("scheduled_arrival");
+    let departure = text("scheduled_departure");
+
+    let mut time = |field: &str, value: &str| {
+        if value.is_empty() {
+            return None;
+        }
+        match DateTime::parse_from_rfc3339(value) {
+            Ok(t) => Some(t.with_timezone(&Utc)),
+            Err(_) => {
+                fail(field, format!("unparseable timestamp '{}'", value));
+                None
+            }
+        }
+    };
+    let scheduled_arrival = time("scheduled_arrival", &arrival);
+    let scheduled_departure = time("scheduled_departure", &departure);
+    if let (Some(arr), Some(dep)) = (scheduled_arrival, scheduled_departure) {
+        if dep <= arr {
+            fail(
+                "scheduled_departure",
+                format!("'{}' is not after scheduled_arrival", departure),
+            );
+        }
+    }
+
+    let status = match fields.get("status") {
+        None => FlightStatus::Scheduled,
+        Some(s) => serde_json::from_value(Value::String(s.clone())).unwrap_or_else(|_| {
+            fail("status", format!("unknown status '{}'", s));
+            FlightStatus::Scheduled
+        }),
+    };
+    let passenger_count = fields.get("passenger_count").and_then(|v| {
+        v.parse()
+            .map_err(|_| fail("passenger_count", format!("not a passenger count '{}'", v)))
+            .ok()
+    });
+    let wingspan_m = fields.get("wingspan_m").and_then(|v| {
+        v.parse().ok().filter(|&w: &f64| w > 0.0).or_else(|| {
+            fail("wingspan_m", format!("not a wingspan in metres '{}'", v));
+            None
+        })
+    });
+    let mut flag = |field: &str| {
+        fields.get(field).is_some_and(|v| {
+            v.parse()
+                .map_err(|_| fail(field, format!("expected true or false, found '{}'", v)))
+                .unwrap_or(false)
+        })
+    };
+    let is_ferry = flag("is_ferry");
+    let requires_double_stand = flag("requires_double_stand");
+
+    match (scheduled_arrival, scheduled_departure) {
+        (Some(scheduled_arrival), Some(scheduled_departure)) if errors.is_empty() => Ok(Flight {
+            flight_id,
+            airline,
+            codeshare_airlines: fields
+                .get("codeshare_airlines")
+                .map(|v| v.split('|').map(|a| a.trim().to_string()).collect())
+                .unwrap_or_default(),
+            origin,
+            destination,
+            aircraft_type,
+            scheduled_arrival,
+            scheduled_departure,
+            status,
+            requires_double_stand,
+            baggage_zone: fields.get("baggage_zone").cloned(),
+            registration: fields.get("registration").cloned(),
+            passenger_count,
+            is_ferry,
+            wingspan_m,
+        }),
+        _ => Err(errors),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "flight_id,airline,origin,destination,aircraft_type,scheduled_arrival,scheduled_departure,passenger_count";
+
+    #[test]
+    fn lenient_import_reports_every_bad_field() {
+        let csv = format!(
+            "{}\n\
+             BA-1,BA,JFK,LHR,B777,2026-03-01T08:00:00Z,2026-03-01T10:00:00Z,300\n\
+             BA-2,BA,JFK,LHR,B777,2026-03-01T08:00:00Z,25:00,300\n\
+             BA-3,BA,,LHR,A320,2026-03-01T10:00:00Z,2026-03-01T09:00:00Z,lots\n\
+             BA-4,BA,CDG\n",
+            HEADER
+        );
+
+        let imported = flights_from_csv(&csv, ImportMode::Lenient).unwrap();
+        assert_eq!(imported.flights.len(), 1);
+        assert_eq!(imported.flights[0].passenger_count, Some(300));
+        let errors: Vec<String> = imported.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            [
+                "row 3: scheduled_departure: unparseable timestamp '25:00'",
+                "row 4: origin: missing",
+                "row 4: scheduled_departure: '2026-03-01T09:00:00Z' is not after scheduled_arrival",
+                "row 4: passenger_count: not a passenger count 'lots'",
+                "row 5: expected 8 fields, found 3",
+            ]
+        );
+
+        let first = flights_from_csv(&csv, ImportMode::Strict).unwrap_err();
+        assert_eq!(first.to_string(), errors[0]);
+    }
+
+    #[test]
+    fn json_records_use_the_same_checks() {
+        let json = r#"[
+            {"flight_id": "VS-1", "airline": "VS", "origin": "JFK", "destination": "LHR",
+             "aircraft_type": "A350", "scheduled_arrival": "2026-03-01T08:00:00Z",
+             "scheduled_departure": "2026-03-01T10:00:00Z", "codeshare_airlines": ["DL"],
+             "is_ferry": false},
+            {"flight_id": "VS-2", "status": "Grounded"}
+        ]"#;
+
+        let imported = flights_from_json(json, ImportMode::Lenient).unwrap();
+        assert_eq!(imported.flights[0].codeshare_airlines, ["DL"]);
+        assert_eq!(imported.errors.len(), 7);
+        assert!(imported
+            .errors
+            .iter()
+            .all(|e| e.record == 2 && !e.field.is_empty()));
+        assert!(flights_from_json("{}", ImportMode::Lenient).is_err());
+    }
+}
@@ -0,0 +1,12 @@
+//! Aviation gate allocation & disruption optimisation engine.
+//!
+//! The binary in `main.rs` wires these modules into the `demo` and `serve` modes; embedders can
+//! depend on the library directly and drive [`engine::AllocationEngine`] without gRPC.
+
+pub mod domain;
+pub mod engine;
+pub mod grpc;
+pub mod import;
+pub mod policy;
+#[cfg(feature = "schema")]
+pub mod schema;
@@ -1,10 +1,8 @@
-mod domain;
-mod engine;
-mod grpc;
-
-use chrono::{TimeZone, Utc};
-use domain::*;
-use engine::AllocationEngine;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use gate_allocation_engine::domain::*;
+use gate_allocation_engine::engine::{validate_registrations, AllocationEngine};
+use gate_allocation_engine::grpc;
+use gate_allocation_engine::policy::AllocationPolicy;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
@@ -17,20 +15,39 @@ const GRPC_LISTEN_ADDR: &str = "[::]:50051";
 
 const MODE_DEMO: &str = "demo";
 const MODE_SERVE: &str = "serve";
+const MODE_SCHEMA: &str = "schema";
+const MODE_BENCH: &str = "bench";
 
-// ── Demo scenario ─────────────────────────────────────────────────────────────
+// ── Policy ───────────────────────────────────────────────────────────────────
 
-/// IATA code of the airport modelled in the demo.
-const DEMO_AIRPORT: &str = "LHR";
+/// Environment variable naming a policy file (TOML, or JSON by extension) to use instead of the
+/// built-in `config/policy.toml`.
+const POLICY_ENV_VAR: &str = "GATE_POLICY";
+
+// ── Outcome output ───────────────────────────────────────────────────────────
 
-/// Airline whose flights are preferentially assigned to Terminal 5 gates.
-const AIRLINE_BRITISH_AIRWAYS: &str = "British Airways";
+/// `serve` flag printing each allocation/disruption outcome to stdout as one JSON line.
+const NDJSON_FLAG: &str = "--ndjson";
 
-/// Preferred gate list for British Airways (home terminal at LHR).
-const BA_PREFERRED_GATES: &[&str] = &["T5-A1", "T5-A2", "T5-B1", "T5-B2", "T5-B3"];
+/// Environment variable enabling the same output as `--ndjson` when set to `1` or `true`.
+const NDJSON_ENV_VAR: &str = "GATE_NDJSON";
 
-/// Preferred gate list for non-BA carriers operating from Terminal 2.
-const OTHER_PREFERRED_GATES: &[&str] = &["T2-A1", "T2-B1", "T2-B2"];
+// ── Bench ────────────────────────────────────────────────────────────────────
+
+/// Gate and flight counts used by `bench` when none are given on the command line.
+const BENCH_DEFAULT_GATES: usize = 200;
+const BENCH_DEFAULT_FLIGHTS: usize = 2_000;
+
+/// Fixed seed so bench runs on different builds allocate the same schedule.
+const BENCH_SEED: u64 = 0x5EED;
+
+/// Aircraft types drawn for bench flights, spanning all three gate sizes.
+const BENCH_AIRCRAFT: &[&str] = &["E190", "ATR72", "A320", "B737", "A321", "B787", "A350"];
+
+// ── Demo scenario ─────────────────────────────────────────────────────────────
+
+/// IATA code of the airport modelled in the demo.
+const DEMO_AIRPORT: &str = "LHR";
 
 /// Flight delayed in the disruption phase of the demo.
 const DEMO_DELAY_FLIGHT: &str = "BA-303";
@@ -41,154 +58,120 @@ const DEMO_CANCEL_FLIGHT: &str = "LH-901";
 /// Gate taken out of service in the disruption phase of the demo.
 const DEMO_FAILED_GATE: &str = "T5-A1";
 
+/// Whether NDJSON outcome output was asked for on the command line or in the environment.
+fn ndjson_enabled() -> bool {
+    std::env::args().skip(2).any(|a| a == NDJSON_FLAG)
+        || std::env::var(NDJSON_ENV_VAR).is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+/// The policy file named by `GATE_POLICY`, or the built-in one.  Exits on an unreadable file.
+fn load_policy() -> AllocationPolicy {
+    match std::env::var(POLICY_ENV_VAR) {
+        Ok(path) => AllocationPolicy::load(path.as_ref()).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }),
+        Err(_) => AllocationPolicy::builtin(),
+    }
+}
+
+fn gate(id: &str, terminal: &str, size: AircraftSize) -> Gate {
+    Gate::builder()
+        .gate_id(id)
+        .terminal(terminal)
+        .size(size)
+        .build()
+        .expect("seed gates are complete")
+}
+
 fn seed_gates() -> Vec<Gate> {
     vec![
-        Gate {
-            gate_id: "T5-A1".into(),
-            terminal: "T5".into(),
-            size: AircraftSize::Large,
-            is_available: true,
-        },
-        Gate {
-            gate_id: "T5-A2".into(),
-            terminal: "T5".into(),
-            size: AircraftSize::Large,
-            is_available: true,
-        },
-        Gate {
-            gate_id: "T5-B1".into(),
-            terminal: "T5".into(),
-            size: AircraftSize::Medium,
-            is_available: true,
-        },
-        Gate {
-            gate_id: "T5-B2".into(),
-            terminal: "T5".into(),
-            size: AircraftSize::Medium,
-            is_available: true,
-        },
-        Gate {
-            gate_id: "T5-B3".into(),
-            terminal: "T5".into(),
-            size: AircraftSize::Medium,
-            is_available: true,
-        },
-        Gate {
-            gate_id: "T5-C1".into(),
-            terminal: "T5".into(),
-            size: AircraftSize::Small,
-            is_available: true,
-        },
-        Gate {
-            gate_id: "T5-C2".into(),
-            terminal: "T5".into(),
-            size: AircraftSize::Small,
-            is_available: true,
-        },
-        Gate {
-            gate_id: "T2-A1".into(),
-            terminal: "T2".into(),
-            size: AircraftSize::Large,
-            is_available: true,
-        },
-        Gate {
-            gate_id: "T2-B1".into(),
-            terminal: "T2".into(),
-            size: AircraftSize::Medium,
-            is_available: true,
-        },
-        Gate {
-            gate_id: "T2-B2".into(),
-            terminal: "T2".into(),
-            size: AircraftSize::Medium,
-            is_available: true,
-        },
+        gate("T5-A1", "T5", AircraftSize::Large),
+        gate("T5-A2", "T5", AircraftSize::Large),
+        gate("T5-B1", "T5", AircraftSize::Medium),
+        gate("T5-B2", "T5", AircraftSize::Medium),
+        gate("T5-B3", "T5", AircraftSize::Medium),
+        gate("T5-C1", "T5", AircraftSize::Small),
+        gate("T5-C2", "T5", AircraftSize::Small),
+        gate("T2-A1", "T2", AircraftSize::Large),
+        gate("T2-B1", "T2", AircraftSize::Medium),
+        gate("T2-B2", "T2", AircraftSize::Medium),
     ]
 }
 
+fn flight(
+    id: &str,
+    airline: &str,
+    origin: &str,
+    aircraft: &str,
+    arr: DateTime<Utc>,
+    dep: DateTime<Utc>,
+) -> Flight {
+    Flight::builder()
+        .flight_id(id)
+        .airline(airline)
+        .origin(origin)
+        .destination("LHR")
+        .aircraft_type(aircraft)
+        .scheduled_arrival(arr)
+        .scheduled_departure(dep)
+        .build()
+        .expect("seed flights are complete")
+}
+
 fn seed_flights() -> Vec<Flight> {
     let d = |h: u32, m: u32| Utc.with_ymd_and_hms(2026, 3, 15, h, m, 0).unwrap();
     vec![
-        Flight {
-            flight_id: "BA-117".into(),
-            airline: "British Airways".into(),
-            origin: "JFK".into(),
-            destination: "LHR".into(),
-            aircraft_type: "B777".into(),
-            scheduled_arrival: d(6, 30),
-            scheduled_departure: d(9, 15),
-            status: FlightStatus::Scheduled,
-        },
-        Flight {
-            flight_id: "BA-303".into(),
-            airline: "British Airways".into(),
-            origin: "CDG".into(),
-            destination: "LHR".into(),
-            aircraft_type: "A320".into(),
-            scheduled_arrival: d(7, 0),
-            scheduled_departure: d(8, 45),
-            status: FlightStatus::Scheduled,
-        },
-        Flight {
-            flight_id: "BA-609".into(),
-            airline: "British Airways".into(),
-            origin: "EDI".into(),
-            destination: "LHR".into(),
-            aircraft_type: "E190".into(),
-            scheduled_arrival: d(7, 15),
-            scheduled_departure: d(8, 30),
-            status: FlightStatus::Scheduled,
-        },
-        Flight {
-            flight_id: "BA-215".into(),
-            airline: "British Airways".into(),
-            origin: "DXB".into(),
-            destination: "LHR".into(),
-            aircraft_type: "A350".into(),
-            scheduled_arrival: d(7, 45),
-            scheduled_departure: d(10, 30),
-            status: FlightStatus::Scheduled,
-        },
-        Flight {
-            flight_id: "BA-456".into(),
-            airline: "British Airways".into(),
-            origin: "MAD".into(),
-            destination: "LHR".into(),
-            aircraft_type: "A320".into(),
-            scheduled_arrival: d(8, 0),
-            scheduled_departure: d(10, 0),
-            status: FlightStatus::Scheduled,
-        },
-        Flight {
-            flight_id: "LH-901".into(),
-            airline: "Lufthansa".into(),
-            origin: "FRA".into(),
-            destination: "LHR".into(),
-            aircraft_type: "A320".into(),
-            scheduled_arrival: d(8, 15),
-            scheduled_departure: d(10, 15),
-            status: FlightStatus::Scheduled,
-        },
-        Flight {
-            flight_id: "AF-1680".into(),
-            airline: "Air France".into(),
-            origin: "CDG".into(),
-            destination: "LHR".into(),
-            aircraft_type: "A320".into(),
-            scheduled_arrival: d(8, 30),
-            scheduled_departure: d(10, 30),
-            status: FlightStatus::Scheduled,
-        },
-        Flight {
-            flight_id: "BA-178".into(),
-            airline: "British Airways".into(),
-            origin: "SIN".into(),
-            destination: "LHR".into(),
-            aircraft_type: "B787".into(),
-            scheduled_arrival: d(9, 0),
-            scheduled_departure: d(12, 0),
-            status: FlightStatus::Scheduled,
-        },
+        flight(
+            "BA-117",
+            "British Airways",
+            "JFK",
+            "B777",
+            d(6, 30),
+            d(9, 15),
+        ),
+        flight(
+            "BA-303",
+            "British Airways",
+            "CDG",
+            "A320",
+            d(7, 0),
+            d(8, 45),
+        ),
+        flight(
+            "BA-609",
+            "British Airways",
+            "EDI",
+            "E190",
+            d(7, 15),
+            d(8, 30),
+        ),
+        flight(
+            "BA-215",
+            "British Airways",
+            "DXB",
+            "A350",
+            d(7, 45),
+            d(10, 30),
+        ),
+        flight(
+            "BA-456",
+            "British Airways",
+            "MAD",
+            "A320",
+            d(8, 0),
+            d(10, 0),
+        ),
+        flight("LH-901", "Lufthansa", "FRA", "A320", d(8, 15), d(10, 15)),
+        flight("AF-1680", "Air France", "CDG", "A320", d(8, 30), d(10, 30)),
+        flight(
+            "BA-178",
+            "British Airways",
+            "SIN",
+            "B787",
+            d(9, 0),
+            d(12, 0),
+        ),
     ]
 }
 
@@ -200,6 +183,8 @@ async fn run_demo() {
     println!("=====================================================================");
 
     let mut engine = AllocationEngine::new();
+    let policy = load_policy();
+    policy.apply(&mut engine);
 
     println!("\n--- Phase 1: Registering Airport Gates ---\n");
     for gate in seed_gates() {
@@ -208,11 +193,14 @@ async fn run_demo() {
     }
 
     println!("\n--- Phase 2: Morning Schedule -- Gate Allocation ---\n");
-    for flight in seed_flights() {
-        let preferred: Vec<String> = match flight.airline.as_str() {
-            AIRLINE_BRITISH_AIRWAYS => BA_PREFERRED_GATES.iter().map(|&s| s.to_string()).collect(),
-            _ => OTHER_PREFERRED_GATES.iter().map(|&s| s.to_string()).collect(),
-        };
+    let flights = seed_flights();
+    if let Err(conflicts) = validate_registrations(&flights) {
+        for c in conflicts {
+            println!("  [WARN] {}", c);
+        }
+    }
+    for flight in flights {
+        let preferred = policy.preferred_for(&flight.airline);
         let r = engine.allocate_gate(&flight, DEMO_AIRPORT, &preferred);
         if r.success {
             let a = r.assignment.as_ref().unwrap();
@@ -241,6 +229,7 @@ async fn run_demo() {
         description: "Fog at CDG".into(),
         reported_at: Utc::now(),
         delay_minutes: 45,
+        airport: None,
     });
     println!("      -> {}", r.summary);
     for a in &r.reassignments {
@@ -256,6 +245,7 @@ async fn run_demo() {
         description: "Hydraulic fault".into(),
         reported_at: Utc::now(),
         delay_minutes: 0,
+        airport: None,
     });
     println!("      -> {}", r.summary);
 
@@ -268,6 +258,7 @@ async fn run_demo() {
         description: DEMO_FAILED_GATE.into(), // gate_id of the failed gate
         reported_at: Utc::now(),
         delay_minutes: 0,
+        airport: None,
     });
     println!("      -> {}", r.summary);
     for a in &r.reassignments {
@@ -291,42 +282,248 @@ async fn run_demo() {
     println!("\n--- Simulation Complete ---\n");
 }
 
-async fn run_server() {
+async fn run_server(ndjson: bool) {
+    let policy = load_policy();
     let engine = Arc::new(Mutex::new(AllocationEngine::new()));
     {
         let mut e = engine.lock().await;
         for g in seed_gates() {
             e.add_gate(g);
         }
+        policy.apply(&mut e);
     }
 
     let addr = GRPC_LISTEN_ADDR.parse().unwrap();
-    println!("\n=====================================================================");
-    println!("  Gate Allocation Engine -- gRPC Server");
-    println!("  Listening on {}", addr);
-    println!("=====================================================================\n");
+    // Stdout carries only NDJSON records when they are enabled.
+    let banner = format!(
+        "\n=====================================================================\n  \
+         Gate Allocation Engine -- gRPC Server\n  Listening on {}\n\
+         =====================================================================\n",
+        addr
+    );
+    if ndjson {
+        eprintln!("{}", banner);
+    } else {
+        println!("{}", banner);
+    }
 
-    grpc::start_grpc_server(engine, addr)
+    grpc::start_grpc_server(engine, policy, addr, ndjson)
         .await
         .expect("gRPC server failed");
 }
 
+/// Print the JSON Schema of the domain types to stdout.
+fn print_schema() {
+    #[cfg(feature = "schema")]
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&gate_allocation_engine::schema::domain_schemas()).unwrap()
+    );
+    #[cfg(not(feature = "schema"))]
+    {
+        eprintln!("The '{}' mode needs the 'schema' feature.", MODE_SCHEMA);
+        std::process::exit(1);
+    }
+}
+
+/// Xorshift generator: repeatable bench inputs without an extra dependency.
+struct BenchRng(u64);
+
+impl BenchRng {
+    fn below(&mut self, n: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % n
+    }
+}
+
+/// `gates` gates over four terminals and `flights` flights arriving between 05:00 and 22:00 with
+/// 45 minute to 3 hour turnarounds, all on one day.
+fn bench_schedule(gates: usize, flights: usize, seed: u64) -> (Vec<Gate>, Vec<Flight>) {
+    let mut rng = BenchRng(seed.max(1));
+    let sizes = [
+        AircraftSize::Small,
+        AircraftSize::Medium,
+        AircraftSize::Large,
+    ];
+    let gates = (0..gates)
+        .map(|i| {
+            let terminal = format!("T{}", 1 + i % 4);
+            let size = sizes[rng.below(3) as usize];
+            gate(&format!("{}-{}", terminal, i), &terminal, size)
+        })
+        .collect();
+    let day = Utc.with_ymd_and_hms(2026, 3, 15, 5, 0, 0).unwrap();
+    let flights = (0..flights)
+        .map(|i| {
+            let aircraft = BENCH_AIRCRAFT[rng.below(BENCH_AIRCRAFT.len() as u64) as usize];
+            let arr = day + Duration::minutes(rng.below(17 * 60) as i64);
+            let dep = arr + Duration::minutes(45 + rng.below(136) as i64);
+            flight(&format!("BN-{}", i), "Bench", "JFK", aircraft, arr, dep)
+        })
+        .collect();
+    (gates, flights)
+}
+
+/// Outcome of one bench run.
+struct BenchReport {
+    flights: usize,
+    placed: usize,
+    elapsed: std::time::Duration,
+}
+
+/// Allocate every flight one at a time, in arrival order, on a fresh engine.
+fn run_bench_with(gates: Vec<Gate>, mut flights: Vec<Flight>) -> (AllocationEngine, BenchReport) {
+    let mut engine = AllocationEngine::new();
+    for g in gates {
+        engine.add_gate(g);
+    }
+    flights.sort_by_key(|f| f.scheduled_arrival);
+    let started = std::time::Instant::now();
+    let placed = flights
+        .iter()
+        .filter(|f| engine.allocate_gate(f, DEMO_AIRPORT, &[]).success)
+        .count();
+    let report = BenchReport {
+        flights: flights.len(),
+        placed,
+        elapsed: started.elapsed(),
+    };
+    (engine, report)
+}
+
+/// Load generator for reproducing performance issues: `bench [gates] [flights]`.
+fn run_bench() {
+    let arg = |n: usize, default: usize| {
+        std::env::args()
+            .nth(n)
+            .map_or(Ok(default), |a| a.parse::<usize>())
+            .unwrap_or_else(|e| {
+                eprintln!("Invalid bench size: {}", e);
+                std::process::exit(1);
+            })
+    };
+    let (gates, flights) = (arg(2, BENCH_DEFAULT_GATES), arg(3, BENCH_DEFAULT_FLIGHTS));
+    let (gate_list, flight_list) = bench_schedule(gates, flights, BENCH_SEED);
+    let (engine, report) = run_bench_with(gate_list, flight_list);
+
+    let secs = report.elapsed.as_secs_f64();
+    println!("\n  Bench: {} gates, {} flights", gates, report.flights);
+    println!("  Elapsed:    {:.3}s", secs);
+    println!(
+        "  Throughput: {:.0} allocations/sec",
+        report.flights as f64 / secs.max(f64::EPSILON)
+    );
+    println!(
+        "  Placed:     {}/{} ({:.1}%)",
+        report.placed,
+        report.flights,
+        100.0 * report.placed as f64 / report.flights.max(1) as f64
+    );
+    println!("  Stats:      {}\n", engine.stats());
+}
+
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt()
+    let mode = std::env::args().nth(1).unwrap_or_else(|| MODE_DEMO.into());
+    // Per-allocation logging would dominate a bench run.
+    let default_filter = if mode == MODE_BENCH { "warn" } else { "info" };
+    let ndjson = mode == MODE_SERVE && ndjson_enabled();
+    let logs = tracing_subscriber::fmt()
         .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()),
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| default_filter.into()),
         )
-        .with_target(false)
-        .init();
+        .with_target(false);
+    // Keep tracing off stdout so NDJSON consumers see only records.
+    if ndjson {
+        logs.with_writer(std::io::stderr).init();
+    } else {
+        logs.init();
+    }
 
-    let mode = std::env::args().nth(1).unwrap_or_else(|| MODE_DEMO.into());
     match mode.as_str() {
         MODE_DEMO => run_demo().await,
-        MODE_SERVE => run_server().await,
+        MODE_SERVE => run_server(ndjson).await,
+        MODE_SCHEMA => print_schema(),
+        MODE_BENCH => run_bench(),
         other => {
-            eprintln!("Unknown mode: '{}'. Use '{}' or '{}'.", other, MODE_DEMO, MODE_SERVE);
+            eprintln!(
+                "Unknown mode: '{}'. Use '{}', '{}', '{}' or '{}'.",
+                other, MODE_DEMO, MODE_SERVE, MODE_SCHEMA, MODE_BENCH
+            );
             std::process::exit(1);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gate_allocation_engine::engine::InventoryCount;
+    use std::collections::HashSet;
+
+    #[test]
+    fn seeded_inventory_counts() {
+        let mut engine = AllocationEngine::new();
+        for g in seed_gates() {
+            engine.add_gate(g);
+        }
+        let inv = engine.inventory();
+        let t5 = |size| inv.by_terminal_size[&("T5".to_string(), size)];
+        assert_eq!(t5(AircraftSize::Large).total, 2);
+        assert_eq!(t5(AircraftSize::Medium).total, 3);
+        assert_eq!(t5(AircraftSize::Small).total, 2);
+        assert_eq!(
+            inv.by_terminal["T2"],
+            InventoryCount {
+                total: 3,
+                available: 3
+            }
+        );
+        assert_eq!(inv.overall.total, 10);
+    }
+
+    #[test]
+    fn large_capable_t5_gates() {
+        let mut engine = AllocationEngine::new();
+        for g in seed_gates() {
+            engine.add_gate(g);
+        }
+        let ids = |gates: Vec<&Gate>| -> Vec<String> {
+            gates.into_iter().map(|g| g.gate_id.clone()).collect()
+        };
+
+        let none = HashSet::new();
+        let large = engine.compatible_gates(AircraftSize::Large, Some("T5"), &none);
+        assert_eq!(ids(large), ["T5-A1", "T5-A2"]);
+        let anywhere = engine.compatible_gates(AircraftSize::Large, None, &none);
+        assert_eq!(anywhere.len(), 3);
+        let bridged = HashSet::from([GateFeature::JetBridge, GateFeature::International]);
+        assert_eq!(
+            engine
+                .compatible_gates(AircraftSize::Medium, Some("T2"), &bridged)
+                .len(),
+            3
+        );
+        let mars = HashSet::from([GateFeature::Mars]);
+        assert!(engine
+            .compatible_gates(AircraftSize::Small, None, &mars)
+            .is_empty());
+    }
+
+    #[test]
+    fn small_bench_places_most_flights() {
+        let (gates, flights) = bench_schedule(24, 80, BENCH_SEED);
+        assert_eq!((gates.len(), flights.len()), (24, 80));
+        let (engine, report) = run_bench_with(gates, flights);
+        assert_eq!(report.flights, 80);
+        assert!(
+            report.placed * 10 >= report.flights * 9,
+            "placed {}",
+            report.placed
+        );
+        assert_eq!(engine.assignments.len(), report.placed);
+    }
+}
@@ -0,0 +1,108 @@
+//! Airline gate preferences and leases loaded from a TOML or JSON file instead of being
+//! hard-coded in the binary.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::GateLease;
+use crate::engine::AllocationEngine;
+
+/// Policy shipped with the binary, used when no file is configured.
+pub const DEFAULT_POLICY_TOML: &str = include_str!("../config/policy.toml");
+
+/// Which gates each airline asks for and which terminals are leased to whom.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AllocationPolicy {
+    /// Airline -> preferred gates, best first.
+    #[serde(default)]
+    pub preferred_gates: BTreeMap<String, Vec<String>>,
+    /// Preferred gates for airlines without their own entry.
+    #[serde(default)]
+    pub default_preferred_gates: Vec<String>,
+    #[serde(default)]
+    pub leases: Vec<GateLease>,
+}
+
+impl AllocationPolicy {
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| format!("Invalid policy TOML: {}", e))
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        serde_json::from_str(text).map_err(|e| format!("Invalid policy JSON: {}", e))
+    }
+
+    /// Read a policy file; `.json` files are parsed as JSON, anything else as TOML.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read policy {}: {}", path.display(), e))?;
+        if path.extension().is_some_and(|ext| ext == "json") {
+            Self::from_json(&text)
+        } else {
+            Self::from_toml(&text)
+        }
+    }
+
+    /// The built-in policy (`config/policy.toml`).
+    pub fn builtin() -> Self {
+        Self::from_toml(DEFAULT_POLICY_TOML).expect("built-in policy is valid TOML")
+    }
+
+    /// Preferred gates for `airline`, falling back to the default list.
+    pub fn preferred_for(&self, airline: &str) -> Vec<String> {
+        self.preferred_gates
+            .get(airline)
+            .unwrap_or(&self.default_preferred_gates)
+            .clone()
+    }
+
+    /// Register the policy's leases with `engine`.
+    pub fn apply(&self, engine: &mut AllocationEngine) {
+        for lease in &self.leases {
+            engine.add_lease(lease.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preferences_and_leases_come_from_policy_file() {
+        let path = std::env::temp_dir().join(format!("policy-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+default_preferred_gates = ["T3-A1"]
+
+[preferred_gates]
+"Virgin Atlantic" = ["T3-B1", "T3-B2"]
+
+[[leases]]
+airline = "Virgin Atlantic"
+terminal = "T3"
+hours = ["06:00:00", "14:00:00"]
+"#,
+        )
+        .unwrap();
+        let policy = AllocationPolicy::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(policy.preferred_for("Virgin Atlantic"), ["T3-B1", "T3-B2"]);
+        assert_eq!(policy.preferred_for("Lufthansa"), ["T3-A1"]);
+        let mut engine = AllocationEngine::new();
+        policy.apply(&mut engine);
+        assert_eq!(engine.leases.len(), 1);
+        assert!(engine.leases[0].hours.is_some());
+
+        let json = AllocationPolicy::from_json(&serde_json::to_string(&policy).unwrap());
+        assert_eq!(json.unwrap(), policy);
+        assert_eq!(
+            AllocationPolicy::builtin().preferred_for("British Airways")[0],
+            "T5-A1"
+        );
+    }
+}
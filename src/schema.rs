@@ -0,0 +1,48 @@
+//! JSON Schema for the serde domain types, for integrators exchanging them as JSON.
+//! Only built with the `schema` feature.
+
+use schemars::schema_for;
+use serde_json::{json, Value};
+
+use crate::domain::{DisruptionEvent, Flight, Gate, GateAssignment};
+
+/// Schemas of the exchanged domain types, keyed by type name.
+pub fn domain_schemas() -> Value {
+    json!({
+        "Flight": schema_for!(Flight),
+        "Gate": schema_for!(Gate),
+        "GateAssignment": schema_for!(GateAssignment),
+        "DisruptionEvent": schema_for!(DisruptionEvent),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flight_schema_lists_required_fields() {
+        let schemas = domain_schemas();
+        let required: Vec<&str> = schemas["Flight"]["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap())
+            .collect();
+        for field in [
+            "flight_id",
+            "airline",
+            "origin",
+            "destination",
+            "aircraft_type",
+            "scheduled_arrival",
+            "scheduled_departure",
+            "status",
+        ] {
+            assert!(required.contains(&field), "{} not required", field);
+        }
+        // Fields with serde defaults stay optional.
+        assert!(!required.contains(&"codeshare_airlines"));
+        assert!(schemas["Gate"]["properties"]["gate_id"].is_object());
+    }
+}